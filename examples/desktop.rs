@@ -0,0 +1,141 @@
+// Native window running the same World as the browser demo, for profiling and
+// debugging outside of wasm:
+//
+//   cargo run --release --example desktop --features desktop
+//
+// Keys:
+//   Space        pause / resume          S      single step while paused
+//   R            restart                 M      toggle the circle mask cache
+//   1 / 2 / 3    draw order: insertion, by radius, by y
+//   Up / Down    split ratio +/- 0.05 (restarts)
+//   Left / Right max balls / 2, * 2 (restarts)
+//   Esc          quit
+
+use std::time::{Duration, Instant};
+
+use bouncing_balls::{DrawOrder, World};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+
+const WIDTH: usize = 960;
+const HEIGHT: usize = 640;
+
+struct Settings {
+    max_balls: usize,
+    split_ratio: f32,
+    draw_order: DrawOrder,
+    mask_cache: bool,
+}
+
+impl Settings {
+    fn world(&self) -> World {
+        let mut world = World::try_new(
+            WIDTH as f32,
+            HEIGHT as f32,
+            self.max_balls,
+            self.split_ratio,
+        )
+        .expect("settings are kept in range");
+        world.set_draw_order(self.draw_order);
+        world.set_mask_cache(self.mask_cache);
+        world
+    }
+}
+
+fn main() {
+    let mut window = Window::new("bouncing_balls", WIDTH, HEIGHT, WindowOptions::default())
+        .unwrap_or_else(|error| panic!("cannot open a window: {error}"));
+    window.set_target_fps(60);
+
+    let mut settings = Settings {
+        max_balls: 100_000,
+        split_ratio: 0.8,
+        draw_order: DrawOrder::Insertion,
+        mask_cache: true,
+    };
+    let mut world = settings.world();
+    let mut rgba = vec![0u8; WIDTH * HEIGHT * 4];
+    let mut pixels = vec![0u32; WIDTH * HEIGHT];
+    let mut paused = false;
+
+    let mut update_time = Duration::ZERO;
+    let mut render_time = Duration::ZERO;
+    let mut frames = 0;
+    let mut last_title = Instant::now();
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let mut step = !paused;
+        let mut restart = false;
+        for key in window.get_keys_pressed(KeyRepeat::No) {
+            match key {
+                Key::Space => paused = !paused,
+                Key::S => step = true,
+                Key::R => restart = true,
+                Key::M => {
+                    settings.mask_cache = !settings.mask_cache;
+                    world.set_mask_cache(settings.mask_cache);
+                }
+                Key::Key1 | Key::Key2 | Key::Key3 => {
+                    settings.draw_order = match key {
+                        Key::Key1 => DrawOrder::Insertion,
+                        Key::Key2 => DrawOrder::ByRadiusDesc,
+                        _ => DrawOrder::ByY,
+                    };
+                    world.set_draw_order(settings.draw_order);
+                }
+                Key::Up | Key::Down => {
+                    let delta = if key == Key::Up { 0.05 } else { -0.05 };
+                    settings.split_ratio = (settings.split_ratio + delta).clamp(0.05, 0.95);
+                    restart = true;
+                }
+                Key::Left => {
+                    settings.max_balls = (settings.max_balls / 2).max(1);
+                    restart = true;
+                }
+                Key::Right => {
+                    settings.max_balls = settings.max_balls.saturating_mul(2);
+                    restart = true;
+                }
+                _ => {}
+            }
+        }
+        if restart {
+            world = settings.world();
+        }
+
+        let start = Instant::now();
+        if step {
+            world.update();
+        }
+        let rendered = Instant::now();
+        world
+            .render_to_buffer(&mut rgba, WIDTH, HEIGHT, None)
+            .expect("buffer matches the window size");
+        for (pixel, rgba) in pixels.iter_mut().zip(rgba.chunks_exact(4)) {
+            *pixel = u32::from_be_bytes([0, rgba[0], rgba[1], rgba[2]]);
+        }
+        update_time += rendered - start;
+        render_time += rendered.elapsed();
+        frames += 1;
+
+        window
+            .update_with_buffer(&pixels, WIDTH, HEIGHT)
+            .unwrap_or_else(|error| panic!("cannot present the frame: {error}"));
+
+        if last_title.elapsed() >= Duration::from_millis(500) {
+            let per_frame = |total: Duration| total.as_secs_f64() * 1000.0 / frames as f64;
+            window.set_title(&format!(
+                "bouncing_balls | {} balls | max {} | split {:.2} | update {:.2} ms | render {:.2} ms{}",
+                world.live_count(),
+                settings.max_balls,
+                settings.split_ratio,
+                per_frame(update_time),
+                per_frame(render_time),
+                if paused { " | paused" } else { "" }
+            ));
+            update_time = Duration::ZERO;
+            render_time = Duration::ZERO;
+            frames = 0;
+            last_title = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,101 @@
+#ifndef BOUNCING_BALLS_H
+#define BOUNCING_BALLS_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define SANITIZED_POSITION 1
+
+#define SANITIZED_VELOCITY 2
+
+#define SANITIZED_RADIUS 4
+
+#define WALL_LEFT 1
+
+#define WALL_RIGHT 2
+
+#define WALL_TOP 4
+
+#define WALL_BOTTOM 8
+
+typedef struct World World;
+
+typedef struct Ball {
+  float x;
+  float y;
+  float vx;
+  float vy;
+  float radius;
+  uint32_t color;
+  uint32_t just_split;
+  uint32_t tag;
+  uint8_t layer;
+  uint32_t alive;
+  uint32_t born_frame;
+  uint32_t generation;
+  float spin;
+  float temperature;
+  float restitution;
+  float friction;
+} Ball;
+
+// Create a world, or return NULL if the configuration is invalid.
+struct World *world_new(float width, float height, size_t max_balls, float split_ratio);
+
+// Create a deterministic world whose RNG starts from `seed`, or NULL if the configuration is invalid.
+struct World *world_new_seeded(float width,
+                               float height,
+                               size_t max_balls,
+                               float split_ratio,
+                               uint32_t seed);
+
+// Release a world created by world_new. NULL is ignored.
+//
+// # Safety
+// `world` must be NULL or a pointer returned by world_new / world_new_seeded
+// that has not been freed yet.
+void world_free(struct World *world);
+
+// Advance the simulation by one frame.
+//
+// # Safety
+// `world` must be a live pointer returned by world_new.
+void world_update(struct World *world);
+
+// Pointer to the ball slots (world_balls_len of them, free slots have alive == 0).
+// Valid until the next call that mutates the world.
+//
+// # Safety
+// `world` must be a live pointer returned by world_new.
+const struct Ball *world_balls_ptr(const struct World *world);
+
+// Number of slots behind world_balls_ptr, including free ones.
+//
+// # Safety
+// `world` must be a live pointer returned by world_new.
+size_t world_balls_len(const struct World *world);
+
+// Number of live balls.
+//
+// # Safety
+// `world` must be a live pointer returned by world_new.
+size_t world_live_count(const struct World *world);
+
+// Render into a tightly packed RGBA8 buffer of `len` bytes (width * height * 4).
+// Returns 0 on success and -1 if a pointer is NULL or the buffer size does not match.
+//
+// # Safety
+// `world` must be a live pointer returned by world_new and `buffer` must be
+// valid for writes of `len` bytes.
+int32_t world_render(const struct World *world,
+                     uint8_t *buffer,
+                     size_t len,
+                     size_t width,
+                     size_t height);
+
+#endif  /* BOUNCING_BALLS_H */
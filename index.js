@@ -238,7 +238,7 @@ async function run() {
             timeUpdateElem.innerText = (t1 - t0).toFixed(2);
             timeRenderElem.innerText = (t2 - t1).toFixed(2);
 
-            ballCountElem.innerText = backend.get_balls_len();
+            ballCountElem.innerText = backend.live_count();
         } else if (currentBackendType === 'js') {
             // Pure JS path
             const t0 = performance.now();
@@ -0,0 +1,115 @@
+// What every step loop (update()'s f32 step, Precision::F64, fixed point and
+// the profiled passes) does with a ball once it has moved and bounced off
+// the walls: the split, the wall effects (squash, shake, flashes, heat map),
+// restitution bookkeeping and corner-trap nudges, in that order. The loops
+// only differ in how they move balls, so this lives in one place and they
+// can't drift apart: the same scene gives the same effects and the same
+// random draws whichever loop runs it.
+
+use crate::corner_trap::CornerTrap;
+use crate::energy::Ledger;
+use crate::events::Event;
+use crate::flash::Flashes;
+use crate::heatmap::WallHeat;
+use crate::host_rng::WorldRng;
+use crate::shake::Shake;
+use crate::squash::Squash;
+use crate::{sim, Ball, SplitKinematics, World};
+
+// The parts of a World the hook writes to, borrowed apart from its balls
+pub(crate) struct AfterBounce<'a> {
+    pub events: &'a mut Vec<Event>,
+    squash: &'a mut Squash,
+    shake: &'a mut Shake,
+    flashes: &'a mut Flashes,
+    wall_heat: &'a mut WallHeat,
+    energy: &'a mut Ledger,
+    corner_trap: &'a mut CornerTrap,
+    rng: &'a mut WorldRng,
+    config: sim::SimConfig,
+    split_ratio: f32,
+    kinematics: SplitKinematics,
+    stamp: u32,
+    capacity: usize,
+    // Children split off so far and how many splits max_balls refused
+    pub children: Vec<Ball>,
+    pub denied: usize,
+}
+
+impl AfterBounce<'_> {
+    // Ball `id` moved and hit `hits` this step; `was_just_split` is its
+    // just_split flag from before the step
+    pub(crate) fn run(
+        &mut self,
+        id: usize,
+        ball: &mut Ball,
+        hits: sim::WallHits,
+        was_just_split: bool,
+    ) {
+        let (config, stamp) = (&self.config, self.stamp);
+        let room = self.children.len() < self.capacity;
+        self.rng.key_ball(id, ball);
+        let split = sim::split_ball(ball, config, hits, was_just_split, room, self.rng);
+        self.squash.record(id, stamp, ball, hits);
+        self.shake.record_wall(stamp, ball, hits);
+        self.flashes.record(stamp, ball, hits);
+        self.wall_heat.record(ball, config, hits);
+        self.energy.record_restitution(hits.lost);
+        self.corner_trap
+            .record(id, stamp, ball, hits, self.rng, self.events);
+        self.rng.record_bounce(id, ball, hits);
+        match split {
+            sim::Split::Child(child) => {
+                self.energy
+                    .record_split(ball, &child, self.split_ratio, self.kinematics);
+                self.children.push(child);
+            }
+            sim::Split::Denied => self.denied += 1,
+            sim::Split::None => {}
+        }
+    }
+}
+
+impl World {
+    // The balls, their modified stamps and the hook for one (sub-)step
+    pub(crate) fn after_bounce(
+        &mut self,
+        stamp: u32,
+    ) -> (&mut [Ball], &mut [u32], AfterBounce<'_>) {
+        let hook = AfterBounce {
+            config: self.sim_config(),
+            // Every live ball's just_split is reset as the step reaches it
+            capacity: self.split_capacity(self.live_count()),
+            split_ratio: self.split_ratio,
+            kinematics: self.split.kinematics,
+            stamp,
+            events: &mut self.events,
+            squash: &mut self.squash,
+            shake: &mut self.shake,
+            flashes: &mut self.flashes,
+            wall_heat: &mut self.wall_heat,
+            energy: &mut self.energy,
+            corner_trap: &mut self.corner_trap,
+            rng: &mut self.rng,
+            children: Vec::new(),
+            denied: 0,
+        };
+        (&mut self.balls, &mut self.modified, hook)
+    }
+
+    // Add the children a step's hook collected (see capacity.rs for a full world)
+    pub(crate) fn insert_children(
+        &mut self,
+        stamp: u32,
+        children: Vec<Ball>,
+        denied: usize,
+    ) -> Vec<u32> {
+        self.log_denied(stamp, denied);
+        self.make_room(children.len());
+        self.count_splits(children.len());
+        children
+            .into_iter()
+            .map(|child| self.insert_child(child, stamp))
+            .collect()
+    }
+}
@@ -0,0 +1,93 @@
+// Output alpha. Frames are opaque by default: every pixel starts as the flat
+// clear color (or the background image over it) with alpha 255. With a
+// transparent background they start fully transparent instead, so hosts can
+// composite the balls over their own page or scene, and translucent drawing
+// (trails, fades, water, Voronoi shading...) leaves partly transparent pixels.
+//
+// set_output_alpha() picks how those come out: Straight (the default, what
+// ImageData and most image formats expect) or Premultiplied (colors already
+// scaled by alpha, for WebGL uploads with premultipliedAlpha and compositors
+// that want it). Opaque pixels are the same either way. The rasterizer works
+// premultiplied throughout, which keeps blending over transparent pixels a
+// plain mix; for Straight output the finished pixels are divided by their
+// alpha at the end. Thumbnails and the sprite atlas follow the setting too.
+
+use wasm_bindgen::prelude::*;
+
+use crate::background::CLEAR_COLOR;
+use crate::render::Clip;
+use crate::srgb::rgb;
+use crate::World;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputAlpha {
+    #[default]
+    Straight = 0,
+    Premultiplied = 1,
+}
+
+// What every pixel is cleared to
+pub(crate) fn clear_pixel(transparent: bool) -> [u8; 4] {
+    if transparent {
+        [0; 4]
+    } else {
+        let [r, g, b] = rgb(CLEAR_COLOR);
+        [r, g, b, 255]
+    }
+}
+
+// Turn premultiplied RGBA pixels into straight ones
+pub(crate) fn unpremultiply(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha == 255 {
+            continue;
+        }
+        for channel in &mut pixel[..3] {
+            *channel = match alpha {
+                0 => 0,
+                _ => ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8,
+            };
+        }
+    }
+}
+
+impl World {
+    // Whether finished frames need unpremultiply()
+    pub(crate) fn straight_output(&self) -> bool {
+        self.render.transparent && self.render.output_alpha == OutputAlpha::Straight
+    }
+
+    pub(crate) fn finish_output(&self, buffer: &mut [u8], stride: usize, clip: Clip) {
+        if !self.straight_output() {
+            return;
+        }
+        for py in clip.y0..clip.y1 {
+            let row = py * stride;
+            unpremultiply(&mut buffer[row + clip.x0 * 4..row + clip.x1 * 4]);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Alpha convention of rendered pixels (see alpha.rs)
+    pub fn set_output_alpha(&mut self, mode: OutputAlpha) {
+        self.render.output_alpha = mode;
+    }
+
+    pub fn output_alpha(&self) -> OutputAlpha {
+        self.render.output_alpha
+    }
+
+    // Clear frames to transparent instead of the dark background color; a
+    // background image is still drawn, with its own alpha
+    pub fn set_transparent_background(&mut self, enabled: bool) {
+        self.render.transparent = enabled;
+    }
+
+    pub fn transparent_background(&self) -> bool {
+        self.render.transparent
+    }
+}
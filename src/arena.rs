@@ -0,0 +1,218 @@
+// The arena's walls. All four are solid by default; an open wall lets balls
+// through, and a ball that has completely left the arena is removed (with an
+// Escaped event), which makes open walls work as drains.
+//
+// Walls are invisible edges unless given a thickness: then each closed wall
+// is drawn as a bar that wide along its side, and balls bounce off the bar's
+// inner face instead of the arena's edge.
+//
+// The corner between two closed walls can be rounded (ArenaShape::Rounded,
+// or Capsule for a stadium whose short sides are half circles). Balls there
+// bounce off the arc along its true normal, which ends the endless corner
+// ping-pong square corners produce. The area outside the arcs is drawn in
+// the wall color, visible walls or not.
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::{self, Event, EventKind};
+use crate::render::Clip;
+use crate::{sim, Ball, World};
+
+pub(crate) const DEFAULT_WALL_COLOR: u32 = 0x606060;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArenaShape {
+    #[default]
+    Rectangle = 0, // Square corners
+    Rounded = 1, // Corners rounded with the given radius
+    Capsule = 2, // Stadium: the short sides are half circles
+}
+
+#[wasm_bindgen]
+impl World {
+    // WALL_* mask of the walls balls pass through (0 = closed box)
+    pub fn set_open_walls(&mut self, mask: u32) {
+        self.open_walls =
+            mask & (sim::WALL_LEFT | sim::WALL_RIGHT | sim::WALL_TOP | sim::WALL_BOTTOM);
+    }
+
+    pub fn open_walls(&self) -> u32 {
+        self.open_walls
+    }
+
+    // Draw the closed walls as `thickness` pixel bars in `color` (0xRRGGBB)
+    // and bounce balls off their inner face. 0 (the default) keeps them
+    // invisible at the arena's edge. At most a quarter of the shorter side
+    // is used.
+    pub fn set_wall_thickness(&mut self, thickness: f32, color: u32) {
+        if thickness.is_finite() {
+            self.wall_thickness = thickness.max(0.0);
+            self.wall_color = color & 0xFF_FFFF;
+        }
+    }
+
+    pub fn wall_thickness(&self) -> f32 {
+        self.wall_thickness
+    }
+
+    pub fn wall_color(&self) -> u32 {
+        self.wall_color
+    }
+
+    // Round the arena's corners. `corner_radius` is only used by Rounded and
+    // is capped at half the shorter side inside the walls.
+    pub fn set_arena_shape(&mut self, shape: ArenaShape, corner_radius: f32) {
+        if corner_radius.is_finite() {
+            self.arena_shape = shape;
+            self.corner_radius = corner_radius.max(0.0);
+        }
+    }
+
+    pub fn arena_shape(&self) -> ArenaShape {
+        self.arena_shape
+    }
+
+    // Radius of the rounded corners in effect (0 for a Rectangle)
+    pub fn arena_corner_radius(&self) -> f32 {
+        let inset = self.wall_inset();
+        let most = (self.width - 2.0 * inset).min(self.height - 2.0 * inset) * 0.5;
+        match self.arena_shape {
+            ArenaShape::Rectangle => 0.0,
+            ArenaShape::Rounded => self.corner_radius.min(most),
+            ArenaShape::Capsule => most,
+        }
+    }
+}
+
+impl World {
+    // How far inside the arena's edge closed walls stand
+    pub(crate) fn wall_inset(&self) -> f32 {
+        self.wall_thickness.min(self.width.min(self.height) * 0.25)
+    }
+
+    pub(crate) fn remove_escaped(&mut self, stamp: u32) {
+        if self.open_walls == 0 {
+            return;
+        }
+        let config = self.sim_config();
+        for id in 0..self.balls.len() {
+            let ball = self.balls[id];
+            if ball.alive == 0 {
+                continue;
+            }
+            let wall = sim::escaped(&ball, &config);
+            if wall == 0 {
+                continue;
+            }
+            self.bins.record_exit(&ball, wall, config.width);
+            self.free_slot(id as u32);
+            events::push_event(
+                &mut self.events,
+                Event {
+                    kind: EventKind::Escaped,
+                    id: id as u32,
+                    frame: stamp,
+                    x: ball.x,
+                    y: ball.y,
+                    value: wall as f32,
+                },
+            );
+        }
+    }
+}
+
+// A crowd or an obstacle can push a ball through a closed wall: put its
+// center back inside and let the next step bounce it
+pub(crate) fn keep_inside(ball: &mut Ball, config: &sim::SimConfig) {
+    let closed = |wall: u32| config.open_walls & wall == 0;
+    let inset = config.wall_inset;
+    if closed(sim::WALL_LEFT) {
+        ball.x = ball.x.max(inset);
+    }
+    if closed(sim::WALL_RIGHT) {
+        ball.x = ball.x.min(config.width - inset);
+    }
+    if closed(sim::WALL_TOP) {
+        ball.y = ball.y.max(inset);
+    }
+    if closed(sim::WALL_BOTTOM) {
+        ball.y = ball.y.min(config.height - inset);
+    }
+    if let Some((cx, cy)) = sim::corner_center(ball.x, ball.y, config) {
+        let (dx, dy) = (ball.x - cx, ball.y - cy);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance > config.corner_radius {
+            let scale = config.corner_radius / distance;
+            ball.x = cx + dx * scale;
+            ball.y = cy + dy * scale;
+        }
+    }
+}
+
+// Paint the visible wall bars and the area outside rounded corners into the
+// band. `buffer` starts at row `clip.y0`.
+pub(crate) fn fill_walls(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    config: &sim::SimConfig,
+    color: u32,
+) {
+    let (width, height) = (config.width, config.height);
+    let (inset, radius) = (config.wall_inset, config.corner_radius);
+    let closed = |wall: u32| config.open_walls & wall == 0;
+    let pixel = [
+        ((color >> 16) & 0xFF) as u8,
+        ((color >> 8) & 0xFF) as u8,
+        (color & 0xFF) as u8,
+        255,
+    ];
+    // First and last + 1 pixel whose center is in [low, high), within the clip
+    let span = |low: f32, high: f32, min: usize, max: usize| {
+        let pixel = |edge: f32| (edge - 0.5).ceil().clamp(min as f32, max as f32) as usize;
+        pixel(low)..pixel(high)
+    };
+
+    let bars = [
+        (sim::WALL_LEFT, (0.0, 0.0, inset, height)),
+        (sim::WALL_RIGHT, (width - inset, 0.0, width, height)),
+        (sim::WALL_TOP, (0.0, 0.0, width, inset)),
+        (sim::WALL_BOTTOM, (0.0, height - inset, width, height)),
+    ];
+    for (_, (x0, y0, x1, y1)) in bars.iter().filter(|(wall, _)| inset > 0.0 && closed(*wall)) {
+        let columns = span(*x0, *x1, clip.x0, clip.x1);
+        for py in span(*y0, *y1, clip.y0, clip.y1) {
+            let row = (py - clip.y0) * stride;
+            for chunk in buffer[row + columns.start * 4..row + columns.end * 4].chunks_exact_mut(4)
+            {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    if radius <= 0.0 {
+        return;
+    }
+    let corners = [
+        (inset, inset),
+        (width - inset - radius, inset),
+        (inset, height - inset - radius),
+        (width - inset - radius, height - inset - radius),
+    ];
+    for (x0, y0) in corners {
+        for py in span(y0, y0 + radius, clip.y0, clip.y1) {
+            let row = (py - clip.y0) * stride;
+            for px in span(x0, x0 + radius, clip.x0, clip.x1) {
+                let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+                let Some((cx, cy)) = sim::corner_center(x, y, config) else {
+                    continue;
+                };
+                if (x - cx) * (x - cx) + (y - cy) * (y - cy) > radius * radius {
+                    let idx = row + px * 4;
+                    buffer[idx..idx + 4].copy_from_slice(&pixel);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// Sprite atlas output for hosts that draw with drawImage or WebGL instead of
+// the WASM rasterizer. render_atlas() builds, once, an RGBA image of white
+// anti-aliased discs at a few radii (one row of cells, in the output alpha
+// convention: straight unless set_output_alpha() says otherwise), and
+// atlas_instances() turns the current frame into per-ball instance data that
+// points into it. Hosts tint the white sprites with the instance color.
+//
+// Levels are spaced geometrically between the world's min_radius and its
+// largest live ball; each ball uses the level nearest to its radius and a
+// scale factor to reach its exact size.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{render, OutputAlpha, World};
+
+// Transparent pixels around each disc so bilinear sampling doesn't bleed
+const CELL_PADDING: usize = 1;
+const MAX_LEVELS: u32 = 32;
+// Bigger balls scale up the largest disc instead
+const MAX_DISC_RADIUS: f32 = 512.0;
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Atlas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    radii: Vec<f32>,
+    cells: Vec<f32>, // Per level: x, y, size (square cells, in pixels)
+}
+
+#[wasm_bindgen]
+impl Atlas {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // RGBA bytes, width * height * 4 (Uint8Array, ready for ImageData)
+    pub fn pixels(&self) -> Vec<u8> {
+        self.pixels.clone()
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.radii.len()
+    }
+
+    // Radius of each level's disc, in pixels
+    pub fn radii(&self) -> Vec<f32> {
+        self.radii.clone()
+    }
+
+    // Source rectangle of each level as x, y, size triples: the disc is
+    // centered in its cell
+    pub fn cells(&self) -> Vec<f32> {
+        self.cells.clone()
+    }
+}
+
+impl Atlas {
+    fn new(radii: Vec<f32>, premultiplied: bool) -> Atlas {
+        let sizes: Vec<usize> = radii
+            .iter()
+            .map(|radius| (2.0 * radius).ceil() as usize + 2 * CELL_PADDING)
+            .collect();
+        let width = sizes.iter().sum();
+        let height = sizes.iter().copied().max().unwrap_or(0);
+        let mut atlas = Atlas {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+            radii,
+            cells: Vec::with_capacity(sizes.len() * 3),
+        };
+        let mut x0 = 0;
+        for (level, &size) in sizes.iter().enumerate() {
+            atlas.draw_disc(x0, size, atlas.radii[level], premultiplied);
+            atlas.cells.extend([x0 as f32, 0.0, size as f32]);
+            x0 += size;
+        }
+        atlas
+    }
+
+    // White disc with its edge coverage in alpha (pixel centers at +0.5)
+    fn draw_disc(&mut self, x0: usize, size: usize, radius: f32, premultiplied: bool) {
+        let center = size as f32 * 0.5;
+        for py in 0..size {
+            for px in 0..size {
+                let dx = px as f32 + 0.5 - center;
+                let dy = py as f32 + 0.5 - center;
+                let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+                let alpha = (coverage * 255.0).round() as u8;
+                let idx = (py * self.width + x0 + px) * 4;
+                let white = if premultiplied { alpha } else { 255 };
+                self.pixels[idx..idx + 4].copy_from_slice(&[white, white, white, alpha]);
+            }
+        }
+    }
+
+    // Level whose radius is nearest in log scale, and the scale to apply to it
+    fn level_for(&self, radius: f32) -> (usize, f32) {
+        let distance = |level: &f32| (radius / level).ln().abs();
+        let level = (0..self.radii.len())
+            .min_by(|&a, &b| distance(&self.radii[a]).total_cmp(&distance(&self.radii[b])))
+            .unwrap_or(0);
+        (level, radius / self.radii[level])
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Pre-render `levels` disc sizes (1..=32) covering the current radius range
+    pub fn render_atlas(&self, levels: u32) -> Atlas {
+        let levels = levels.clamp(1, MAX_LEVELS);
+        let smallest = self.min_radius.clamp(0.5, MAX_DISC_RADIUS);
+        let largest = self
+            .live_balls()
+            .map(|(_, ball)| ball.radius)
+            .fold(smallest, f32::max)
+            .min(MAX_DISC_RADIUS);
+        let radii = (0..levels)
+            .map(|level| {
+                let share = if levels > 1 {
+                    level as f32 / (levels - 1) as f32
+                } else {
+                    0.0
+                };
+                // Largest first: geometric steps from `largest` down to `smallest`
+                largest * (smallest / largest).powf(share)
+            })
+            .collect();
+        Atlas::new(
+            radii,
+            self.render.output_alpha == OutputAlpha::Premultiplied,
+        )
+    }
+
+    // One instance per drawn ball, in paint order (same filter, layers and
+    // draw order as render_to_buffer): x, y, level, scale, color (the
+    // 0xRRGGBB bits of the color mode's fill, read them back with a
+    // Uint32Array view) - 5 floats each
+    pub fn atlas_instances(&self, atlas: &Atlas) -> Vec<f32> {
+        if atlas.radii.is_empty() {
+            return Vec::new();
+        }
+        let ids = self.draw_list();
+        let mut instances = Vec::with_capacity(ids.len() * 5);
+        for id in ids {
+            let ball = &self.balls[id as usize];
+            let radius = render::grown_radius(ball, self.frame, self.split.grow_frames);
+            let (level, scale) = atlas.level_for(radius);
+            instances.extend([
+                ball.x,
+                ball.y,
+                level as f32,
+                scale,
+                f32::from_bits(self.render.color_mode.fill(ball)),
+            ]);
+        }
+        instances
+    }
+}
@@ -0,0 +1,106 @@
+// Background image. set_background_image() replaces the flat dark clear color
+// with an RGBA image, stretched over the arena or tiled from its top-left
+// corner at 1:1. Translucent image pixels are blended over the usual clear
+// color, so a partly transparent backdrop still gives a dark base (or, with a
+// transparent background, stays partly transparent).
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::Clip;
+use crate::srgb::Blending;
+use crate::{World, WorldError};
+
+// The flat clear color drawn without an image
+pub(crate) use crate::sim::CLEAR_COLOR;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackgroundFit {
+    #[default]
+    Stretch = 0, // Scaled (nearest pixel) to cover the arena exactly
+    Tile = 1, // Repeated at its own size
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Background {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Background {
+    // Draw the image over the cleared band. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(
+        &self,
+        buffer: &mut [u8],
+        stride: usize,
+        clip: Clip,
+        fit: BackgroundFit,
+        (arena_w, arena_h): (f32, f32),
+        blending: Blending,
+    ) {
+        let source = |p: usize, size: usize, arena: f32| match fit {
+            BackgroundFit::Stretch => {
+                (((p as f32 + 0.5) * size as f32 / arena) as usize).min(size - 1)
+            }
+            BackgroundFit::Tile => p % size,
+        };
+        let columns: Vec<usize> = (clip.x0..clip.x1)
+            .map(|px| source(px, self.width, arena_w) * 4)
+            .collect();
+        for py in clip.y0..clip.y1 {
+            let image_row = source(py, self.height, arena_h) * self.width * 4;
+            let row = (py - clip.y0) * stride;
+            let pixels = buffer[row + clip.x0 * 4..row + clip.x1 * 4].chunks_exact_mut(4);
+            for (pixel, &column) in pixels.zip(&columns) {
+                let texel = &self.pixels[image_row + column..image_row + column + 4];
+                let rgb = [texel[0], texel[1], texel[2]];
+                blending.blend(pixel, rgb, texel[3] as f32 / 255.0);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Draw a width x height RGBA image (rows packed, width * height * 4
+    // bytes) behind everything instead of the flat clear color. A 0-sized
+    // image removes it.
+    pub fn set_background_image(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba_pixels: &[u8],
+    ) -> Result<(), WorldError> {
+        let (width, height) = (width as usize, height as usize);
+        let expected = width.saturating_mul(height).saturating_mul(4);
+        if rgba_pixels.len() != expected {
+            return Err(WorldError::BufferSizeMismatch {
+                expected,
+                actual: rgba_pixels.len(),
+            });
+        }
+        self.render.background = (expected > 0).then(|| Background {
+            width,
+            height,
+            pixels: rgba_pixels.to_vec(),
+        });
+        Ok(())
+    }
+
+    pub fn clear_background_image(&mut self) {
+        self.render.background = None;
+    }
+
+    pub fn has_background_image(&self) -> bool {
+        self.render.background.is_some()
+    }
+
+    pub fn set_background_fit(&mut self, fit: BackgroundFit) {
+        self.render.background_fit = fit;
+    }
+
+    pub fn background_fit(&self) -> BackgroundFit {
+        self.render.background_fit
+    }
+}
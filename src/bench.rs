@@ -0,0 +1,114 @@
+// Headless benchmark: the same deterministic workload everywhere, so timings
+// from different browsers, machines and feature flags can be compared. The
+// bench world collides its balls and runs profiled (see profile.rs), and the
+// report adds up each step's phase timings.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::profile::now_ms;
+use crate::World;
+
+const BENCH_WIDTH: usize = 800;
+const BENCH_HEIGHT: usize = 600;
+const BENCH_SEED: u32 = 1;
+// Largest ball_target a run will seed
+const MAX_BENCH_BALLS: usize = 100_000;
+
+// Totals over the whole run, in milliseconds
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct BenchReport {
+    pub steps: u32,
+    pub balls: u32,          // Live balls when the run finished
+    pub integration_ms: f64, // Integration, wall bounces and splits
+    pub collision_ms: f64,   // Ball-ball collisions
+    pub raster_ms: f64,      // render_to_buffer into an 800x600 framebuffer
+    pub total_ms: f64,       // Wall clock, profiling overhead included
+    pub state_hash: u32,     // Should match across runs of the same build
+}
+
+#[wasm_bindgen]
+impl BenchReport {
+    pub fn ms_per_step(&self) -> f64 {
+        if self.steps == 0 {
+            0.0
+        } else {
+            self.total_ms / self.steps as f64
+        }
+    }
+}
+
+// Seed a deterministic world with `ball_target` balls (also its max_balls,
+// clamped to 1..=MAX_BENCH_BALLS), then run `steps` updates, rendering after
+// each one.
+#[wasm_bindgen]
+pub fn bench(steps: u32, ball_target: usize) -> BenchReport {
+    let ball_target = ball_target.clamp(1, MAX_BENCH_BALLS);
+    let mut world = World::new_seeded(
+        BENCH_WIDTH as f32,
+        BENCH_HEIGHT as f32,
+        ball_target,
+        0.8,
+        BENCH_SEED,
+    );
+    world.set_collisions(true);
+    world.set_profiling(true);
+    let mut placement = ChaCha8Rng::seed_from_u64(ball_target as u64);
+    while world.live_count() < ball_target {
+        let radius = placement.gen_range(2.0..10.0);
+        let added = world.add_ball(
+            placement.gen_range(radius..BENCH_WIDTH as f32 - radius),
+            placement.gen_range(radius..BENCH_HEIGHT as f32 - radius),
+            placement.gen_range(-4.0..4.0),
+            placement.gen_range(-4.0..4.0),
+            radius,
+            placement.gen(),
+        );
+        if added.is_none() {
+            break;
+        }
+    }
+
+    let mut pixels = vec![0u8; BENCH_WIDTH * BENCH_HEIGHT * 4];
+    let mut integration_ms = 0.0;
+    let mut collision_ms = 0.0;
+    let mut raster_ms = 0.0;
+    let start = now_ms();
+    for _ in 0..steps {
+        world.update();
+        world
+            .render_to_buffer(&mut pixels, BENCH_WIDTH, BENCH_HEIGHT, None)
+            .expect("bench framebuffer matches its surface");
+        let timings = world.frame_timings();
+        integration_ms += timings.integration_ms + timings.wall_ms + timings.split_ms;
+        collision_ms += timings.collision_ms;
+        raster_ms += timings.render_ms;
+    }
+
+    BenchReport {
+        steps,
+        balls: world.live_count() as u32,
+        integration_ms,
+        collision_ms,
+        raster_ms,
+        total_ms: now_ms() - start,
+        state_hash: world.state_hash(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_times_every_phase() {
+        let report = bench(20, 200);
+        assert_eq!(report.balls, 200);
+        assert!(report.integration_ms > 0.0);
+        assert!(report.collision_ms > 0.0);
+        assert!(report.raster_ms > 0.0);
+        assert_eq!(report.state_hash, bench(20, 200).state_hash);
+    }
+}
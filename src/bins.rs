@@ -0,0 +1,87 @@
+// Bin counts, for Galton boards and other "where do they end up" demos.
+// Every ball that drains out through an open floor is tallied by where it
+// left. bin_counts(n) cuts the arena width into n equal bins and returns, per
+// bin, the balls that left through it plus the live balls that have settled
+// in it: slower than SETTLED_SPEED pixels/frame in the lower half of the
+// arena, e.g. resting on a closed floor or piled up between dividers.
+//
+// Exits are kept as fractions of the width in EXIT_COLUMNS columns, so they
+// stay put when the arena is resized, and with very many bins a ball right at
+// a boundary may be counted one bin over.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{sim, Ball, World};
+
+const EXIT_COLUMNS: usize = 4096;
+const SETTLED_SPEED: f32 = 0.5;
+// bin_counts' limit (256 KiB of u32), far finer than EXIT_COLUMNS anyway
+const MAX_BINS: u32 = 1 << 16;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Bins {
+    exits: Vec<u32>, // Per column, allocated on the first exit
+}
+
+impl Bins {
+    // A ball that has left through `wall` (see sim::escaped)
+    pub(crate) fn record_exit(&mut self, ball: &Ball, wall: u32, width: f32) {
+        if wall != sim::WALL_BOTTOM {
+            return;
+        }
+        if self.exits.is_empty() {
+            self.exits = vec![0; EXIT_COLUMNS];
+        }
+        let column = (ball.x / width * EXIT_COLUMNS as f32).clamp(0.0, (EXIT_COLUMNS - 1) as f32);
+        let count = &mut self.exits[column as usize];
+        *count = count.saturating_add(1);
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Balls per bin, left to right, of `n_bins` equal bins across the width:
+    // those drained out through the floor there so far plus the live ones
+    // settled there (see bins.rs). Empty for 0 bins or more than 2^16.
+    pub fn bin_counts(&self, n_bins: u32) -> Vec<u32> {
+        if n_bins == 0 || n_bins > MAX_BINS {
+            return Vec::new();
+        }
+        let n = n_bins as usize;
+        let mut counts = vec![0u32; n];
+        let bin = |fraction: f32| ((fraction * n as f32) as usize).min(n - 1);
+        for (column, &exits) in self.bins.exits.iter().enumerate() {
+            if exits > 0 {
+                let count = &mut counts[bin((column as f32 + 0.5) / EXIT_COLUMNS as f32)];
+                *count = count.saturating_add(exits);
+            }
+        }
+        for (_, ball) in self.live_balls() {
+            let slow = ball.vx * ball.vx + ball.vy * ball.vy < SETTLED_SPEED * SETTLED_SPEED;
+            if slow && ball.y > self.height / 2.0 {
+                let count = &mut counts[bin((ball.x / self.width).max(0.0))];
+                *count = count.saturating_add(1);
+            }
+        }
+        counts
+    }
+
+    // Forget the tallied exits (settled balls are counted as long as they stay)
+    pub fn clear_bin_counts(&mut self) {
+        self.bins.exits = Vec::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    #[test]
+    fn bin_counts_limits() {
+        let world = World::new(200.0, 150.0, 64, 0.7);
+        assert!(world.bin_counts(0).is_empty());
+        assert!(world.bin_counts(u32::MAX).is_empty());
+        assert!(world.bin_counts((1 << 16) + 1).is_empty());
+        assert_eq!(world.bin_counts(1 << 16).len(), 1 << 16);
+    }
+}
@@ -0,0 +1,159 @@
+// Camera: a render-only pan and zoom. A view is the arena point drawn at the
+// center of the picture plus a zoom factor; without a camera set the arena is
+// drawn 1:1 as usual. move_camera() glides from the current view to a new one
+// over a number of frames, eased at both ends, so moves can be queued ahead of
+// time like any other action (see schedule.rs).
+//
+// Only the picture moves: physics, picking and every other API stay in arena
+// coordinates, and the minimap and thumbnails still show the whole arena.
+// Camera shake (see shake.rs) jolts the view on top of the camera. A
+// transformed render paints the arena into a scratch buffer first and samples
+// it (nearest pixel) for each output pixel, so zooming in shows pixels grow.
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::{Clip, Surface};
+use crate::World;
+
+const MIN_ZOOM: f32 = 1.0 / 16.0;
+const MAX_ZOOM: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct View {
+    pub x: f32, // Arena point at the center of the picture
+    pub y: f32,
+    pub zoom: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Camera {
+    from: View,
+    to: View,
+    start: u32, // Frame the glide started in
+    frames: u32,
+}
+
+impl Camera {
+    fn view(&self, frame: u32) -> View {
+        let elapsed = frame.wrapping_sub(self.start);
+        if elapsed >= self.frames {
+            return self.to;
+        }
+        let t = elapsed as f32 / self.frames as f32;
+        let t = t * t * (3.0 - 2.0 * t);
+        let (from, to) = (self.from, self.to);
+        View {
+            x: from.x + (to.x - from.x) * t,
+            y: from.y + (to.y - from.y) * t,
+            // Geometric, so every frame of a zoom scales by the same factor
+            zoom: from.zoom * (to.zoom / from.zoom).powf(t),
+        }
+    }
+}
+
+// Fill `clip` of `buffer` (rows `stride` bytes apart) by looking at the
+// painted arena `scene` through `view`; pixels outside the arena get `clear`
+pub(crate) fn sample(
+    (scene, scene_surface): (&[u8], Surface),
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    view: View,
+    arena: (f32, f32),
+    clear: [u8; 4],
+) {
+    let source = |x: f32, y: f32| {
+        let (sx, sy) = (x.floor(), y.floor());
+        if sx < 0.0 || sy < 0.0 {
+            return None;
+        }
+        let (sx, sy) = (sx as usize, sy as usize);
+        (sx < scene_surface.width && sy < scene_surface.height)
+            .then(|| sy * scene_surface.stride + sx * 4)
+    };
+    for py in clip.y0..clip.y1 {
+        let y = view.y + (py as f32 + 0.5 - arena.1 / 2.0) / view.zoom;
+        let row = py * stride;
+        for px in clip.x0..clip.x1 {
+            let x = view.x + (px as f32 + 0.5 - arena.0 / 2.0) / view.zoom;
+            let idx = row + px * 4;
+            match source(x, y) {
+                Some(from) => buffer[idx..idx + 4].copy_from_slice(&scene[from..from + 4]),
+                None => buffer[idx..idx + 4].copy_from_slice(&clear),
+            }
+        }
+    }
+}
+
+impl World {
+    // What a camera-less render shows
+    fn home_view(&self) -> View {
+        View {
+            x: self.width / 2.0,
+            y: self.height / 2.0,
+            zoom: 1.0,
+        }
+    }
+
+    fn current_view(&self) -> View {
+        match self.camera {
+            Some(camera) => camera.view(self.frame),
+            None => self.home_view(),
+        }
+    }
+
+    // The view for this frame's render, shaken, or None when it is the
+    // plain 1:1 one
+    pub(crate) fn camera_view(&self) -> Option<View> {
+        let mut view = self.current_view();
+        let (dx, dy) = self.shake.offset(self.frame);
+        view.x -= dx / view.zoom;
+        view.y -= dy / view.zoom;
+        (view != self.home_view()).then_some(view)
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Glide the camera from where it is now to center arena point (x, y) at
+    // `zoom` (clamped to 1/16..=16) over `frames` frames; 0 frames jumps.
+    // Ignored unless every argument is finite and the zoom positive.
+    pub fn move_camera(&mut self, x: f32, y: f32, zoom: f32, frames: u32) {
+        if !(x.is_finite() && y.is_finite() && zoom.is_finite()) || zoom <= 0.0 {
+            return;
+        }
+        self.camera = Some(Camera {
+            from: self.current_view(),
+            to: View {
+                x,
+                y,
+                zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            },
+            start: self.frame,
+            frames,
+        });
+    }
+
+    // Jump straight to a view (move_camera over 0 frames)
+    pub fn set_camera(&mut self, x: f32, y: f32, zoom: f32) {
+        self.move_camera(x, y, zoom, 0);
+    }
+
+    // Back to drawing the arena 1:1
+    pub fn reset_camera(&mut self) {
+        self.camera = None;
+    }
+
+    // The view at the current frame
+    pub fn camera_x(&self) -> f32 {
+        self.current_view().x
+    }
+
+    pub fn camera_y(&self) -> f32 {
+        self.current_view().y
+    }
+
+    pub fn camera_zoom(&self) -> f32 {
+        self.current_view().zoom
+    }
+}
@@ -0,0 +1,95 @@
+// What happens when a split would take the world past max_balls. By default
+// the split is refused (the ball bounces without splitting), which eventually
+// freezes the population. The Replace* policies free up a slot instead, so a
+// full world keeps churning.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Ball, World};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CapPolicy {
+    #[default]
+    Reject = 0, // No split while the world is full
+    ReplaceOldest = 1,   // Remove the ball with the earliest born_frame
+    ReplaceSmallest = 2, // Remove the ball with the smallest radius
+}
+
+#[wasm_bindgen]
+impl World {
+    pub fn set_cap_policy(&mut self, policy: CapPolicy) {
+        self.cap_policy = policy;
+    }
+
+    pub fn cap_policy(&self) -> CapPolicy {
+        self.cap_policy
+    }
+}
+
+impl World {
+    // Remove balls according to the cap policy until `children` more fit
+    // under max_balls. Balls that split in this step are never removed;
+    // split_capacity() keeps the splits to what can be made room for.
+    pub(crate) fn make_room(&mut self, children: usize) {
+        let free = self.max_balls.saturating_sub(self.live_count());
+        if children <= free {
+            return;
+        }
+        let needed = children - free;
+        let mut candidates: Vec<(usize, &Ball)> = self
+            .live_balls()
+            .filter(|(_, ball)| ball.just_split == 0)
+            .collect();
+        let victims = needed.min(candidates.len());
+        if victims > 0 {
+            let order = |a: &(usize, &Ball), b: &(usize, &Ball)| {
+                match self.cap_policy {
+                    CapPolicy::ReplaceSmallest => a.1.radius.total_cmp(&b.1.radius),
+                    _ => a.1.born_frame.cmp(&b.1.born_frame),
+                }
+                .then(a.0.cmp(&b.0))
+            };
+            if victims < candidates.len() {
+                candidates.select_nth_unstable_by(victims - 1, order);
+            }
+            let ids: Vec<u32> = candidates[..victims]
+                .iter()
+                .map(|&(id, _)| id as u32)
+                .collect();
+            for id in ids {
+                self.free_slot(id);
+            }
+        }
+        debug_assert_eq!(
+            victims, needed,
+            "split_capacity let in more splits than fit"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CapPolicy, World};
+
+    // Four balls that all reach a wall in a full world: two may split, each
+    // replacing one of the other two, and no parent shrinks without its child
+    #[test]
+    fn replaced_splits_keep_their_children() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 4, 0.7, 1).unwrap();
+        world.set_cap_policy(CapPolicy::ReplaceOldest);
+        for y in [20.0, 50.0, 80.0, 110.0] {
+            world.add_ball(188.0, y, 5.0, 0.0, 10.0, 0).unwrap();
+        }
+        world.update();
+        let frame = world.frame();
+        let split: Vec<_> = world
+            .live_balls()
+            .filter(|(_, ball)| ball.just_split == 1)
+            .map(|(_, ball)| ball.born_frame == frame)
+            .collect();
+        assert_eq!(world.live_count(), 4);
+        assert_eq!(split.iter().filter(|&&child| child).count(), 2);
+        assert_eq!(split.iter().filter(|&&child| !child).count(), 2);
+    }
+}
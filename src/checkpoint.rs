@@ -0,0 +1,185 @@
+// Named in-memory checkpoints, for "restart from the good part" buttons.
+// save_checkpoint() keeps a full snapshot of the balls (the snapshot.rs
+// format) and the free list, the simulation parameters, the random streams
+// and every stateful feature update() reads: obstacles, emitters,
+// attractors, shockwaves, the schedule, water, goals, corner-trap and
+// auto-color state, the f64 and Q16.16 shadows and a pending global force.
+// load_checkpoint() puts all of it back, frame counter included, so from a
+// seeded world the run replays exactly as it did after the save (the same
+// state_hash() frame by frame, see lockstep.rs).
+//
+// Loading rewinds the frame counter: hosts streaming snapshot_delta() to
+// viewers should follow it with a snapshot_full(). Trails and the undo
+// history are cleared; edit mode, the camera and other view state stay as
+// they are.
+
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::colors::AutoColorState;
+use crate::corner_trap::CornerTrap;
+use crate::dynamics::Attractor;
+#[cfg(feature = "fixed")]
+use crate::fixed::FixedState;
+use crate::goals::Goal;
+use crate::history::Params;
+use crate::host_rng::WorldRng;
+use crate::obstacles::Obstacle;
+use crate::precision::Precise;
+use crate::schedule::Schedule;
+use crate::shockwave::Shockwaves;
+use crate::water::Water;
+use crate::{Emitter, World, WorldError};
+
+#[derive(Clone, Debug)]
+struct Checkpoint {
+    snapshot: Vec<u8>,
+    free: Vec<u32>, // In order, as apply_snapshot() rebuilds it sorted
+    params: Params,
+    min_contrast: f32,
+    force: (f32, f32),
+    rng: WorldRng,
+    precise: Option<Vec<Precise>>,
+    #[cfg(feature = "fixed")]
+    fixed: Option<Vec<FixedState>>,
+    obstacles: Vec<Obstacle>,
+    emitters: Vec<Emitter>,
+    attractors: Vec<Attractor>,
+    shockwaves: Shockwaves,
+    schedule: Schedule,
+    water: Water,
+    goals: Vec<Goal>,
+    corner_trap: CornerTrap,
+    auto_color: AutoColorState,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Checkpoints {
+    saved: BTreeMap<String, Checkpoint>,
+}
+
+#[wasm_bindgen]
+impl World {
+    // Save the current state under `name`, replacing any checkpoint of that name
+    pub fn save_checkpoint(&mut self, name: &str) {
+        let checkpoint = Checkpoint {
+            snapshot: self.snapshot_full(),
+            free: self.free.clone(),
+            params: self.params(),
+            min_contrast: self.min_contrast,
+            force: self.force,
+            rng: self.rng.clone(),
+            precise: self.precise.clone(),
+            #[cfg(feature = "fixed")]
+            fixed: self.fixed.clone(),
+            obstacles: self.obstacles.clone(),
+            emitters: self.emitters.clone(),
+            attractors: self.attractors.clone(),
+            shockwaves: self.shockwaves.clone(),
+            schedule: self.schedule.clone(),
+            water: self.water,
+            goals: self.goals.clone(),
+            corner_trap: self.corner_trap.clone(),
+            auto_color: self.auto_color.clone(),
+        };
+        self.checkpoints.saved.insert(name.to_string(), checkpoint);
+    }
+
+    pub fn load_checkpoint(&mut self, name: &str) -> Result<(), WorldError> {
+        let Some(checkpoint) = self.checkpoints.saved.get(name) else {
+            return Err(WorldError::UnknownCheckpoint(name.to_string()));
+        };
+        let checkpoint = checkpoint.clone();
+        self.apply_snapshot(&checkpoint.snapshot)?;
+        self.free = checkpoint.free;
+        self.apply_params(checkpoint.params);
+        self.min_contrast = checkpoint.min_contrast;
+        self.force = checkpoint.force;
+        self.rng = checkpoint.rng;
+        self.precise = checkpoint.precise;
+        #[cfg(feature = "fixed")]
+        {
+            self.fixed = checkpoint.fixed;
+        }
+        self.obstacles = checkpoint.obstacles;
+        self.emitters = checkpoint.emitters;
+        self.attractors = checkpoint.attractors;
+        self.shockwaves = checkpoint.shockwaves;
+        self.schedule = checkpoint.schedule;
+        self.water = checkpoint.water;
+        self.goals = checkpoint.goals;
+        self.corner_trap = checkpoint.corner_trap;
+        self.auto_color = checkpoint.auto_color;
+        self.clear_trails();
+        self.despawns.clear();
+        self.outlines.clear();
+        self.clear_history();
+        Ok(())
+    }
+
+    pub fn has_checkpoint(&self, name: &str) -> bool {
+        self.checkpoints.saved.contains_key(name)
+    }
+
+    pub fn delete_checkpoint(&mut self, name: &str) -> bool {
+        self.checkpoints.saved.remove(name).is_some()
+    }
+
+    // Sorted names of the saved checkpoints
+    pub fn checkpoint_names(&self) -> Vec<String> {
+        self.checkpoints.saved.keys().cloned().collect()
+    }
+
+    // Bytes held by all checkpoints (roughly 64 per ball slot each)
+    pub fn checkpoint_bytes(&self) -> usize {
+        self.checkpoints
+            .saved
+            .values()
+            .map(|checkpoint| {
+                checkpoint.snapshot.len()
+                    + checkpoint.obstacles.len() * std::mem::size_of::<Obstacle>()
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Emitter, Precision, World};
+
+    fn scene() -> World {
+        let mut world = World::new_seeded(400.0, 300.0, 300, 0.7, 7);
+        world.set_collisions(true);
+        world.set_hashed_random(true);
+        world.set_corner_trap(3, 2.0);
+        world.set_precision(Precision::F64);
+        world.add_emitter(&Emitter::new(100.0, 20.0, 1.0, 0.0, 4.0, 0.3));
+        world.add_attractor(200.0, 150.0, 50.0);
+        world.shockwave(200.0, 150.0, 120.0, 2.0);
+        world.queue_action(40, &Action::shockwave(100.0, 100.0, 80.0, 3.0));
+        world.set_water_level(250.0, 0.5);
+        world.apply_global_force(0.5, 0.0);
+        world
+    }
+
+    // Save, run on, load and run again: the same frames come out
+    #[test]
+    fn load_replays_the_run() {
+        let mut world = scene();
+        world.save_checkpoint("start");
+        let saved = world.state_hash();
+        let hashes: Vec<u32> = (0..60)
+            .map(|_| {
+                world.update();
+                world.state_hash()
+            })
+            .collect();
+        world.load_checkpoint("start").unwrap();
+        assert_eq!(world.state_hash(), saved);
+        for (frame, &hash) in hashes.iter().enumerate() {
+            world.update();
+            assert_eq!(world.state_hash(), hash, "diverged at frame {frame}");
+        }
+    }
+}
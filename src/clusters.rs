@@ -0,0 +1,104 @@
+// Cluster analysis: balls whose surfaces are within a gap of each other are
+// joined, and so on transitively, so a pile or chain of touching balls is
+// one cluster. Hosts use it to notice a big clump forming, e.g. to blow it
+// up with a shockwave. Neighbors come from the collision broadphase grid and
+// are joined with a union-find, so this is about as cheap as a collision pass.
+
+use wasm_bindgen::prelude::*;
+
+use crate::collision::Grid;
+use crate::World;
+
+// Result of World::clusters
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Clusters {
+    count: u32,
+    largest: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl Clusters {
+    // Clusters among the live balls, single balls included
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn largest_size(&self) -> u32 {
+        self.largest.len() as u32
+    }
+
+    // Ids in the largest cluster, ascending (of the largest ones, the one
+    // with the lowest id)
+    pub fn largest(&self) -> Vec<u32> {
+        self.largest.clone()
+    }
+}
+
+// Root of `id`'s set, halving the path on the way
+fn find(parents: &mut [u32], mut id: usize) -> usize {
+    while parents[id] as usize != id {
+        let grandparent = parents[parents[id] as usize];
+        parents[id] = grandparent;
+        id = grandparent as usize;
+    }
+    id
+}
+
+#[wasm_bindgen]
+impl World {
+    // Join balls whose surfaces are at most `max_gap` pixels apart
+    // (overlapping balls always are; a negative or non-finite gap counts as 0)
+    pub fn clusters(&self, max_gap: f32) -> Clusters {
+        let gap = if max_gap.is_finite() {
+            max_gap.max(0.0)
+        } else {
+            0.0
+        };
+        let max_radius = self
+            .live_balls()
+            .fold(0.0f32, |max, (_, ball)| max.max(ball.radius));
+        let grid = Grid::new(&self.balls, max_radius * 2.0 + gap);
+        let mut parents: Vec<u32> = (0..self.balls.len() as u32).collect();
+        for (a, ball) in self.live_balls() {
+            for b in grid.neighbors(ball) {
+                if b <= a {
+                    continue;
+                }
+                let other = &self.balls[b];
+                let (dx, dy) = (other.x - ball.x, other.y - ball.y);
+                let reach = ball.radius + other.radius + gap;
+                if dx * dx + dy * dy > reach * reach {
+                    continue;
+                }
+                let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+                // The lower id stays the root, so roots are stable
+                parents[root_a.max(root_b)] = root_a.min(root_b) as u32;
+            }
+        }
+
+        let mut sizes = vec![0u32; self.balls.len()];
+        for (id, _) in self.live_balls() {
+            let root = find(&mut parents, id);
+            sizes[root] += 1;
+        }
+        let count = sizes.iter().filter(|&&size| size > 0).count() as u32;
+        // max_by_key would keep the last of equal sizes
+        let mut biggest: Option<(usize, u32)> = None;
+        for (root, &size) in sizes.iter().enumerate() {
+            if size > 0 && biggest.is_none_or(|(_, most)| size > most) {
+                biggest = Some((root, size));
+            }
+        }
+        let largest = match biggest {
+            Some((root, _)) => self
+                .live_balls()
+                .map(|(id, _)| id)
+                .filter(|&id| find(&mut parents, id) == root)
+                .map(|id| id as u32)
+                .collect(),
+            None => Vec::new(),
+        };
+        Clusters { count, largest }
+    }
+}
@@ -0,0 +1,410 @@
+// Ball-ball collisions (off by default). After each (sub-)step, overlapping
+// pairs are found with a uniform grid and resolved as collisions between
+// discs of mass r^2: the pair is pushed apart along the line between the
+// centers and, if approaching, exchanges momentum along it. They are elastic
+// unless the balls' mixed restitution is below 1.
+//
+// The grid is rebuilt every pass with a cell size of the largest diameter,
+// so each ball only has to be checked against its own and the 8 neighbouring
+// cells (the plexus effect, cluster analysis and Voronoi shading reuse the
+// same Grid). Only add/sub/mul/div/sqrt
+// are used, which keeps lockstep exact.
+// A ball squeezed through a closed wall gets its center put back inside.
+//
+// With an impact split speed set, pairs that collide at least that fast also
+// split, both balls pushing off along the contact normal as they would off a
+// wall. The split rules (just_split cooldown, max_balls, max_splits_per_frame,
+// min_radius...) are the same as for wall hits.
+
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "fixed")]
+use crate::fixed;
+use crate::{arena, profile, sim, Ball, World};
+
+// Grid size limits: a few cells per ball, but never fewer than MIN_CELLS or
+// more than MAX_CELLS ones
+const MIN_CELLS: usize = 1 << 16;
+const MAX_CELLS: usize = 1 << 22;
+const CELLS_PER_BALL: usize = 16;
+
+#[wasm_bindgen]
+impl World {
+    pub fn set_collisions(&mut self, enabled: bool) {
+        self.collisions = enabled;
+    }
+
+    pub fn collisions(&self) -> bool {
+        self.collisions
+    }
+
+    // Also split balls in collisions with a closing speed of at least
+    // `speed` px/frame (0, the default, turns this off). Needs collisions on.
+    pub fn set_impact_splitting(&mut self, speed: f32) {
+        if speed.is_finite() {
+            self.impact_split_speed = speed.max(0.0);
+        }
+    }
+
+    pub fn impact_splitting(&self) -> f32 {
+        self.impact_split_speed
+    }
+}
+
+impl World {
+    // Resolve every overlapping pair once. With profiling on, the time is
+    // reported as collision_ms.
+    pub(crate) fn collide(&mut self, stamp: u32) {
+        let start = self.profile.enabled().then(profile::now_ms);
+
+        let max_radius = self
+            .live_balls()
+            .fold(0.0f32, |max, (_, ball)| max.max(ball.radius));
+        let grid = Grid::new(&self.balls, max_radius * 2.0);
+        let config = self.sim_config();
+
+        // Ball id and the normal away from the other ball, per fast impact
+        let mut impacts = Vec::new();
+        for a in 0..self.balls.len() {
+            if self.balls[a].alive == 0 {
+                continue;
+            }
+            for b in grid.neighbors(&self.balls[a]) {
+                if b <= a {
+                    continue;
+                }
+                let Some(contact) = self.resolve_contact(a, b) else {
+                    continue;
+                };
+                self.energy.record_restitution(contact.lost);
+                self.shake.record_collision(stamp, contact.speed);
+                for id in [a, b] {
+                    arena::keep_inside(&mut self.balls[id], &config);
+                }
+                self.modified[a] = stamp;
+                self.modified[b] = stamp;
+                let threshold = self.impact_split_speed;
+                if threshold > 0.0 && contact.speed >= threshold {
+                    let (nx, ny) = contact.normal;
+                    impacts.push((a, (-nx, -ny)));
+                    impacts.push((b, (nx, ny)));
+                }
+            }
+        }
+
+        if !impacts.is_empty() {
+            self.split_impacts(&impacts, stamp);
+        }
+
+        if let Some(start) = start {
+            self.profile.add_collision(profile::now_ms() - start);
+        }
+    }
+
+    // Split balls pushed off along a normal (per impact: ball id, unit normal)
+    // and return the indices into `impacts` of those that split
+    pub(crate) fn split_impacts(
+        &mut self,
+        impacts: &[(usize, (f32, f32))],
+        stamp: u32,
+    ) -> Vec<usize> {
+        let mut split = Vec::new();
+        let mut new_balls = Vec::new();
+        let mut denied = 0;
+        let unsplit = self
+            .live_balls()
+            .filter(|(_, ball)| ball.just_split == 0)
+            .count();
+        let capacity = self.split_capacity(unsplit);
+        let config = self.sim_config();
+        for (index, &(id, normal)) in impacts.iter().enumerate() {
+            let ball = &mut self.balls[id];
+            let room = new_balls.len() < capacity;
+            self.rng.key_ball(id, ball);
+            match sim::split_on_impact(ball, &config, normal, room, &mut self.rng) {
+                sim::Split::Child(child) => {
+                    self.energy
+                        .record_split(ball, &child, self.split_ratio, self.split.kinematics);
+                    self.modified[id] = stamp;
+                    new_balls.push(child);
+                    split.push(index);
+                }
+                sim::Split::Denied => denied += 1,
+                sim::Split::None => {}
+            }
+        }
+
+        self.log_denied(stamp, denied);
+        self.make_room(new_balls.len());
+        self.count_splits(new_balls.len());
+        for ball in new_balls {
+            self.insert_child(ball, stamp);
+        }
+        split
+    }
+}
+
+impl World {
+    // resolve_pair on the f32 balls, or on the fixed-point shadow when it's on
+    fn resolve_contact(&mut self, a: usize, b: usize) -> Option<Contact> {
+        #[cfg(feature = "fixed")]
+        if let Some(states) = &mut self.fixed {
+            return fixed::resolve_pair(states, &mut self.balls, a, b, self.heating, self.mix_rule);
+        }
+        resolve_pair(&mut self.balls, a, b, self.heating, self.mix_rule)
+    }
+}
+
+// Uniform broadphase grid over the live balls at the time it was built.
+// Any two balls closer than `cell` (center to center) are in the same or
+// adjacent cells. It covers the balls' bounding box rather than the arena, so
+// a few balls in a huge arena get a small grid; cells grow coarser than
+// `cell` where that box would need more of them than the balls warrant.
+pub(crate) struct Grid {
+    origin: (f32, f32), // Top-left of the bounding box
+    cell: f32,
+    cols: usize,
+    rows: usize,
+    starts: Vec<u32>, // Per cell, the first index into `ids` (plus an end marker)
+    ids: Vec<u32>,    // Live ball ids sorted by cell, by id within a cell
+}
+
+impl Grid {
+    pub(crate) fn new(balls: &[Ball], cell: f32) -> Grid {
+        let live = || balls.iter().enumerate().filter(|(_, ball)| ball.alive != 0);
+        let mut count: usize = 0;
+        let (mut low, mut high) = (
+            (f32::INFINITY, f32::INFINITY),
+            (f32::NEG_INFINITY, f32::NEG_INFINITY),
+        );
+        for (_, ball) in live() {
+            low = (low.0.min(ball.x), low.1.min(ball.y));
+            high = (high.0.max(ball.x), high.1.max(ball.y));
+            count += 1;
+        }
+        if count == 0 {
+            (low, high) = ((0.0, 0.0), (0.0, 0.0));
+        }
+        let (width, height) = (high.0 - low.0, high.1 - low.1);
+
+        let most = count
+            .saturating_mul(CELLS_PER_BALL)
+            .clamp(MIN_CELLS, MAX_CELLS);
+        let mut cell = cell.max(1.0);
+        let cells = (width / cell) * (height / cell);
+        if cells > most as f32 {
+            cell *= (cells / most as f32).sqrt();
+        }
+        let cols = ((width / cell) as usize).clamp(1, most);
+        let rows = ((height / cell) as usize).clamp(1, most / cols);
+        let mut grid = Grid {
+            origin: low,
+            cell,
+            cols,
+            rows,
+            starts: vec![0; cols * rows + 1],
+            ids: Vec::new(),
+        };
+
+        // Counting sort of the live ball ids by cell
+        for (_, ball) in live() {
+            let (cx, cy) = grid.cell_of(ball);
+            grid.starts[cy * cols + cx + 1] += 1;
+        }
+        for index in 1..grid.starts.len() {
+            grid.starts[index] += grid.starts[index - 1];
+        }
+        let mut fill = grid.starts.clone();
+        grid.ids = vec![0; grid.starts[cols * rows] as usize];
+        for (id, ball) in live() {
+            let (cx, cy) = grid.cell_of(ball);
+            let slot = &mut fill[cy * cols + cx];
+            grid.ids[*slot as usize] = id as u32;
+            *slot += 1;
+        }
+        grid
+    }
+
+    // Cell of a point, the nearest edge cell for points outside the box
+    fn cell_at(&self, x: f32, y: f32) -> (usize, usize) {
+        let cx = (((x - self.origin.0) / self.cell).max(0.0) as usize).min(self.cols - 1);
+        let cy = (((y - self.origin.1) / self.cell).max(0.0) as usize).min(self.rows - 1);
+        (cx, cy)
+    }
+
+    fn cell_of(&self, ball: &Ball) -> (usize, usize) {
+        self.cell_at(ball.x, ball.y)
+    }
+
+    // Ids in the ball's cell and the 8 around it (including its own id)
+    pub(crate) fn neighbors(&self, ball: &Ball) -> impl Iterator<Item = usize> + '_ {
+        let (cx, cy) = self.cell_of(ball);
+        let columns = cx.saturating_sub(1)..=(cx + 1).min(self.cols - 1);
+        (cy.saturating_sub(1)..=(cy + 1).min(self.rows - 1)).flat_map(move |ny| {
+            columns.clone().flat_map(move |nx| {
+                let cell_index = ny * self.cols + nx;
+                let range = self.starts[cell_index] as usize..self.starts[cell_index + 1] as usize;
+                self.ids[range].iter().map(|&id| id as usize)
+            })
+        })
+    }
+
+    // Id of the ball whose center is nearest (x, y), searching rings of
+    // cells outwards until none further out can hold a closer one
+    pub(crate) fn nearest(&self, balls: &[Ball], x: f32, y: f32) -> Option<usize> {
+        let (cx, cy) = self.cell_at(x, y);
+        let (cx, cy) = (cx as isize, cy as isize);
+        let mut best: Option<(usize, f32)> = None;
+        for ring in 0..self.cols.max(self.rows) as isize {
+            // Every cell of this ring is at least ring - 1 cells away
+            let reach = (ring - 1) as f32 * self.cell;
+            if best.is_some_and(|(_, squared)| reach > 0.0 && reach * reach > squared) {
+                break;
+            }
+            for ny in (cy - ring).max(0)..=(cy + ring).min(self.rows as isize - 1) {
+                let edge = ny == cy - ring || ny == cy + ring;
+                let step = if edge || ring == 0 { 1 } else { 2 * ring };
+                for nx in ((cx - ring)..=(cx + ring)).step_by(step as usize) {
+                    if nx < 0 || nx >= self.cols as isize {
+                        continue;
+                    }
+                    let cell_index = ny as usize * self.cols + nx as usize;
+                    let range =
+                        self.starts[cell_index] as usize..self.starts[cell_index + 1] as usize;
+                    for &id in &self.ids[range] {
+                        let ball = &balls[id as usize];
+                        let (dx, dy) = (ball.x - x, ball.y - y);
+                        let squared = dx * dx + dy * dy;
+                        if best.is_none_or(|(_, closest)| squared < closest) {
+                            best = Some((id as usize, squared));
+                        }
+                    }
+                }
+            }
+        }
+        best.map(|(id, _)| id)
+    }
+}
+
+// An overlapping pair: unit normal from the first ball to the second, how
+// fast they were closing along it (0 if already separating) and the kinetic
+// energy restitution took
+pub(crate) struct Contact {
+    pub(crate) normal: (f32, f32),
+    pub(crate) speed: f32,
+    pub(crate) lost: f32,
+}
+
+// Restitution of a collision: the two balls' mixed
+pub(crate) fn mix_restitution(a: &Ball, b: &Ball, rule: sim::MixRule) -> f32 {
+    rule.mix(a.restitution, b.restitution).clamp(0.0, 1.0)
+}
+
+// Kinetic energy a collision at `speed` with `restitution` takes from a pair
+// of reduced mass m_a m_b / (m_a + m_b)
+pub(crate) fn collision_loss(reduced_mass: f32, speed: f32, restitution: f32) -> f32 {
+    0.5 * reduced_mass * speed * speed * (1.0 - restitution * restitution)
+}
+
+// Separate and bounce balls `a` < `b` if they overlap, heating both by
+// `heating` per unit of approach speed. Returns None if they don't touch.
+fn resolve_pair(
+    balls: &mut [Ball],
+    a: usize,
+    b: usize,
+    heating: f32,
+    mix_rule: sim::MixRule,
+) -> Option<Contact> {
+    let (head, tail) = balls.split_at_mut(b);
+    let (first, second) = (&mut head[a], &mut tail[0]);
+    let before = (*first, *second);
+
+    let dx = second.x - first.x;
+    let dy = second.y - first.y;
+    let reach = first.radius + second.radius;
+    let distance2 = dx * dx + dy * dy;
+    if distance2 >= reach * reach {
+        return None;
+    }
+    let distance = distance2.sqrt();
+    // Concentric balls: push them apart horizontally
+    let (nx, ny) = if distance > 0.0 {
+        (dx / distance, dy / distance)
+    } else {
+        (1.0, 0.0)
+    };
+
+    let mass_a = first.radius * first.radius;
+    let mass_b = second.radius * second.radius;
+    let total = mass_a + mass_b;
+
+    // Each ball moves back by its share of the overlap, the lighter one more
+    let overlap = reach - distance;
+    first.x -= nx * overlap * mass_b / total;
+    first.y -= ny * overlap * mass_b / total;
+    second.x += nx * overlap * mass_a / total;
+    second.y += ny * overlap * mass_a / total;
+
+    let approach = (second.vx - first.vx) * nx + (second.vy - first.vy) * ny;
+    let mut lost = 0.0;
+    if approach < 0.0 {
+        // Impulse along the normal (elastic at a mixed restitution of 1)
+        let restitution = mix_restitution(first, second, mix_rule);
+        let impulse = -(1.0 + restitution) * approach * mass_a * mass_b / total;
+        lost = collision_loss(mass_a * mass_b / total, approach, restitution);
+        first.vx -= impulse / mass_a * nx;
+        first.vy -= impulse / mass_a * ny;
+        second.vx += impulse / mass_b * nx;
+        second.vy += impulse / mass_b * ny;
+        if heating > 0.0 {
+            first.temperature -= heating * approach;
+            second.temperature -= heating * approach;
+        }
+    }
+    // Radii or speeds near f32::MAX overflow the products above; leave such
+    // a pair alone rather than poison both balls
+    let finite = [&*first, &*second].iter().all(|ball| {
+        [ball.x, ball.y, ball.vx, ball.vy, ball.temperature]
+            .iter()
+            .all(|v| v.is_finite())
+    });
+    if !finite {
+        (*first, *second) = before;
+        return None;
+    }
+    Some(Contact {
+        normal: (nx, ny),
+        speed: (-approach).max(0.0),
+        lost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, MIN_CELLS};
+    use crate::{Ball, World};
+
+    // A handful of balls in a vast arena get a small grid, and still collide
+    #[test]
+    fn grid_follows_the_balls() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        world.set_size(1e30, 1e30).unwrap();
+        world.set_collisions(true);
+        let a = world.add_ball(1e6, 1e6, 1.0, 0.0, 1.0, 0).unwrap();
+        let b = world.add_ball(1e6 + 3.0, 1e6, -1.0, 0.0, 1.0, 0).unwrap();
+        world.add_ball(5e29, 5e29, 0.0, 0.0, 1.0, 0).unwrap();
+
+        let grid = Grid::new(&world.balls, 2.0);
+        assert!(grid.starts.len() <= MIN_CELLS + 1);
+        world.update();
+        assert!(world.ball(a).unwrap().vx() < 0.0);
+        assert!(world.ball(b).unwrap().vx() > 0.0);
+    }
+
+    #[test]
+    fn empty_grid() {
+        let grid = Grid::new(&[Ball::default()], 10.0);
+        assert_eq!((grid.cols, grid.rows), (1, 1));
+        assert_eq!(grid.neighbors(&Ball::default()).count(), 0);
+    }
+}
@@ -0,0 +1,144 @@
+// Automatic recoloring for crowded scenes. Instead of every split picking a
+// random color, balls are grouped into classes (by generation or by size) and
+// each class gets a color from a golden-angle palette: consecutive hues are
+// 137.5 degrees apart, so any number of classes stays visually distinct.
+//
+// Palette slots go to the classes in the order of their key (smallest
+// generation or radius first) regardless of the key values, a histogram
+// equalization of sorts: two classes always look different, however close
+// their sizes. The class -> color map is rebuilt when a new class shows up or
+// the population changed by more than REAPPLY_CHANGE since the last rebuild;
+// in between, balls are only recolored to their class's current color.
+//
+// Random colors (split children, emitters, presets) can be kept from
+// vanishing into the background: with a minimum contrast set, colors too
+// close to the flat clear color are re-rolled or lightened (see
+// sim::random_color). A background image isn't taken into account.
+
+use wasm_bindgen::prelude::*;
+
+use crate::background::CLEAR_COLOR;
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::sim::{self, GOLDEN_ANGLE_DEGREES, PALETTE_SATURATION, PALETTE_VALUE};
+use crate::{Ball, World};
+
+const REAPPLY_CHANGE: f32 = 0.1;
+// Size classes are quarter-pixel radii
+const SIZE_STEPS_PER_PIXEL: f32 = 4.0;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutoColor {
+    #[default]
+    Off = 0, // Balls keep their own (random) colors
+    Generation = 1,
+    Size = 2,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AutoColorState {
+    mode: AutoColor,
+    applied_count: usize,
+    classes: Vec<u32>, // Sorted class keys; palette slot = index
+}
+
+impl StateHash for AutoColorState {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u32(self.mode as u32);
+        hash.write(&self.applied_count);
+        hash.write(&self.classes);
+    }
+}
+
+impl AutoColor {
+    fn class(self, ball: &Ball) -> u32 {
+        match self {
+            AutoColor::Off => 0,
+            AutoColor::Generation => ball.generation,
+            AutoColor::Size => (ball.radius * SIZE_STEPS_PER_PIXEL).round() as u32,
+        }
+    }
+}
+
+// Color `index` of the golden-angle palette, as 0xRRGGBB
+pub(crate) fn palette_color(index: usize) -> u32 {
+    let hue = (index as f32 * GOLDEN_ANGLE_DEGREES) % 360.0;
+    sim::hsv_to_rgb(hue, PALETTE_SATURATION, PALETTE_VALUE)
+}
+
+#[wasm_bindgen]
+impl World {
+    // Recolor balls by class (see colors.rs); Off leaves the current colors as they are
+    pub fn set_auto_color(&mut self, mode: AutoColor) {
+        self.auto_color = AutoColorState {
+            mode,
+            ..AutoColorState::default()
+        };
+        self.apply_auto_color(self.frame.wrapping_add(1));
+    }
+
+    pub fn auto_color(&self) -> AutoColor {
+        self.auto_color.mode
+    }
+
+    // Keep random colors at least this contrast ratio (1..=21, WCAG-style)
+    // away from the background; 1, the default, allows any color. 3 or so
+    // rules out the darkest shades on the default dark background.
+    pub fn set_min_color_contrast(&mut self, ratio: f32) {
+        if ratio.is_finite() {
+            self.min_contrast = ratio.clamp(1.0, 21.0);
+        }
+    }
+
+    pub fn min_color_contrast(&self) -> f32 {
+        self.min_contrast
+    }
+}
+
+impl World {
+    // A random color for a new ball, contrasting with the background
+    pub(crate) fn random_color(&mut self) -> u32 {
+        sim::random_color(CLEAR_COLOR, self.min_contrast, &mut self.rng)
+    }
+
+    // Called at the end of update() and when the mode changes
+    pub(crate) fn apply_auto_color(&mut self, stamp: u32) {
+        let mode = self.auto_color.mode;
+        if mode == AutoColor::Off {
+            return;
+        }
+        let count = self.live_count();
+        let state = &self.auto_color;
+        let changed = count.abs_diff(state.applied_count) as f32
+            > REAPPLY_CHANGE * state.applied_count as f32;
+        let unknown = || {
+            self.live_balls()
+                .any(|(_, ball)| state.classes.binary_search(&mode.class(ball)).is_err())
+        };
+        if state.classes.is_empty() || changed || unknown() {
+            let mut classes: Vec<u32> = self
+                .live_balls()
+                .map(|(_, ball)| mode.class(ball))
+                .collect();
+            classes.sort_unstable();
+            classes.dedup();
+            self.auto_color.classes = classes;
+            self.auto_color.applied_count = count;
+        }
+
+        let classes = &self.auto_color.classes;
+        for (ball, modified) in self.balls.iter_mut().zip(self.modified.iter_mut()) {
+            if ball.alive == 0 {
+                continue;
+            }
+            let Ok(slot) = classes.binary_search(&mode.class(ball)) else {
+                continue;
+            };
+            let color = palette_color(slot);
+            if ball.color != color {
+                ball.color = color;
+                *modified = stamp;
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Corner traps. A ball driven exactly into a square corner can end up
+// bouncing off the two walls in turn forever, a few pixels from the corner.
+// With a window set, a ball whose wall hits alternate between the x and y
+// walls TRAP_BOUNCES times in a row, each within `window` frames of the
+// previous one, is nudged out: its velocity is turned by a random angle of
+// up to `jitter` radians, and a CornerTrapped event is emitted.
+//
+// Off by default: the nudge draws from the world's RNG, so turning it on
+// changes how seeded runs unfold.
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::{self, Event, EventKind};
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::sim::{self, SimRng};
+use crate::{Ball, World};
+
+// Alternating hits that make a trap
+const TRAP_BOUNCES: u32 = 4;
+const DEFAULT_JITTER: f32 = 0.2;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Bounces {
+    frame: u32,   // Of the last wall hit
+    x_wall: bool, // Whether it was an x wall (or both, in the corner itself)
+    y_wall: bool,
+    run: u32, // Alternating hits so far
+}
+
+impl StateHash for Bounces {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&(self.frame, self.run));
+        hash.write(&(self.x_wall, self.y_wall));
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CornerTrap {
+    window: u32, // 0 = off
+    jitter: f32,
+    bounces: Vec<Bounces>, // Per slot
+}
+
+impl StateHash for CornerTrap {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&(self.window, self.jitter));
+        hash.write(&self.bounces);
+    }
+}
+
+impl Default for CornerTrap {
+    fn default() -> CornerTrap {
+        CornerTrap {
+            window: 0,
+            jitter: DEFAULT_JITTER,
+            bounces: Vec::new(),
+        }
+    }
+}
+
+impl CornerTrap {
+    // Count a wall hit of ball `id`, after the bounce reflected its velocity,
+    // and nudge the ball once the hits make a trap
+    pub(crate) fn record(
+        &mut self,
+        id: usize,
+        frame: u32,
+        ball: &mut Ball,
+        hits: sim::WallHits,
+        rng: &mut impl SimRng,
+        events: &mut Vec<Event>,
+    ) {
+        if self.window == 0 || !hits.any() {
+            return;
+        }
+        if id >= self.bounces.len() {
+            self.bounces.resize(id + 1, Bounces::default());
+        }
+        let last = self.bounces[id];
+        // Slots are reused: bounces from before the ball was born belong to a removed ball
+        let recent = last.run > 0
+            && last.frame >= ball.born_frame
+            && frame.wrapping_sub(last.frame) <= self.window;
+        let other_wall = (hits.x && last.y_wall) || (hits.y && last.x_wall);
+        let run = if recent && other_wall {
+            last.run + 1
+        } else {
+            1
+        };
+        self.bounces[id] = Bounces {
+            frame,
+            x_wall: hits.x,
+            y_wall: hits.y,
+            run,
+        };
+        if run < TRAP_BOUNCES {
+            return;
+        }
+
+        self.bounces[id].run = 0;
+        let angle = (rng.next_f32() * 2.0 - 1.0) * self.jitter;
+        let (sin, cos) = angle.sin_cos();
+        let (vx, vy) = (ball.vx, ball.vy);
+        ball.vx = vx * cos - vy * sin;
+        ball.vy = vx * sin + vy * cos;
+        events::push_event(
+            events,
+            Event {
+                kind: EventKind::CornerTrapped,
+                id: id as u32,
+                frame,
+                x: ball.x,
+                y: ball.y,
+                value: angle,
+            },
+        );
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Nudge balls caught bouncing between two walls: TRAP_BOUNCES alternating
+    // hits, each within `window` frames of the last, turn the velocity by a
+    // random angle of up to `jitter` radians (clamped to 0..=PI). A window of
+    // 0, the default, turns it off.
+    pub fn set_corner_trap(&mut self, window: u32, jitter: f32) {
+        if !jitter.is_finite() {
+            return;
+        }
+        self.corner_trap.window = window;
+        self.corner_trap.jitter = jitter.clamp(0.0, std::f32::consts::PI);
+        if window == 0 {
+            self.corner_trap.bounces.clear();
+        }
+    }
+
+    pub fn corner_trap_window(&self) -> u32 {
+        self.corner_trap.window
+    }
+
+    pub fn corner_trap_jitter(&self) -> f32 {
+        self.corner_trap.jitter
+    }
+}
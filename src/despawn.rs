@@ -0,0 +1,157 @@
+// Despawn animation. Removed balls (remove_ball, escaping through an open
+// wall, CapPolicy evictions...) normally vanish from one frame to the next.
+// With a style set, a copy of each removed ball lingers for `frames` frames,
+// shrinking to nothing or fading out, so removals look intentional.
+//
+// The copies are purely visual: the slot is freed right away and they take
+// no part in the simulation. They are drawn behind the live balls; hosts with
+// their own renderer read them from despawning().
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::{Clip, ColorMode};
+use crate::srgb::{self, Blending};
+use crate::{Ball, World};
+
+// Oldest animations are dropped beyond this many
+const MAX_DYING: usize = 4096;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DespawnStyle {
+    #[default]
+    Off = 0,
+    Shrink = 1, // Radius eases to 0
+    Fade = 2,   // Full size, opacity eases to 0
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Dying {
+    ball: Ball,
+    removed_frame: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Despawns {
+    style: DespawnStyle,
+    frames: u32,
+    dying: Vec<Dying>,
+}
+
+impl Despawns {
+    // Called for every ball whose slot is freed
+    pub(crate) fn record(&mut self, ball: &Ball, frame: u32) {
+        if self.style == DespawnStyle::Off || self.frames == 0 {
+            return;
+        }
+        if self.dying.len() >= MAX_DYING {
+            self.dying.remove(0);
+        }
+        self.dying.push(Dying {
+            ball: *ball,
+            removed_frame: frame,
+        });
+    }
+
+    // Drop the animations that have run their course by `frame`
+    pub(crate) fn expire(&mut self, frame: u32) {
+        let frames = self.frames;
+        self.dying
+            .retain(|dying| frame.wrapping_sub(dying.removed_frame) < frames);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.dying.clear();
+    }
+
+    // Center, drawn radius and opacity of each dying ball at `frame`
+    fn shapes(&self, frame: u32) -> impl Iterator<Item = (&Ball, f32, f32)> + '_ {
+        self.dying.iter().map(move |dying| {
+            let age = frame.wrapping_sub(dying.removed_frame).min(self.frames);
+            let left = 1.0 - age as f32 / self.frames as f32;
+            let ball = &dying.ball;
+            match self.style {
+                DespawnStyle::Shrink => (ball, ball.radius * left, 1.0),
+                _ => (ball, ball.radius, left),
+            }
+        })
+    }
+
+    // Blend every dying ball into the band. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(
+        &self,
+        buffer: &mut [u8],
+        stride: usize,
+        clip: Clip,
+        frame: u32,
+        color_mode: ColorMode,
+        blending: Blending,
+    ) {
+        for (ball, radius, alpha) in self.shapes(frame) {
+            let color = color_mode.fill(ball);
+            let disc = (ball.x, ball.y, radius);
+            fill_disc(buffer, stride, clip, disc, color, alpha, blending);
+        }
+    }
+}
+
+// A disc blended over what is already drawn
+fn fill_disc(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    (cx, cy, r): (f32, f32, f32),
+    color: u32,
+    alpha: f32,
+    blending: Blending,
+) {
+    if !(r > 0.0 && alpha > 0.0) {
+        return;
+    }
+    let rgb = srgb::rgb(color);
+    let x_min = (cx - r).max(clip.x0 as f32) as i64;
+    let x_max = (cx + r).min(clip.x1 as f32) as i64;
+    let y_min = (cy - r).max(clip.y0 as f32) as i64;
+    let y_max = (cy + r).min(clip.y1 as f32) as i64;
+    for py in y_min..y_max {
+        let row = (py as usize - clip.y0) * stride;
+        for px in x_min..x_max {
+            let (dx, dy) = (px as f32 - cx, py as f32 - cy);
+            if dx * dx + dy * dy > r * r {
+                continue;
+            }
+            let idx = row + px as usize * 4;
+            blending.blend(&mut buffer[idx..idx + 4], rgb, alpha);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Animate removals for `frames` frames (Off or 0 frames turns it off and
+    // drops the running animations)
+    pub fn set_despawn_animation(&mut self, style: DespawnStyle, frames: u32) {
+        self.despawns.style = style;
+        self.despawns.frames = frames;
+        if style == DespawnStyle::Off || frames == 0 {
+            self.despawns.dying = Vec::new();
+        }
+    }
+
+    pub fn despawn_animation(&self) -> DespawnStyle {
+        self.despawns.style
+    }
+
+    pub fn despawn_frames(&self) -> u32 {
+        self.despawns.frames
+    }
+
+    // The balls currently animating out as x, y, radius, opacity (0..=1)
+    // quadruples, radius and opacity already eased for this frame
+    pub fn despawning(&self) -> Vec<f32> {
+        self.despawns
+            .shapes(self.frame)
+            .flat_map(|(ball, radius, alpha)| [ball.x, ball.y, radius, alpha])
+            .collect()
+    }
+}
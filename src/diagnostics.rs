@@ -0,0 +1,59 @@
+// Build information and panic reporting, so bug reports from the browser come
+// with a readable stack and the exact build configuration.
+
+use wasm_bindgen::prelude::*;
+
+// Cargo features this build was compiled with
+const FEATURES: &[(&str, bool)] = &[
+    ("std", cfg!(feature = "std")),
+    ("parallel", cfg!(feature = "parallel")),
+    ("worker", cfg!(feature = "worker")),
+    ("web", cfg!(feature = "web")),
+    ("ffi", cfg!(feature = "ffi")),
+    ("gpu", cfg!(feature = "gpu")),
+    ("fixed", cfg!(feature = "fixed")),
+    ("debug", cfg!(feature = "debug")),
+    ("python", cfg!(feature = "python")),
+    ("scene", cfg!(feature = "scene")),
+    ("desktop", cfg!(feature = "desktop")),
+    ("log", cfg!(feature = "log")),
+    ("console_log", cfg!(feature = "console_log")),
+];
+
+// Send Rust panics to console.error with their message and location instead
+// of an opaque "unreachable executed". Safe to call more than once.
+#[wasm_bindgen]
+pub fn init_diagnostics() {
+    console_error_panic_hook::set_once();
+}
+
+#[wasm_bindgen]
+pub fn crate_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+#[wasm_bindgen]
+pub fn enabled_features() -> Vec<String> {
+    FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+// One line for bug reports, e.g. "bouncing_balls 0.1.0 (wasm32, debug) features: std, worker"
+#[wasm_bindgen]
+pub fn build_info() -> String {
+    format!(
+        "{} {} ({}, {}) features: {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::ARCH,
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        },
+        enabled_features().join(", ")
+    )
+}
@@ -0,0 +1,236 @@
+// WorldDriver: runs a World's simulation loop inside a dedicated worker.
+//
+// Load the wasm module in a worker, create a World there and hand it to
+// `WorldDriver::new`, then call `listen()`. Every tick the driver steps the
+// world, renders it and posts `{ type: "frame", frame, width, height, pixels }`
+// to the main thread, transferring `pixels` (a Uint8ClampedArray ready for
+// `new ImageData(pixels, width, height)`). Hosts that prefer shared memory can
+// instead call `world.set_mirror(...)` before handing the world over.
+//
+// Messages understood by `handle_message` (and by the listener):
+//   { type: "start", fps }             start or restart the loop
+//   { type: "stop" }                   stop the loop
+//   { type: "resize", width, height }  change the framebuffer size
+//   { type: "set", key, value }        keys: "draw_order", "mask_cache", "seed"
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Object, Reflect, Uint8ClampedArray};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{console, DedicatedWorkerGlobalScope, MessageEvent};
+
+use crate::{DrawOrder, World};
+
+struct Driver {
+    world: World,
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    interval: Option<i32>,
+    tick: Option<Closure<dyn FnMut()>>,
+}
+
+#[wasm_bindgen]
+pub struct WorldDriver {
+    inner: Rc<RefCell<Driver>>,
+    on_message: Option<Closure<dyn FnMut(MessageEvent)>>,
+}
+
+fn worker_scope() -> Result<DedicatedWorkerGlobalScope, JsValue> {
+    js_sys::global()
+        .dyn_into::<DedicatedWorkerGlobalScope>()
+        .map_err(|_| JsError::new("WorldDriver must run inside a dedicated worker").into())
+}
+
+// Zeroed RGBA pixels, or an error if that many bytes can't be allocated
+fn framebuffer(width: usize, height: usize) -> Result<Vec<u8>, JsValue> {
+    let bytes = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(4));
+    let mut pixels = Vec::new();
+    match bytes {
+        Some(bytes) if pixels.try_reserve_exact(bytes).is_ok() => {
+            pixels.extend(std::iter::repeat_n(0, bytes));
+            Ok(pixels)
+        }
+        _ => Err(JsError::new(&format!("no memory for a {width}x{height} framebuffer")).into()),
+    }
+}
+
+fn get_f64(data: &JsValue, key: &str) -> Option<f64> {
+    Reflect::get(data, &JsValue::from_str(key)).ok()?.as_f64()
+}
+
+#[wasm_bindgen]
+impl WorldDriver {
+    // Throws if a width x height framebuffer doesn't fit in memory
+    #[wasm_bindgen(constructor)]
+    pub fn new(world: World, width: usize, height: usize) -> Result<WorldDriver, JsValue> {
+        Ok(WorldDriver {
+            inner: Rc::new(RefCell::new(Driver {
+                world,
+                width,
+                height,
+                pixels: framebuffer(width, height)?,
+                interval: None,
+                tick: None,
+            })),
+            on_message: None,
+        })
+    }
+
+    pub fn start(&mut self, fps: f64) -> Result<(), JsValue> {
+        start(&self.inner, fps)
+    }
+
+    pub fn stop(&mut self) {
+        stop(&self.inner);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.inner.borrow().interval.is_some()
+    }
+
+    // Apply one control message (see the module docs for the accepted shapes)
+    pub fn handle_message(&mut self, data: &JsValue) -> Result<(), JsValue> {
+        handle_message(&self.inner, data)
+    }
+
+    // Route the worker's incoming messages to handle_message
+    pub fn listen(&mut self) -> Result<(), JsValue> {
+        let scope = worker_scope()?;
+        let inner = Rc::clone(&self.inner);
+        let closure = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Err(error) = handle_message(&inner, &event.data()) {
+                console::error_1(&error);
+            }
+        });
+        scope.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+        self.on_message = Some(closure);
+        Ok(())
+    }
+
+    // Run a single update + render + post, e.g. to drive the loop from elsewhere
+    pub fn step(&mut self) -> Result<(), JsValue> {
+        tick(&self.inner)
+    }
+}
+
+impl Drop for WorldDriver {
+    fn drop(&mut self) {
+        stop(&self.inner);
+        if self.on_message.is_some() {
+            if let Ok(scope) = worker_scope() {
+                scope.set_onmessage(None);
+            }
+        }
+    }
+}
+
+fn start(inner: &Rc<RefCell<Driver>>, fps: f64) -> Result<(), JsValue> {
+    if !(fps > 0.0 && fps.is_finite()) {
+        return Err(JsError::new("fps must be positive").into());
+    }
+    stop(inner);
+    let scope = worker_scope()?;
+    let weak = Rc::downgrade(inner);
+    let closure = Closure::<dyn FnMut()>::new(move || {
+        if let Some(inner) = weak.upgrade() {
+            if let Err(error) = tick(&inner) {
+                console::error_1(&error);
+                stop(&inner);
+            }
+        }
+    });
+    let interval = scope.set_interval_with_callback_and_timeout_and_arguments_0(
+        closure.as_ref().unchecked_ref(),
+        (1000.0 / fps).round() as i32,
+    )?;
+    let mut driver = inner.borrow_mut();
+    driver.interval = Some(interval);
+    driver.tick = Some(closure);
+    Ok(())
+}
+
+fn stop(inner: &Rc<RefCell<Driver>>) {
+    let mut driver = inner.borrow_mut();
+    if let Some(interval) = driver.interval.take() {
+        if let Ok(scope) = worker_scope() {
+            scope.clear_interval_with_handle(interval);
+        }
+    }
+    driver.tick = None;
+}
+
+fn tick(inner: &Rc<RefCell<Driver>>) -> Result<(), JsValue> {
+    let mut driver = inner.borrow_mut();
+    let Driver {
+        world,
+        width,
+        height,
+        pixels,
+        ..
+    } = &mut *driver;
+    world.update();
+    world.render_to_buffer(pixels, *width, *height, None)?;
+
+    // A fresh JS-owned copy per frame, whose buffer is transferred (not cloned) to the main thread
+    let frame_pixels = Uint8ClampedArray::new_with_length(pixels.len() as u32);
+    frame_pixels.copy_from(pixels);
+    let message = Object::new();
+    Reflect::set(&message, &"type".into(), &"frame".into())?;
+    Reflect::set(&message, &"frame".into(), &world.frame().into())?;
+    Reflect::set(&message, &"width".into(), &(*width as u32).into())?;
+    Reflect::set(&message, &"height".into(), &(*height as u32).into())?;
+    Reflect::set(&message, &"pixels".into(), &frame_pixels)?;
+    let transfer = Array::of1(&frame_pixels.buffer());
+    worker_scope()?.post_message_with_transfer(&message, &transfer)
+}
+
+fn handle_message(inner: &Rc<RefCell<Driver>>, data: &JsValue) -> Result<(), JsValue> {
+    let kind = Reflect::get(data, &"type".into())?
+        .as_string()
+        .unwrap_or_default();
+    match kind.as_str() {
+        "start" => start(inner, get_f64(data, "fps").unwrap_or(60.0)),
+        "stop" => {
+            stop(inner);
+            Ok(())
+        }
+        "resize" => {
+            let (Some(width), Some(height)) = (get_f64(data, "width"), get_f64(data, "height"))
+            else {
+                return Err(JsError::new("resize needs numeric width and height").into());
+            };
+            let (width, height) = (width as usize, height as usize);
+            let pixels = framebuffer(width, height)?;
+            let mut driver = inner.borrow_mut();
+            driver.width = width;
+            driver.height = height;
+            driver.pixels = pixels;
+            Ok(())
+        }
+        "set" => {
+            let key = Reflect::get(data, &"key".into())?
+                .as_string()
+                .unwrap_or_default();
+            let value = Reflect::get(data, &"value".into())?;
+            let mut driver = inner.borrow_mut();
+            let world = &mut driver.world;
+            match key.as_str() {
+                "draw_order" => world.set_draw_order(match value.as_f64() {
+                    Some(1.0) => DrawOrder::ByRadiusDesc,
+                    Some(2.0) => DrawOrder::ByY,
+                    _ => DrawOrder::Insertion,
+                }),
+                "mask_cache" => world.set_mask_cache(value.is_truthy()),
+                "seed" => world.set_seed(value.as_f64().unwrap_or(0.0) as u32),
+                _ => return Err(JsError::new(&format!("unknown setting {key:?}")).into()),
+            }
+            Ok(())
+        }
+        _ => Err(JsError::new(&format!("unknown message type {kind:?}")).into()),
+    }
+}
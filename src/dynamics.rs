@@ -0,0 +1,240 @@
+// Integrator choice, global acceleration (gravity, tilt and per-frame input
+// forces), attractors, sub-stepping, the splitting switch, and spin: wall
+// friction and the Magnus effect.
+//
+// An attractor pulls every ball towards a point with an acceleration of
+// strength / d^2, softened near the center so a ball passing through it
+// doesn't get flung away: a = strength * d / (d^2 + s^2)^1.5. A negative
+// strength repels.
+//
+// Balls start without spin. Wall friction trades a ball's sliding along a
+// wall for spin and back (sim::wall_friction); with a Magnus coefficient k a
+// spinning ball is pushed sideways by k * spin * |v|, so it curves in flight.
+//
+// Bounces are elastic unless given a restitution below 1. Walls (and
+// obstacles) have one restitution and one friction; every ball carries its
+// own of each, default 1, so rubbery and clay-like balls can share an arena.
+// The mix rule combines the ball's coefficient with the wall's, or in a
+// collision the two balls' restitutions (ball-ball contacts stay frictionless).
+
+use wasm_bindgen::prelude::*;
+
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::{Integrator, MixRule, World};
+
+const MAX_SUBSTEPS: u32 = 64;
+// Gravity strength tilt() uses while gravity is off (the rain preset's)
+pub(crate) const DEFAULT_TILT_GRAVITY: f32 = 0.2;
+const ATTRACTOR_SOFTENING: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Attractor {
+    x: f32,
+    y: f32,
+    strength: f32,
+}
+
+impl StateHash for Attractor {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        for value in [self.x, self.y, self.strength] {
+            hash.write_f32(value);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    // Constant acceleration applied to every ball, in pixels/frame^2 (0, 0 by default)
+    pub fn set_gravity(&mut self, x: f32, y: f32) {
+        if x.is_finite() && y.is_finite() {
+            self.gravity = (x, y);
+        }
+    }
+
+    pub fn gravity_x(&self) -> f32 {
+        self.gravity.0
+    }
+
+    pub fn gravity_y(&self) -> f32 {
+        self.gravity.1
+    }
+
+    // Extra acceleration (pixels/frame^2) for the next update() only, on top
+    // of gravity; calls before it add up. Meant to be fed every frame from
+    // held keys or a gamepad stick.
+    pub fn apply_global_force(&mut self, fx: f32, fy: f32) {
+        if fx.is_finite() && fy.is_finite() {
+            self.force.0 += fx;
+            self.force.1 += fy;
+        }
+    }
+
+    // "Tilt the box": point gravity `angle` radians away from straight down
+    // (positive = towards the right wall), keeping its strength, or using
+    // DEFAULT_TILT_GRAVITY while gravity is off
+    pub fn tilt(&mut self, angle: f32) {
+        if !angle.is_finite() {
+            return;
+        }
+        let (gx, gy) = self.gravity;
+        let strength = (gx * gx + gy * gy).sqrt();
+        let strength = if strength > 0.0 {
+            strength
+        } else {
+            DEFAULT_TILT_GRAVITY
+        };
+        let (sin, cos) = angle.sin_cos();
+        self.gravity = (strength * sin, strength * cos);
+    }
+
+    // Split every update() into `substeps` equal steps (1..=64, default 1).
+    // Velocities stay in pixels per frame; wall bounces and splits are
+    // checked in each sub-step.
+    pub fn set_substeps(&mut self, substeps: u32) {
+        self.substeps = substeps.clamp(1, MAX_SUBSTEPS);
+    }
+
+    pub fn substeps(&self) -> u32 {
+        self.substeps
+    }
+
+    // Add a point attractor and return its index. Ignored (None) unless
+    // every argument is finite.
+    pub fn add_attractor(&mut self, x: f32, y: f32, strength: f32) -> Option<u32> {
+        if !(x.is_finite() && y.is_finite() && strength.is_finite()) {
+            return None;
+        }
+        self.attractors.push(Attractor { x, y, strength });
+        Some((self.attractors.len() - 1) as u32)
+    }
+
+    pub fn clear_attractors(&mut self) {
+        self.attractors.clear();
+    }
+
+    pub fn attractor_count(&self) -> usize {
+        self.attractors.len()
+    }
+
+    // With splitting off, balls still bounce off the walls but never split
+    pub fn set_splitting(&mut self, enabled: bool) {
+        self.splitting = enabled;
+    }
+
+    pub fn splitting(&self) -> bool {
+        self.splitting
+    }
+
+    // Share (0..=1, default 0) of a ball's slip against a wall that friction
+    // removes on each bounce
+    pub fn set_wall_friction(&mut self, friction: f32) {
+        if friction.is_finite() {
+            self.wall_friction = friction.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn wall_friction(&self) -> f32 {
+        self.wall_friction
+    }
+
+    // Share of the speed into a wall or obstacle a ball keeps after bouncing
+    // (0..=1, default 1 = elastic), mixed with the ball's own restitution
+    pub fn set_wall_restitution(&mut self, restitution: f32) {
+        if restitution.is_finite() {
+            self.wall_restitution = restitution.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn wall_restitution(&self) -> f32 {
+        self.wall_restitution
+    }
+
+    // How per-ball restitution and friction combine with the walls' (and
+    // restitution with the other ball's in a collision)
+    pub fn set_mix_rule(&mut self, rule: MixRule) {
+        self.mix_rule = rule;
+    }
+
+    pub fn mix_rule(&self) -> MixRule {
+        self.mix_rule
+    }
+
+    // Give a ball its own restitution (clamped to 0..=1) and friction
+    // (clamped to >= 0), inherited by its split children. Returns false if
+    // the id is not a live ball.
+    pub fn set_material(&mut self, id: u32, restitution: f32, friction: f32) -> bool {
+        if !(restitution.is_finite() && friction.is_finite()) {
+            return false;
+        }
+        match self
+            .balls
+            .get_mut(id as usize)
+            .filter(|ball| ball.alive != 0)
+        {
+            Some(ball) => {
+                ball.restitution = restitution.clamp(0.0, 1.0);
+                ball.friction = friction.max(0.0);
+                self.touch(id as usize);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Magnus coefficient (default 0 = off); negative values curve the other way
+    pub fn set_magnus(&mut self, magnus: f32) {
+        if magnus.is_finite() {
+            self.magnus = magnus;
+        }
+    }
+
+    pub fn magnus(&self) -> f32 {
+        self.magnus
+    }
+}
+
+impl World {
+    // Kick every live ball's velocity by the attractors' pull over one (sub-)step
+    pub(crate) fn attract(&mut self, stamp: u32) {
+        if self.attractors.is_empty() {
+            return;
+        }
+        let dt = 1.0 / self.substeps as f32;
+        let soft2 = ATTRACTOR_SOFTENING * ATTRACTOR_SOFTENING;
+        for (ball, modified) in self.balls.iter_mut().zip(self.modified.iter_mut()) {
+            if ball.alive == 0 {
+                continue;
+            }
+            for attractor in &self.attractors {
+                let dx = attractor.x - ball.x;
+                let dy = attractor.y - ball.y;
+                let d2 = dx * dx + dy * dy + soft2;
+                let scale = attractor.strength / (d2 * d2.sqrt()) * dt;
+                ball.vx += dx * scale;
+                ball.vy += dy * scale;
+            }
+            *modified = stamp;
+        }
+    }
+
+    // Potential per unit mass at (x, y): -strength / sqrt(d^2 + s^2) summed
+    pub(crate) fn attractor_potential(&self, x: f32, y: f32) -> f64 {
+        let soft2 = (ATTRACTOR_SOFTENING as f64).powi(2);
+        self.attractors
+            .iter()
+            .map(|attractor| {
+                let dx = (attractor.x - x) as f64;
+                let dy = (attractor.y - y) as f64;
+                -attractor.strength as f64 / (dx * dx + dy * dy + soft2).sqrt()
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,130 @@
+// Edit mode, for level editors built on the crate. While it is on, update()
+// freezes the physics (no movement, splits, emitters or frame advance) but
+// every editing call keeps working and rendering shows the edits, so a host
+// can keep its usual update() + render loop running.
+//
+// Besides add_ball / remove_ball and the obstacle constructors, editors get
+// drag_ball, move_obstacle, remove_obstacle and obstacle_at for picking, and a
+// ghost obstacle: a preview drawn translucent over the scene (but ignored by
+// the physics) while an obstacle is being placed.
+
+use wasm_bindgen::prelude::*;
+
+use crate::history::Edit;
+use crate::obstacles::{Obstacle, ObstacleShape};
+use crate::World;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Editor {
+    pub(crate) enabled: bool,
+    pub(crate) ghost: Option<Obstacle>,
+}
+
+#[wasm_bindgen]
+impl World {
+    pub fn set_edit_mode(&mut self, enabled: bool) {
+        self.editor.enabled = enabled;
+    }
+
+    pub fn edit_mode(&self) -> bool {
+        self.editor.enabled
+    }
+
+    // Move a ball to (x, y), keeping its velocity. Returns false if the id
+    // is not a live ball or the position isn't finite.
+    pub fn drag_ball(&mut self, id: u32, x: f32, y: f32) -> bool {
+        if !(x.is_finite() && y.is_finite()) {
+            return false;
+        }
+        match self
+            .balls
+            .get_mut(id as usize)
+            .filter(|ball| ball.alive != 0)
+        {
+            Some(ball) => {
+                let from = (ball.x, ball.y);
+                ball.x = x;
+                ball.y = y;
+                self.touch(id as usize);
+                self.record_edit(Edit::MoveBall {
+                    id,
+                    from,
+                    to: (x, y),
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Move an obstacle's center (circles, polygons) or top-left corner
+    // (rectangles)
+    pub fn move_obstacle(&mut self, index: u32, x: f32, y: f32) -> bool {
+        if !(x.is_finite() && y.is_finite()) {
+            return false;
+        }
+        match self.obstacles.get_mut(index as usize) {
+            Some(obstacle) => {
+                let from = *obstacle;
+                obstacle.move_to(x, y);
+                let to = *obstacle;
+                self.record_edit(Edit::MoveObstacle {
+                    index: index as usize,
+                    from: Box::new(from),
+                    to: Box::new(to),
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Later obstacles move down one index
+    pub fn remove_obstacle(&mut self, index: u32) -> bool {
+        let index = index as usize;
+        if index < self.obstacles.len() {
+            let obstacle = self.obstacles.remove(index);
+            self.record_edit(Edit::RemoveObstacle { index, obstacle });
+            true
+        } else {
+            false
+        }
+    }
+
+    // Topmost (most recently added) obstacle containing the point
+    pub fn obstacle_at(&self, x: f32, y: f32) -> Option<u32> {
+        self.obstacles
+            .iter()
+            .rposition(|obstacle| obstacle.contains(x, y))
+            .map(|index| index as u32)
+    }
+
+    // Show a circle obstacle preview. Ignored unless the arguments are finite
+    // and the radius positive.
+    pub fn set_ghost_circle(&mut self, x: f32, y: f32, radius: f32) {
+        if [x, y, radius].iter().all(|value| value.is_finite()) && radius > 0.0 {
+            self.editor.ghost = Some(Obstacle::new(ObstacleShape::Circle { x, y, radius }));
+        }
+    }
+
+    // Show a rectangle obstacle preview, top-left corner at (x, y)
+    pub fn set_ghost_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let finite = [x, y, width, height].iter().all(|value| value.is_finite());
+        if finite && width > 0.0 && height > 0.0 {
+            self.editor.ghost = Some(Obstacle::new(ObstacleShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            }));
+        }
+    }
+
+    pub fn clear_ghost(&mut self) {
+        self.editor.ghost = None;
+    }
+
+    pub fn has_ghost(&self) -> bool {
+        self.editor.ghost.is_some()
+    }
+}
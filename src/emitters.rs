@@ -0,0 +1,159 @@
+// Emitters add new balls at the start of every update(), e.g. rain falling
+// from the top edge. Each one spawns `rate` balls per frame on average
+// (fractions carry over), spread along a horizontal line and with a random
+// velocity jitter. Spawning stops while the world is at max_balls.
+
+use rand::Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::{Ball, World};
+
+// Per emitter and frame; beyond this f32 `pending` can't count down anyway
+const MAX_RATE: f32 = 4096.0;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emitter {
+    pub x: f32, // Center of the spawn line
+    pub y: f32,
+    pub width: f32, // Length of the spawn line (0 = a point)
+    pub vx: f32,
+    pub vy: f32,
+    pub jitter: f32, // Up to +/- this much is added to each velocity component
+    pub radius: f32,
+    pub rate: f32,  // Balls per frame
+    pub color: u32, // 0xRRGGBB, ignored when random_color is set
+    pub random_color: bool,
+    pending: f32,
+}
+
+impl StateHash for Emitter {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        for value in [
+            self.x,
+            self.y,
+            self.width,
+            self.vx,
+            self.vy,
+            self.jitter,
+            self.radius,
+            self.rate,
+            self.pending,
+        ] {
+            hash.write_f32(value);
+        }
+        hash.write_u32(self.color);
+        hash.write(&self.random_color);
+    }
+}
+
+#[wasm_bindgen]
+impl Emitter {
+    // A point emitter without jitter, spawning random colors
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f32, y: f32, vx: f32, vy: f32, radius: f32, rate: f32) -> Emitter {
+        Emitter {
+            x,
+            y,
+            width: 0.0,
+            vx,
+            vy,
+            jitter: 0.0,
+            radius,
+            rate,
+            color: 0,
+            random_color: true,
+            pending: 0.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Returns the emitter's index (indices shift only on clear_emitters). The
+    // rate is capped at 4096 balls per frame; NaN means 0.
+    pub fn add_emitter(&mut self, emitter: &Emitter) -> u32 {
+        let rate = if emitter.rate.is_nan() {
+            0.0
+        } else {
+            emitter.rate.min(MAX_RATE)
+        };
+        self.emitters.push(Emitter {
+            rate,
+            pending: 0.0,
+            ..*emitter
+        });
+        (self.emitters.len() - 1) as u32
+    }
+
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+
+    pub fn emitter_count(&self) -> usize {
+        self.emitters.len()
+    }
+}
+
+impl World {
+    pub(crate) fn emit(&mut self, stamp: u32) {
+        for index in 0..self.emitters.len() {
+            let emitter = &mut self.emitters[index];
+            if !(emitter.rate > 0.0 && emitter.radius > 0.0) {
+                continue;
+            }
+            emitter.pending += emitter.rate;
+            while self.emitters[index].pending >= 1.0 {
+                if self.live_count() >= self.max_balls {
+                    // Don't save up a burst for when room frees up
+                    self.emitters[index].pending = 0.0;
+                    break;
+                }
+                self.emitters[index].pending -= 1.0;
+                let ball = self.spawn(self.emitters[index]);
+                self.energy.record_emitted(&ball);
+                self.insert_child(ball, stamp);
+            }
+        }
+    }
+
+    fn spawn(&mut self, emitter: Emitter) -> Ball {
+        let offset = (self.rng.gen::<f32>() - 0.5) * emitter.width;
+        let jitter_x = (self.rng.gen::<f32>() - 0.5) * 2.0 * emitter.jitter;
+        let jitter_y = (self.rng.gen::<f32>() - 0.5) * 2.0 * emitter.jitter;
+        let color = if emitter.random_color {
+            self.random_color()
+        } else {
+            emitter.color & 0xFFFFFF
+        };
+        Ball::new(
+            emitter.x + offset,
+            emitter.y,
+            emitter.vx + jitter_x,
+            emitter.vy + jitter_y,
+            emitter.radius,
+            color,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Emitter, World};
+
+    // An infinite rate is capped and a NaN one (or a NaN radius) spawns
+    // nothing, so neither hangs update()
+    #[test]
+    fn broken_rates_are_clamped() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 10_000, 0.7, 1).unwrap();
+        world.add_emitter(&Emitter::new(10.0, 10.0, 1.0, 1.0, 2.0, f32::NAN));
+        world.add_emitter(&Emitter::new(10.0, 10.0, 1.0, 1.0, f32::NAN, 5.0));
+        world.update();
+        assert_eq!(world.live_count(), 0);
+
+        world.add_emitter(&Emitter::new(10.0, 10.0, 1.0, 1.0, 2.0, f32::INFINITY));
+        world.update();
+        assert_eq!(world.live_count(), 4096);
+    }
+}
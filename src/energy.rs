@@ -0,0 +1,131 @@
+// Energy bookkeeping. A ball's mass is its area (radius^2, the constant pi
+// dropped), so kinetic energy is 0.5 * r^2 * |v|^2; a spinning ball, a solid
+// disc, adds 0.25 * r^4 * spin^2. Wall bounces and ball collisions are
+// elastic unless a restitution below 1 is set, so apart from gravity and
+// attractors (both counted as potential energy) only splits, emitters,
+// restitution and water change the total: a split parent shrinks and its
+// child gets a jittered copy of its velocity (or, with Momentum kinematics,
+// the two share the parent's momentum). The ledger adds up those changes for
+// the current frame. Wall friction (which only ever removes energy),
+// buoyancy and shockwave kicks aren't itemized.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Ball, SplitKinematics, World};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EnergyReport {
+    pub frame: u32,               // Frame the per-frame numbers belong to
+    pub kinetic: f64,             // Sum of 0.5 * r^2 * |v|^2 over live balls
+    pub rotational: f64,          // Sum of 0.25 * r^4 * spin^2 over live balls
+    pub potential: f64,           // Gravity (relative to the arena origin) and attractor potential
+    pub split_added: f64,         // Gained by splits this frame
+    pub split_removed: f64,       // Lost by splits this frame
+    pub emitted: f64,             // Brought in by emitters this frame
+    pub restitution_removed: f64, // Lost to restitution in bounces and collisions this frame (elastic: 0)
+    pub drag_removed: f64,        // Lost to water drag this frame
+}
+
+#[wasm_bindgen]
+impl EnergyReport {
+    pub fn total(&self) -> f64 {
+        self.kinetic + self.rotational + self.potential
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Ledger {
+    frame: u32,
+    split_added: f64,
+    split_removed: f64,
+    emitted: f64,
+    restitution_removed: f64,
+    drag_removed: f64,
+}
+
+impl Ledger {
+    pub(crate) fn begin_frame(&mut self, frame: u32) {
+        *self = Ledger {
+            frame,
+            ..Ledger::default()
+        };
+    }
+
+    // `parent` is the ball after it split; before the split it had the same
+    // spin, a radius of child.radius / split_ratio and, with Visual
+    // kinematics, the same velocity (with Momentum, the pair's mean one)
+    pub(crate) fn record_split(
+        &mut self,
+        parent: &Ball,
+        child: &Ball,
+        split_ratio: f32,
+        kinematics: SplitKinematics,
+    ) {
+        let before_radius = child.radius as f64 / split_ratio as f64;
+        let mut velocity = (parent.vx as f64, parent.vy as f64);
+        if kinematics == SplitKinematics::Momentum {
+            velocity.0 = 0.5 * (velocity.0 + child.vx as f64);
+            velocity.1 = 0.5 * (velocity.1 + child.vy as f64);
+        }
+        let speed2 = velocity.0.powi(2) + velocity.1.powi(2);
+        let before = 0.5 * before_radius.powi(2) * speed2
+            + 0.25 * before_radius.powi(4) * (parent.spin as f64).powi(2);
+        let delta =
+            kinetic(parent) + rotational(parent) + kinetic(child) + rotational(child) - before;
+        if delta >= 0.0 {
+            self.split_added += delta;
+        } else {
+            self.split_removed -= delta;
+        }
+    }
+
+    pub(crate) fn record_emitted(&mut self, ball: &Ball) {
+        self.emitted += kinetic(ball);
+    }
+
+    // Kinetic energy an inelastic bounce or collision took
+    pub(crate) fn record_restitution(&mut self, lost: f32) {
+        self.restitution_removed += lost as f64;
+    }
+
+    // Kinetic energy water drag took
+    pub(crate) fn record_drag(&mut self, lost: f64) {
+        self.drag_removed += lost;
+    }
+}
+
+pub(crate) fn kinetic(ball: &Ball) -> f64 {
+    let mass = (ball.radius as f64).powi(2);
+    0.5 * mass * ((ball.vx as f64).powi(2) + (ball.vy as f64).powi(2))
+}
+
+// Moment of inertia of a disc is m r^2 / 2
+pub(crate) fn rotational(ball: &Ball) -> f64 {
+    0.25 * (ball.radius as f64).powi(4) * (ball.spin as f64).powi(2)
+}
+
+#[wasm_bindgen]
+impl World {
+    // Energy of the current state, plus what the last update() added or removed
+    pub fn energy_report(&self) -> EnergyReport {
+        let (gx, gy) = (self.gravity.0 as f64, self.gravity.1 as f64);
+        let mut report = EnergyReport {
+            frame: self.energy.frame,
+            split_added: self.energy.split_added,
+            split_removed: self.energy.split_removed,
+            emitted: self.energy.emitted,
+            restitution_removed: self.energy.restitution_removed,
+            drag_removed: self.energy.drag_removed,
+            ..EnergyReport::default()
+        };
+        for (_, ball) in self.live_balls() {
+            let mass = (ball.radius as f64).powi(2);
+            report.kinetic += kinetic(ball);
+            report.rotational += rotational(ball);
+            report.potential -= mass * (gx * ball.x as f64 + gy * ball.y as f64);
+            report.potential += mass * self.attractor_potential(ball.x, ball.y);
+        }
+        report
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+// Errors returned by fallible World methods. On the JS side they are thrown
+// as regular `Error` objects carrying the Display message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorldError {
+    InvalidDimensions { width: f32, height: f32 },
+    InvalidMaxBalls,
+    InvalidSplitRatio(f32),
+    InvalidMinRadius(f32),
+    InvalidBall,
+    WorldFull { max_balls: usize },
+    BufferSizeMismatch { expected: usize, actual: usize },
+    InvalidStride { stride: usize, min: usize },
+    InvalidMirrorRegion { byte_offset: u32, byte_length: u32 },
+    SnapshotTruncated,
+    SnapshotBadMagic,
+    SnapshotUnknownKind(u8),
+    SnapshotBaseMismatch { base_frame: u32, frame: u32 },
+    SnapshotBadIndex(u32),
+    GpuUnavailable(String),
+    UnknownPreset(String),
+    InvalidScene(String),
+    InvalidTimeline(String),
+    UnknownCheckpoint(String),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::InvalidDimensions { width, height } => write!(
+                f,
+                "world dimensions must be positive and finite, got {width} x {height}"
+            ),
+            WorldError::InvalidMaxBalls => write!(f, "max_balls must be at least 1"),
+            WorldError::InvalidSplitRatio(ratio) => {
+                write!(
+                    f,
+                    "split_ratio must be strictly between 0 and 1, got {ratio}"
+                )
+            }
+            WorldError::InvalidMinRadius(radius) => {
+                write!(f, "min_radius must be positive and finite, got {radius}")
+            }
+            WorldError::InvalidBall => write!(
+                f,
+                "a ball needs a finite position and velocity and a positive, finite radius"
+            ),
+            WorldError::WorldFull { max_balls } => {
+                write!(f, "world already holds max_balls ({max_balls}) balls")
+            }
+            WorldError::BufferSizeMismatch { expected, actual } => write!(
+                f,
+                "pixel buffer has {actual} bytes but the surface needs {expected}"
+            ),
+            WorldError::InvalidStride { stride, min } => {
+                write!(f, "row stride {stride} is smaller than width * 4 = {min}")
+            }
+            WorldError::InvalidMirrorRegion {
+                byte_offset,
+                byte_length,
+            } => write!(
+                f,
+                "mirror region at {byte_offset} (+{byte_length} bytes) must be 4-byte aligned, \
+                 hold at least the header and fit inside the SharedArrayBuffer"
+            ),
+            WorldError::SnapshotTruncated => write!(f, "snapshot is truncated"),
+            WorldError::SnapshotBadMagic => write!(f, "not a bouncing_balls snapshot"),
+            WorldError::SnapshotUnknownKind(kind) => write!(f, "unknown snapshot kind {kind}"),
+            WorldError::SnapshotBaseMismatch { base_frame, frame } => write!(
+                f,
+                "delta is based on frame {base_frame} but this world is at frame {frame}"
+            ),
+            WorldError::SnapshotBadIndex(index) => {
+                write!(f, "snapshot references ball {index} beyond its ball count")
+            }
+            WorldError::GpuUnavailable(reason) => write!(f, "GPU backend unavailable: {reason}"),
+            WorldError::UnknownPreset(name) => write!(f, "unknown preset {name:?}"),
+            WorldError::InvalidScene(reason) => write!(f, "invalid scene: {reason}"),
+            WorldError::InvalidTimeline(reason) => write!(f, "invalid timeline: {reason}"),
+            WorldError::UnknownCheckpoint(name) => write!(f, "no checkpoint named {name:?}"),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+impl From<WorldError> for JsValue {
+    fn from(error: WorldError) -> JsValue {
+        JsError::new(&error.to_string()).into()
+    }
+}
@@ -0,0 +1,86 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Ball, World};
+
+// Upper bound on undrained events, so a host that never calls drain_events()
+// does not grow memory forever. Newer events are dropped once it is reached.
+const MAX_PENDING_EVENTS: usize = 4096;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    // A ball had a non-finite position/velocity/radius and was repaired.
+    // `value` holds a bitmask of SANITIZED_* flags.
+    Sanitized = 0,
+    // A ball left the arena through an open wall and was removed.
+    // `value` holds the WALL_* flag of that wall.
+    Escaped = 1,
+    // A shockwave split the ball (its child is a new ball at the same spot).
+    // `value` holds the kinetic energy of the kick that split it.
+    Shockwave = 2,
+    // A ball kept bouncing between two walls and was nudged out of the corner.
+    // `value` holds the angle its velocity was turned by, in radians.
+    CornerTrapped = 3,
+    // A ball's center crossed the water line (x, y: where). `value` holds its
+    // vertical velocity: positive going under, negative coming up.
+    Splash = 4,
+    // A goal zone's required count of balls has entered it. `id` is the
+    // goal's index (not a ball), (x, y) its center, `value` the count.
+    GoalReached = 5,
+    // A team's balls (see teams.rs) grew past the area threshold. `id` is the
+    // team's tag, (x, y) its area-weighted center, `value` its area.
+    TeamThreshold = 6,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    pub kind: EventKind,
+    pub id: u32,
+    pub frame: u32,
+    pub x: f32,
+    pub y: f32,
+    pub value: f32,
+}
+
+#[wasm_bindgen]
+impl World {
+    // Take all events emitted since the previous call
+    pub fn drain_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+}
+
+pub(crate) fn push_event(events: &mut Vec<Event>, event: Event) {
+    if events.len() < MAX_PENDING_EVENTS {
+        events.push(event);
+    }
+}
+
+// Report a ball that sim::sanitize_ball repaired during `frame`
+pub(crate) fn push_sanitized(
+    events: &mut Vec<Event>,
+    id: usize,
+    frame: u32,
+    ball: &Ball,
+    mask: u32,
+) {
+    log_warn!(
+        "frame {frame}: ball {id} had non-finite state (SANITIZED_* mask {mask:#x}), repaired"
+    );
+    push_event(
+        events,
+        Event {
+            kind: EventKind::Sanitized,
+            id: id as u32,
+            frame,
+            x: ball.x,
+            y: ball.y,
+            value: mask as f32,
+        },
+    );
+}
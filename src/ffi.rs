@@ -0,0 +1,120 @@
+// C ABI for embedding the simulation outside of wasm (feature "ffi").
+//
+// A World is an opaque heap object owned by the caller: create it with
+// world_new and release it with world_free. Ball records behind
+// world_balls_ptr use the `Ball` layout from the generated header
+// (include/bouncing_balls.h, regenerate with `cbindgen --output include/bouncing_balls.h`).
+
+use std::ptr;
+
+use crate::{Ball, World};
+
+/// Create a world, or return NULL if the configuration is invalid.
+#[no_mangle]
+pub extern "C" fn world_new(
+    width: f32,
+    height: f32,
+    max_balls: usize,
+    split_ratio: f32,
+) -> *mut World {
+    match World::try_new(width, height, max_balls, split_ratio) {
+        Ok(world) => Box::into_raw(Box::new(world)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Create a deterministic world whose RNG starts from `seed`, or NULL if the configuration is invalid.
+#[no_mangle]
+pub extern "C" fn world_new_seeded(
+    width: f32,
+    height: f32,
+    max_balls: usize,
+    split_ratio: f32,
+    seed: u32,
+) -> *mut World {
+    match World::try_new_seeded(width, height, max_balls, split_ratio, seed) {
+        Ok(world) => Box::into_raw(Box::new(world)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Release a world created by world_new. NULL is ignored.
+///
+/// # Safety
+/// `world` must be NULL or a pointer returned by world_new / world_new_seeded
+/// that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn world_free(world: *mut World) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// Advance the simulation by one frame.
+///
+/// # Safety
+/// `world` must be a live pointer returned by world_new.
+#[no_mangle]
+pub unsafe extern "C" fn world_update(world: *mut World) {
+    if let Some(world) = world.as_mut() {
+        world.update();
+    }
+}
+
+/// Pointer to the ball slots (world_balls_len of them, free slots have alive == 0).
+/// Valid until the next call that mutates the world.
+///
+/// # Safety
+/// `world` must be a live pointer returned by world_new.
+#[no_mangle]
+pub unsafe extern "C" fn world_balls_ptr(world: *const World) -> *const Ball {
+    match world.as_ref() {
+        Some(world) => world.get_balls_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Number of slots behind world_balls_ptr, including free ones.
+///
+/// # Safety
+/// `world` must be a live pointer returned by world_new.
+#[no_mangle]
+pub unsafe extern "C" fn world_balls_len(world: *const World) -> usize {
+    world.as_ref().map_or(0, World::slot_count)
+}
+
+/// Number of live balls.
+///
+/// # Safety
+/// `world` must be a live pointer returned by world_new.
+#[no_mangle]
+pub unsafe extern "C" fn world_live_count(world: *const World) -> usize {
+    world.as_ref().map_or(0, World::live_count)
+}
+
+/// Render into a tightly packed RGBA8 buffer of `len` bytes (width * height * 4).
+/// Returns 0 on success and -1 if a pointer is NULL or the buffer size does not match.
+///
+/// # Safety
+/// `world` must be a live pointer returned by world_new and `buffer` must be
+/// valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn world_render(
+    world: *const World,
+    buffer: *mut u8,
+    len: usize,
+    width: usize,
+    height: usize,
+) -> i32 {
+    let Some(world) = world.as_ref() else {
+        return -1;
+    };
+    if buffer.is_null() {
+        return -1;
+    }
+    let buffer = std::slice::from_raw_parts_mut(buffer, len);
+    match world.render_to_buffer(buffer, width, height, None) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
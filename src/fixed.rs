@@ -0,0 +1,511 @@
+// Deterministic fixed-point integration (`fixed` feature). With
+// set_fixed_point(true) every ball's position and velocity live in a Q16.16
+// shadow array (i32, 1/65536 px) that update() integrates, bounces off the
+// walls and collides ball against ball with integer arithmetic only, so runs
+// are bit-identical on every browser and CPU. The Ball records (what
+// get_balls_ptr, views and rendering see) get the f32 conversion after each
+// step.
+//
+// Radii, spin, temperature and splitting stay f32 and are only read or
+// converted at the boundary. Like the F64 shadow in precision.rs, external
+// edits (add_ball, attractors, obstacles, set_* ...) show up as a mismatch
+// between a ball and its shadow and simply reload the shadow from the ball.
+// Values saturate at +-32768 px (or px/frame).
+
+use wasm_bindgen::prelude::*;
+
+use crate::collision::{collision_loss, mix_restitution, Contact};
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::{events, profile, sim, Ball, Integrator, World};
+
+const FRACTION_BITS: u32 = 16;
+const ONE: i64 = 1 << FRACTION_BITS;
+
+// Nearest Q16.16 value; NaN becomes 0 and out-of-range values saturate
+fn to_fixed(value: f32) -> i32 {
+    (value as f64 * ONE as f64).round() as i32
+}
+
+// The f64 quotient is exact, so the f32 rounding is the same everywhere
+fn to_f32(value: i32) -> f32 {
+    (value as f64 / ONE as f64) as f32
+}
+
+fn saturate(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+// Products and quotients go through i128 and saturate, so big radii or
+// speeds clamp instead of overflowing
+fn wide(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+fn mul(a: i64, b: i64) -> i64 {
+    wide((a as i128 * b as i128) >> FRACTION_BITS)
+}
+
+fn div(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        0
+    } else {
+        wide(((a as i128) << FRACTION_BITS) / b as i128)
+    }
+}
+
+// a * b / c on raw values, without the intermediate overflowing
+fn mul_div(a: i64, b: i64, c: i64) -> i64 {
+    if c == 0 {
+        0
+    } else {
+        wide(a as i128 * b as i128 / c as i128)
+    }
+}
+
+// Integer square root (floor), bit by bit
+fn isqrt(mut value: u128) -> u128 {
+    let mut root = 0;
+    let mut bit = 1u128 << 126;
+    while bit > value {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if value >= root + bit {
+            value -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FixedState {
+    x: i32,
+    y: i32,
+    vx: i32,
+    vy: i32,
+}
+
+impl StateHash for FixedState {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        for value in [self.x, self.y, self.vx, self.vy] {
+            hash.write(&value);
+        }
+    }
+}
+
+impl FixedState {
+    fn from_ball(ball: &Ball) -> FixedState {
+        FixedState {
+            x: to_fixed(ball.x),
+            y: to_fixed(ball.y),
+            vx: to_fixed(ball.vx),
+            vy: to_fixed(ball.vy),
+        }
+    }
+
+    fn matches(&self, ball: &Ball) -> bool {
+        to_f32(self.x) == ball.x
+            && to_f32(self.y) == ball.y
+            && to_f32(self.vx) == ball.vx
+            && to_f32(self.vy) == ball.vy
+    }
+
+    fn store(&self, ball: &mut Ball) {
+        ball.x = to_f32(self.x);
+        ball.y = to_f32(self.y);
+        ball.vx = to_f32(self.vx);
+        ball.vy = to_f32(self.vy);
+    }
+
+    fn set(&mut self, x: i64, y: i64, vx: i64, vy: i64) {
+        *self = FixedState {
+            x: saturate(x),
+            y: saturate(y),
+            vx: saturate(vx),
+            vy: saturate(vy),
+        };
+    }
+
+    // Same rules as sim::integrate, in Q16.16
+    fn integrate(&mut self, spin: f32, config: &sim::SimConfig) {
+        let dt = to_fixed(config.dt) as i64;
+        let (ax, ay) = (
+            mul(to_fixed(config.gravity_x) as i64, dt),
+            mul(to_fixed(config.gravity_y) as i64, dt),
+        );
+        let (mut x, mut y) = (self.x as i64, self.y as i64);
+        let (mut vx, mut vy) = (self.vx as i64, self.vy as i64);
+        match config.integrator {
+            Integrator::Euler => {
+                x += mul(vx, dt);
+                y += mul(vy, dt);
+                (vx, vy) = magnus(vx + ax, vy + ay, spin, config);
+            }
+            Integrator::SemiImplicitEuler | Integrator::Verlet => {
+                (vx, vy) = magnus(vx + ax, vy + ay, spin, config);
+                x += mul(vx, dt);
+                y += mul(vy, dt);
+            }
+        }
+        self.set(x, y, vx, vy);
+    }
+
+    // Same rules as sim::bounce_walls, in Q16.16
+    fn bounce_walls(&mut self, ball: &Ball, config: &sim::SimConfig) -> sim::WallHits {
+        let radius = to_fixed(ball.radius);
+        let low = to_fixed(config.wall_inset);
+        let right = to_fixed(config.width - config.wall_inset);
+        let bottom = to_fixed(config.height - config.wall_inset);
+        let closed = |wall: u32| config.open_walls & wall == 0;
+        let restitution = to_fixed(sim::wall_restitution(ball, config)) as i64;
+        let mut hits = sim::WallHits::default();
+        let mut lost = 0.0;
+        // Speed away from the wall for a velocity component of `into` towards it
+        let mut rebound = |into: i32| {
+            if into <= 0 || restitution == ONE {
+                return into.saturating_abs();
+            }
+            let speed = to_f32(into);
+            lost += sim::restitution_loss(ball.radius, speed, to_f32(restitution as i32));
+            saturate(mul(into as i64, restitution))
+        };
+        if closed(sim::WALL_LEFT) && self.x.saturating_sub(radius) < low {
+            self.x = low.saturating_add(radius);
+            self.vx = rebound(self.vx.saturating_neg());
+            hits.x = true;
+        } else if closed(sim::WALL_RIGHT) && self.x.saturating_add(radius) > right {
+            self.x = right.saturating_sub(radius);
+            self.vx = -rebound(self.vx);
+            hits.x = true;
+        }
+        if closed(sim::WALL_TOP) && self.y.saturating_sub(radius) < low {
+            self.y = low.saturating_add(radius);
+            self.vy = rebound(self.vy.saturating_neg());
+            hits.y = true;
+        } else if closed(sim::WALL_BOTTOM) && self.y.saturating_add(radius) > bottom {
+            self.y = bottom.saturating_sub(radius);
+            self.vy = -rebound(self.vy);
+            hits.y = true;
+        }
+        hits.lost = lost;
+        self.round_corner(ball, restitution, config, &mut hits);
+        hits
+    }
+
+    // Same rules as sim's rounded corners, in Q16.16
+    fn round_corner(
+        &mut self,
+        ball: &Ball,
+        restitution: i64,
+        config: &sim::SimConfig,
+        hits: &mut sim::WallHits,
+    ) {
+        let radius = to_fixed(ball.radius);
+        let Some((cx, cy)) = sim::corner_center(to_f32(self.x), to_f32(self.y), config) else {
+            return;
+        };
+        let (cx, cy) = (to_fixed(cx) as i64, to_fixed(cy) as i64);
+        let limit = to_fixed(config.corner_radius) as i64 - radius as i64;
+        let (dx, dy) = (self.x as i64 - cx, self.y as i64 - cy);
+        let squared = (dx as i128 * dx as i128 + dy as i128 * dy as i128) as u128;
+        if limit <= 0 || squared <= (limit * limit) as u128 {
+            return;
+        }
+        let distance = isqrt(squared) as i64;
+        let (ox, oy) = (div(dx, distance), div(dy, distance));
+        let (mut vx, mut vy) = (self.vx as i64, self.vy as i64);
+        let out = mul(vx, ox) + mul(vy, oy);
+        if out > 0 {
+            // Reflected, then the restitution's share of the speed put back
+            let back = mul(out, restitution);
+            vx -= mul(out, ox) + mul(back, ox);
+            vy -= mul(out, oy) + mul(back, oy);
+            if restitution < ONE {
+                let (speed, restitution) = (to_f32(saturate(out)), to_f32(restitution as i32));
+                hits.lost += sim::restitution_loss(ball.radius, speed, restitution);
+            }
+        }
+        self.set(cx + mul(ox, limit), cy + mul(oy, limit), vx, vy);
+        hits.x = true;
+        hits.y = true;
+        hits.corner = Some((-to_f32(ox as i32), -to_f32(oy as i32)));
+    }
+
+    // Same rules as sim::wall_friction; the slip is fixed-point, the spin
+    // it feeds stays f32
+    fn wall_friction(&mut self, ball: &mut Ball, config: &sim::SimConfig, hits: sim::WallHits) {
+        let share = config
+            .mix_rule
+            .mix(config.wall_friction, ball.friction)
+            .min(1.0);
+        if share <= 0.0 || !hits.any() {
+            return;
+        }
+        let share = to_fixed(share) as i64;
+        let mut spin = ball.spin;
+        let rim_speed = |spin: f32| to_fixed(spin * ball.radius) as i64;
+        let (mut vx, mut vy) = (self.vx as i64, self.vy as i64);
+        if let Some((nx, ny)) = hits.corner {
+            let (nx, ny) = (to_fixed(nx) as i64, to_fixed(ny) as i64);
+            let slip = -mul(vx, ny) + mul(vy, nx) - rim_speed(spin);
+            vx += mul(mul(share, slip) / 3, ny);
+            vy -= mul(mul(share, slip) / 3, nx);
+            spin += 2.0 * to_f32(saturate(mul(share, slip))) / (3.0 * ball.radius);
+            self.set(self.x as i64, self.y as i64, vx, vy);
+            ball.spin = spin;
+            return;
+        }
+        if hits.x {
+            let nx = if self.x < to_fixed(config.width * 0.5) {
+                ONE
+            } else {
+                -ONE
+            };
+            let slip = mul(vy, nx) - rim_speed(spin);
+            vy -= mul(mul(share, slip) / 3, nx);
+            spin += 2.0 * to_f32(saturate(mul(share, slip))) / (3.0 * ball.radius);
+        }
+        if hits.y {
+            let ny = if self.y < to_fixed(config.height * 0.5) {
+                ONE
+            } else {
+                -ONE
+            };
+            let slip = -mul(vx, ny) - rim_speed(spin);
+            vx += mul(mul(share, slip) / 3, ny);
+            spin += 2.0 * to_f32(saturate(mul(share, slip))) / (3.0 * ball.radius);
+        }
+        self.set(self.x as i64, self.y as i64, vx, vy);
+        ball.spin = spin;
+    }
+}
+
+// Same rotation as the Magnus turn in sim::integrate
+fn magnus(vx: i64, vy: i64, spin: f32, config: &sim::SimConfig) -> (i64, i64) {
+    if config.magnus == 0.0 || spin == 0.0 {
+        return (vx, vy);
+    }
+    let half = to_fixed(0.5 * config.magnus * spin * config.dt) as i64;
+    let half2 = mul(half, half);
+    let scale = div(ONE, ONE + half2);
+    (
+        mul(mul(ONE - half2, vx) - 2 * mul(half, vy), scale),
+        mul(mul(ONE - half2, vy) + 2 * mul(half, vx), scale),
+    )
+}
+
+// collision::resolve_pair on the shadow states of balls `a` < `b`
+pub(crate) fn resolve_pair(
+    states: &mut Vec<FixedState>,
+    balls: &mut [Ball],
+    a: usize,
+    b: usize,
+    heating: f32,
+    mix_rule: sim::MixRule,
+) -> Option<Contact> {
+    if states.len() < balls.len() {
+        states.resize(balls.len(), FixedState::default());
+    }
+    for id in [a, b] {
+        if !states[id].matches(&balls[id]) {
+            states[id] = FixedState::from_ball(&balls[id]);
+        }
+    }
+    let (first, second) = (states[a], states[b]);
+    let (ra, rb) = (
+        to_fixed(balls[a].radius) as i64,
+        to_fixed(balls[b].radius) as i64,
+    );
+
+    let dx = second.x as i64 - first.x as i64;
+    let dy = second.y as i64 - first.y as i64;
+    let reach = ra + rb;
+    let squared = |value: i64| (value as i128 * value as i128) as u128;
+    let distance2 = squared(dx) + squared(dy);
+    if distance2 >= squared(reach) {
+        return None;
+    }
+    let distance = isqrt(distance2) as i64;
+    // Concentric balls: push them apart horizontally
+    let (nx, ny) = if distance > 0 {
+        (div(dx, distance), div(dy, distance))
+    } else {
+        (ONE, 0)
+    };
+
+    // Tiny radii round to a zero mass; keep the shares defined
+    let mass_a = mul(ra, ra).max(1);
+    let mass_b = mul(rb, rb).max(1);
+    let total = mass_a.saturating_add(mass_b);
+
+    // Each ball moves back by its share of the overlap, the lighter one more
+    let overlap = reach - distance;
+    let (push_x, push_y) = (mul(nx, overlap), mul(ny, overlap));
+    let (ax, ay) = (
+        first.x as i64 - mul_div(push_x, mass_b, total),
+        first.y as i64 - mul_div(push_y, mass_b, total),
+    );
+    let (bx, by) = (
+        second.x as i64 + mul_div(push_x, mass_a, total),
+        second.y as i64 + mul_div(push_y, mass_a, total),
+    );
+    let (mut avx, mut avy) = (first.vx as i64, first.vy as i64);
+    let (mut bvx, mut bvy) = (second.vx as i64, second.vy as i64);
+
+    let approach = mul(bvx - avx, nx) + mul(bvy - avy, ny);
+    let mut lost = 0.0;
+    if approach < 0 {
+        // Impulse along the normal, per unit of each ball's mass: elastic
+        // (twice the approach) at a mixed restitution of 1
+        let restitution = mix_restitution(&balls[a], &balls[b], mix_rule);
+        let impulse = -approach - mul(approach, to_fixed(restitution) as i64);
+        let reduced = to_f32(saturate(mul_div(mass_a, mass_b, total)));
+        lost = collision_loss(reduced, to_f32(saturate(approach)), restitution);
+        let (share_a, share_b) = (
+            mul_div(impulse, mass_b, total),
+            mul_div(impulse, mass_a, total),
+        );
+        avx -= mul(share_a, nx);
+        avy -= mul(share_a, ny);
+        bvx += mul(share_b, nx);
+        bvy += mul(share_b, ny);
+        if heating > 0.0 {
+            let speed = to_f32(saturate(approach));
+            balls[a].temperature -= heating * speed;
+            balls[b].temperature -= heating * speed;
+        }
+    }
+    states[a].set(ax, ay, avx, avy);
+    states[b].set(bx, by, bvx, bvy);
+    states[a].store(&mut balls[a]);
+    states[b].store(&mut balls[b]);
+    Some(Contact {
+        normal: (to_f32(nx as i32), to_f32(ny as i32)),
+        speed: to_f32(saturate(-approach.min(0))),
+        lost,
+    })
+}
+
+#[wasm_bindgen]
+impl World {
+    // Integrate in Q16.16 fixed point (see fixed.rs). Turning it on starts
+    // from the current f32 state and replaces Precision::F64; turning it off
+    // keeps the converted positions.
+    pub fn set_fixed_point(&mut self, enabled: bool) {
+        self.fixed = enabled.then(|| self.balls.iter().map(FixedState::from_ball).collect());
+        if enabled {
+            self.precise = None;
+        }
+    }
+
+    pub fn fixed_point(&self) -> bool {
+        self.fixed.is_some()
+    }
+}
+
+impl World {
+    // One (sub-)step of update() in fixed-point mode. With profiling on, the
+    // whole step is reported as integration.
+    pub(crate) fn step_fixed(&mut self, stamp: u32) {
+        let Some(mut fixed) = self.fixed.take() else {
+            return;
+        };
+        let start = self.profile.enabled().then(profile::now_ms);
+        fixed.resize(self.balls.len(), FixedState::default());
+
+        let config = self.sim_config();
+        let dt = to_fixed(config.dt) as i64;
+        let (width, height) = (self.width, self.height);
+        let (balls, modified, mut hook) = self.after_bounce(stamp);
+
+        for (id, (ball, modified)) in balls.iter_mut().zip(modified.iter_mut()).enumerate() {
+            if ball.alive == 0 {
+                continue;
+            }
+            let before = *ball;
+
+            let sanitized = sim::sanitize_ball(ball, width, height);
+            if sanitized != 0 {
+                events::push_sanitized(hook.events, id, stamp, ball, sanitized);
+            }
+            let state = &mut fixed[id];
+            if !state.matches(ball) {
+                *state = FixedState::from_ball(ball);
+            }
+
+            let was_just_split = ball.just_split == 1;
+            ball.just_split = 0;
+            let start = (state.x as i64, state.y as i64);
+            state.integrate(ball.spin, &config);
+            let hits = state.bounce_walls(ball, &config);
+            if config.integrator == Integrator::Verlet {
+                let vx = match hits.x {
+                    true => state.vx as i64,
+                    false => div(state.x as i64 - start.0, dt),
+                };
+                let vy = match hits.y {
+                    true => state.vy as i64,
+                    false => div(state.y as i64 - start.1, dt),
+                };
+                state.set(state.x as i64, state.y as i64, vx, vy);
+            }
+            state.wall_friction(ball, &config, hits);
+            state.store(ball);
+            sim::heat(ball, &config, hits);
+            hook.run(id, ball, hits, was_just_split);
+
+            if *ball != before {
+                *modified = stamp;
+            }
+        }
+
+        let (children, denied) = (hook.children, hook.denied);
+        for id in self.insert_children(stamp, children, denied) {
+            let id = id as usize;
+            if id >= fixed.len() {
+                fixed.resize(id + 1, FixedState::default());
+            }
+            fixed[id] = FixedState::from_ball(&self.balls[id]);
+        }
+        self.fixed = Some(fixed);
+
+        if let Some(start) = start {
+            self.profile.add_step(profile::now_ms() - start);
+        }
+    }
+
+    pub(crate) fn fixed_memory_bytes(&self) -> usize {
+        self.fixed.as_ref().map_or(0, |fixed| {
+            fixed.capacity() * std::mem::size_of::<FixedState>()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    // Two big balls colliding head-on: the Q16.16 mass products used to overflow
+    #[test]
+    fn big_balls_collide_without_overflow() {
+        let mut world = World::new_empty_seeded(4000.0, 3000.0, 10, 0.7, 1).unwrap();
+        world.set_collisions(true);
+        world.set_fixed_point(true);
+        let a = world
+            .add_ball(1000.0, 1500.0, 5.0, 0.0, 300.0, 0xFF0000)
+            .unwrap();
+        let b = world
+            .add_ball(1500.0, 1500.0, -5.0, 0.0, 300.0, 0x00FF00)
+            .unwrap();
+        for _ in 0..50 {
+            world.update();
+        }
+        // Equal masses, elastic: the velocities are exchanged
+        assert_eq!(world.ball(a).unwrap().vx(), -5.0);
+        assert_eq!(world.ball(b).unwrap().vx(), 5.0);
+    }
+}
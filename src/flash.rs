@@ -0,0 +1,193 @@
+// Impact flashes. With them on, every wall bounce leaves a ring (white unless
+// given a color) at the contact point that expands to twice the ball's radius and fades out over
+// FLASH_FRAMES frames. A bounce at FULL_FLASH_SPEED pixels/frame (along the
+// wall normal) times `strength` starts the ring fully opaque; slower ones
+// start fainter in proportion.
+//
+// The rings are purely visual, drawn over the balls; hosts with their own
+// renderer read them from impact_flashes(). They live in a pool of a fixed
+// number of slots, allocated when flashes are turned on, so even a frame full
+// of bounces allocates nothing: once every slot is taken, each new ring
+// replaces the oldest.
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::Clip;
+use crate::sim;
+use crate::srgb::{self, Blending};
+use crate::{Ball, World};
+
+const FLASH_FRAMES: u32 = 10;
+const FULL_FLASH_SPEED: f32 = 10.0;
+const RING_WIDTH: f32 = 2.0;
+const DEFAULT_COLOR: u32 = 0xFFFFFF;
+const DEFAULT_POOL_SIZE: usize = 1024;
+const MAX_POOL_SIZE: usize = 1 << 16;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Ring {
+    x: f32, // Contact point
+    y: f32,
+    reach: f32,   // Final radius
+    opacity: f32, // 0 = free slot
+    frame: u32,   // Of the bounce
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Flashes {
+    enabled: bool,
+    strength: f32,
+    color: u32,
+    pool_size: usize,
+    rings: Vec<Ring>, // The pool: pool_size slots while enabled, else none
+    next: usize,      // Slot the next ring goes into
+}
+
+impl Default for Flashes {
+    fn default() -> Flashes {
+        Flashes {
+            enabled: false,
+            strength: 1.0,
+            color: DEFAULT_COLOR,
+            pool_size: DEFAULT_POOL_SIZE,
+            rings: Vec::new(),
+            next: 0,
+        }
+    }
+}
+
+impl Flashes {
+    // A wall bounce of `ball`, after its velocity was reflected
+    pub(crate) fn record(&mut self, frame: u32, ball: &Ball, hits: sim::WallHits) {
+        if self.rings.is_empty() || !hits.any() {
+            return;
+        }
+        // The velocity now points away from the wall(s) it bounced off
+        let (mut x, mut y, mut speed) = (ball.x, ball.y, 0.0f32);
+        if hits.x {
+            x -= ball.vx.signum() * ball.radius;
+            speed = speed.max(ball.vx.abs());
+        }
+        if hits.y {
+            y -= ball.vy.signum() * ball.radius;
+            speed = speed.max(ball.vy.abs());
+        }
+        let opacity = (speed * self.strength / FULL_FLASH_SPEED).min(1.0);
+        if opacity <= 0.0 {
+            return;
+        }
+        self.rings[self.next] = Ring {
+            x,
+            y,
+            reach: ball.radius * 2.0,
+            opacity,
+            frame,
+        };
+        self.next = (self.next + 1) % self.rings.len();
+    }
+
+    // (Re)allocate the pool, empty, or free it while flashes are off
+    fn reset_pool(&mut self) {
+        let size = if self.enabled { self.pool_size } else { 0 };
+        self.rings = vec![Ring::default(); size];
+        self.next = 0;
+    }
+
+    // Center, radius and opacity of each ring showing at `frame`, oldest first
+    fn shapes(&self, frame: u32) -> impl Iterator<Item = (f32, f32, f32, f32)> + '_ {
+        let (newer, older) = self.rings.split_at(self.next);
+        let showing =
+            move |ring: &&Ring| ring.opacity > 0.0 && frame.wrapping_sub(ring.frame) < FLASH_FRAMES;
+        older.iter().chain(newer).filter(showing).map(move |ring| {
+            let age = frame.wrapping_sub(ring.frame);
+            // Age 0 is the bounce itself, already a small ring
+            let grown = (age + 1) as f32 / (FLASH_FRAMES + 1) as f32;
+            let left = 1.0 - age as f32 / FLASH_FRAMES as f32;
+            (ring.x, ring.y, ring.reach * grown, ring.opacity * left)
+        })
+    }
+
+    // Blend the rings into the band. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(
+        &self,
+        buffer: &mut [u8],
+        stride: usize,
+        clip: Clip,
+        frame: u32,
+        blending: Blending,
+    ) {
+        let rgb = srgb::rgb(self.color);
+        for (cx, cy, r, alpha) in self.shapes(frame) {
+            let inner = (r - RING_WIDTH).max(0.0);
+            let x_min = (cx - r).max(clip.x0 as f32) as i64;
+            let x_max = (cx + r).min(clip.x1 as f32) as i64;
+            let y_min = (cy - r).max(clip.y0 as f32) as i64;
+            let y_max = (cy + r).min(clip.y1 as f32) as i64;
+            for py in y_min..y_max {
+                let row = (py as usize - clip.y0) * stride;
+                for px in x_min..x_max {
+                    let (dx, dy) = (px as f32 - cx, py as f32 - cy);
+                    let d = dx * dx + dy * dy;
+                    if d > r * r || d <= inner * inner {
+                        continue;
+                    }
+                    let idx = row + px as usize * 4;
+                    blending.blend(&mut buffer[idx..idx + 4], rgb, alpha);
+                }
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Flash a ring where balls hit the walls, `strength` times as bright as
+    // the default (1) for a given impact speed. Turning it off drops the
+    // rings still showing.
+    pub fn set_impact_flash(&mut self, enabled: bool, strength: f32) {
+        if !strength.is_finite() {
+            return;
+        }
+        self.flashes.strength = strength.max(0.0);
+        if enabled != self.flashes.enabled {
+            self.flashes.enabled = enabled;
+            self.flashes.reset_pool();
+        }
+    }
+
+    pub fn impact_flash(&self) -> bool {
+        self.flashes.enabled
+    }
+
+    pub fn impact_flash_strength(&self) -> f32 {
+        self.flashes.strength
+    }
+
+    // Ring color, 0xRRGGBB (white by default)
+    pub fn set_impact_flash_color(&mut self, color: u32) {
+        self.flashes.color = color & 0xFF_FFFF;
+    }
+
+    pub fn impact_flash_color(&self) -> u32 {
+        self.flashes.color
+    }
+
+    // Number of effect slots (1..=65536, default 1024): at most this many
+    // rings show at once. Resizing drops the rings showing.
+    pub fn set_effect_pool_size(&mut self, size: usize) {
+        self.flashes.pool_size = size.clamp(1, MAX_POOL_SIZE);
+        self.flashes.reset_pool();
+    }
+
+    pub fn effect_pool_size(&self) -> usize {
+        self.flashes.pool_size
+    }
+
+    // The rings as x, y, radius, opacity quadruples
+    pub fn impact_flashes(&self) -> Vec<f32> {
+        self.flashes
+            .shapes(self.frame)
+            .flat_map(|(x, y, r, alpha)| [x, y, r, alpha])
+            .collect()
+    }
+}
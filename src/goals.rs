@@ -0,0 +1,118 @@
+// Goal zones, for games and puzzles. A goal is a rectangle that counts the
+// balls whose centers enter it; once `required` balls have, it emits one
+// GoalReached event. A ball counts again each time it comes back in after
+// leaving. Goals are checked once per frame, after the (sub-)steps, and are
+// invisible: hosts draw them however the game wants.
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::{self, Event, EventKind};
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::World;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Goal {
+    x: f32, // Top-left corner
+    y: f32,
+    width: f32,
+    height: f32,
+    required: u32,
+    count: u32, // Balls that entered so far
+    // Per slot: 1 + born_frame of the ball in it while that ball is inside
+    // (0 = outside), so a new ball in a reused slot isn't taken for the old one
+    inside: Vec<u32>,
+}
+
+impl StateHash for Goal {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        for value in [self.x, self.y, self.width, self.height] {
+            hash.write_f32(value);
+        }
+        hash.write(&(self.required, self.count));
+        hash.write(&self.inside);
+    }
+}
+
+impl Goal {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+impl World {
+    pub(crate) fn check_goals(&mut self, stamp: u32) {
+        for (index, goal) in self.goals.iter_mut().enumerate() {
+            let mut marks = std::mem::take(&mut goal.inside);
+            if marks.len() < self.balls.len() {
+                marks.resize(self.balls.len(), 0);
+            }
+            let before = goal.count;
+            for (ball, inside) in self.balls.iter().zip(marks.iter_mut()) {
+                let mark = ball.born_frame.wrapping_add(1);
+                if ball.alive == 0 || !goal.contains(ball.x, ball.y) {
+                    *inside = 0;
+                } else if *inside != mark {
+                    *inside = mark;
+                    goal.count = goal.count.saturating_add(1);
+                }
+            }
+            goal.inside = marks;
+            if before < goal.required && goal.count >= goal.required {
+                events::push_event(
+                    &mut self.events,
+                    Event {
+                        kind: EventKind::GoalReached,
+                        id: index as u32,
+                        frame: stamp,
+                        x: goal.x + goal.width / 2.0,
+                        y: goal.y + goal.height / 2.0,
+                        value: goal.count as f32,
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // A goal with its top-left corner at (x, y) that is reached once
+    // `required_count` balls have entered it (0: from the start, without an
+    // event). Returns its index (indices shift only on clear_goals), or None
+    // unless the arguments are finite and the size positive.
+    pub fn add_goal(&mut self, x: f32, y: f32, w: f32, h: f32, required_count: u32) -> Option<u32> {
+        let finite = [x, y, w, h].iter().all(|value| value.is_finite());
+        if !(finite && w > 0.0 && h > 0.0) {
+            return None;
+        }
+        self.goals.push(Goal {
+            x,
+            y,
+            width: w,
+            height: h,
+            required: required_count,
+            count: 0,
+            inside: Vec::new(),
+        });
+        Some(self.goals.len() as u32 - 1)
+    }
+
+    // Balls that have entered a goal so far (0 if there is no such goal)
+    pub fn goal_entries(&self, index: u32) -> u32 {
+        self.goals.get(index as usize).map_or(0, |goal| goal.count)
+    }
+
+    pub fn goal_reached(&self, index: u32) -> bool {
+        self.goals
+            .get(index as usize)
+            .is_some_and(|goal| goal.count >= goal.required)
+    }
+
+    pub fn goal_count(&self) -> usize {
+        self.goals.len()
+    }
+
+    pub fn clear_goals(&mut self) {
+        self.goals.clear();
+    }
+}
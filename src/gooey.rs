@@ -0,0 +1,149 @@
+// Gooey splits. With a frame count set, the two halves of a split are drawn
+// for that many frames as one metaball blob that pinches apart: the pixels
+// where s * (ra^2 / da^2 + rb^2 / db^2) >= 1 are filled around the two discs,
+// s fading from 1 at the split to 1/2. Outside both discs each term is below
+// 1, so by then nothing is left but the discs.
+//
+// Nothing is recorded at split time: both halves carry the split's frame as
+// born_frame and the same generation, so each render pairs young balls
+// sharing those with their nearest such sibling. The blob is purely visual.
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::{grown_radius, Clip, ColorMode};
+use crate::{Ball, World};
+
+// Splits in the same frame and generation beyond this many aren't paired
+const MAX_GROUP: usize = 64;
+// No point of the blob is further than this many radii from a center
+const REACH: f32 = std::f32::consts::SQRT_2;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Gooey {
+    frames: u32, // 0 = off
+}
+
+// One pinching pair: center, drawn radius and fill color of both halves,
+// and the field strength s
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Neck {
+    a: (f32, f32, f32, u32),
+    b: (f32, f32, f32, u32),
+    strength: f32,
+}
+
+impl Gooey {
+    // Pairs still pinching apart at `frame`
+    pub(crate) fn necks(
+        &self,
+        balls: &[Ball],
+        frame: u32,
+        grow_frames: u32,
+        color_mode: ColorMode,
+    ) -> Vec<Neck> {
+        let mut necks = Vec::new();
+        if self.frames == 0 {
+            return necks;
+        }
+        let age = |ball: &Ball| frame.wrapping_sub(ball.born_frame);
+        let mut young: Vec<usize> = (0..balls.len())
+            .filter(|&id| {
+                let ball = &balls[id];
+                ball.alive != 0 && ball.generation > 0 && age(ball) < self.frames
+            })
+            .collect();
+        // The split a ball came from
+        let split = |id: usize| (balls[id].born_frame, balls[id].generation);
+        young.sort_by_key(|&id| (split(id), id));
+
+        let drawn = |ball: &Ball| {
+            let radius = grown_radius(ball, frame, grow_frames);
+            (ball.x, ball.y, radius, color_mode.fill(ball))
+        };
+        for group in young.chunk_by(|&a, &b| split(a) == split(b)) {
+            if group.len() > MAX_GROUP {
+                continue;
+            }
+            let mut paired = [false; MAX_GROUP];
+            for i in 0..group.len() {
+                if paired[i] {
+                    continue;
+                }
+                let a = &balls[group[i]];
+                let distance = |j: usize| {
+                    let b = &balls[group[j]];
+                    (b.x - a.x) * (b.x - a.x) + (b.y - a.y) * (b.y - a.y)
+                };
+                let nearest = (i + 1..group.len())
+                    .filter(|&j| !paired[j])
+                    .min_by(|&j, &k| distance(j).total_cmp(&distance(k)));
+                let Some(j) = nearest else {
+                    continue;
+                };
+                let b = &balls[group[j]];
+                // Siblings start out touching; anything further is another split
+                let reach = (a.radius + b.radius) * 2.0;
+                if distance(j) > reach * reach {
+                    continue;
+                }
+                paired[i] = true;
+                paired[j] = true;
+                necks.push(Neck {
+                    a: drawn(a),
+                    b: drawn(b),
+                    strength: 1.0 - 0.5 * age(a) as f32 / self.frames as f32,
+                });
+            }
+        }
+        necks
+    }
+}
+
+// Fill the blobs' pixels outside the discs (the balls are drawn over them).
+// `buffer` starts at row `clip.y0`.
+pub(crate) fn fill_necks(buffer: &mut [u8], stride: usize, clip: Clip, necks: &[Neck]) {
+    for neck in necks {
+        let (ax, ay, ar, a_color) = neck.a;
+        let (bx, by, br, b_color) = neck.b;
+        let (ar2, br2) = (ar * ar, br * br);
+        let x_min = ((ax - ar * REACH).min(bx - br * REACH).max(clip.x0 as f32)) as i64;
+        let x_max = ((ax + ar * REACH).max(bx + br * REACH).min(clip.x1 as f32)) as i64;
+        let y_min = ((ay - ar * REACH).min(by - br * REACH).max(clip.y0 as f32)) as i64;
+        let y_max = ((ay + ar * REACH).max(by + br * REACH).min(clip.y1 as f32)) as i64;
+        for py in y_min..y_max {
+            let row = (py as usize - clip.y0) * stride;
+            for px in x_min..x_max {
+                let (x, y) = (px as f32, py as f32);
+                let da2 = (x - ax) * (x - ax) + (y - ay) * (y - ay);
+                let db2 = (x - bx) * (x - bx) + (y - by) * (y - by);
+                // The discs themselves are left to the ball pass
+                if da2 <= ar2 || db2 <= br2 {
+                    continue;
+                }
+                let (fa, fb) = (ar2 / da2, br2 / db2);
+                if neck.strength * (fa + fb) < 1.0 {
+                    continue;
+                }
+                let color = if fa >= fb { a_color } else { b_color };
+                let idx = row + px as usize * 4;
+                buffer[idx] = ((color >> 16) & 0xFF) as u8;
+                buffer[idx + 1] = ((color >> 8) & 0xFF) as u8;
+                buffer[idx + 2] = (color & 0xFF) as u8;
+                buffer[idx + 3] = 255;
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Draw split pairs as a blob pinching apart over `frames` frames (0, the
+    // default, turns it off)
+    pub fn set_gooey_splits(&mut self, frames: u32) {
+        self.gooey.frames = frames;
+    }
+
+    pub fn gooey_splits(&self) -> u32 {
+        self.gooey.frames
+    }
+}
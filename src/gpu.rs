@@ -0,0 +1,362 @@
+// GpuBackend: integration + wall bouncing in a WGSL compute shader (feature "gpu").
+//
+// For very large worlds the per-ball loop in update() cannot keep up. The
+// backend keeps a copy of the ball slots in a GPU storage buffer and advances
+// them there. Splitting, events and everything else stay on the CPU, so balls
+// moved on the GPU bounce but never split. A typical loop:
+//
+//   gpu.upload(world)             after the ball set changed on the CPU
+//   gpu.step(frames)              advance on the GPU, no readback
+//   gpu.request_read_back()       start copying the slots back, then on a later
+//   gpu.poll_read_back(world)     frame: true once the world holds the results
+//
+// In JS the backend is created with `await GpuBackend.create()`. Native hosts can
+// drive `create()` with any executor and use `read_back_blocking` instead of polling.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+use wgpu::util::DeviceExt;
+
+use crate::{Ball, World, WorldError};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+// Same field order as Ball::encode (layer widened to a full word)
+const SHADER: &str = r#"
+struct Ball {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    radius: f32,
+    color: u32,
+    just_split: u32,
+    tag: u32,
+    layer: u32,
+    alive: u32,
+    born_frame: u32,
+    generation: u32,
+    spin: f32,
+    temperature: f32,
+    restitution: f32,
+    friction: f32,
+}
+
+struct Params {
+    width: f32,
+    height: f32,
+    slots: u32,
+    frames: u32,
+}
+
+@group(0) @binding(0) var<storage, read_write> balls: array<Ball>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.slots) {
+        return;
+    }
+    var ball = balls[id.x];
+    if (ball.alive == 0u || params.frames == 0u) {
+        return;
+    }
+    ball.just_split = 0u;
+    for (var i = 0u; i < params.frames; i++) {
+        ball.x += ball.vx;
+        ball.y += ball.vy;
+        if (ball.x - ball.radius < 0.0) {
+            ball.x = ball.radius;
+            ball.vx = abs(ball.vx);
+        } else if (ball.x + ball.radius > params.width) {
+            ball.x = params.width - ball.radius;
+            ball.vx = -abs(ball.vx);
+        }
+        if (ball.y - ball.radius < 0.0) {
+            ball.y = ball.radius;
+            ball.vy = abs(ball.vy);
+        } else if (ball.y + ball.radius > params.height) {
+            ball.y = params.height - ball.radius;
+            ball.vy = -abs(ball.vy);
+        }
+    }
+    balls[id.x] = ball;
+}
+"#;
+
+// Readback progress, shared with the map_async callback
+const READ_IDLE: u8 = 0;
+const READ_PENDING: u8 = 1;
+const READ_READY: u8 = 2;
+const READ_FAILED: u8 = 3;
+
+struct Slots {
+    count: u32,
+    balls: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+#[wasm_bindgen]
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    params: wgpu::Buffer,
+    slots: Option<Slots>,
+    width: f32,
+    height: f32,
+    pending_frames: u32, // Frames stepped on the GPU since the last upload or read back
+    read_state: Arc<AtomicU8>,
+}
+
+#[wasm_bindgen]
+impl GpuBackend {
+    // Request an adapter and device and compile the compute shader
+    pub async fn create() -> Result<GpuBackend, WorldError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .map_err(|error| WorldError::GpuUnavailable(error.to_string()))?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .await
+            .map_err(|error| WorldError::GpuUnavailable(error.to_string()))?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("bouncing_balls step"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("bouncing_balls step"),
+            layout: None,
+            module: &module,
+            entry_point: Some("step"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bouncing_balls params"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(GpuBackend {
+            device,
+            queue,
+            pipeline,
+            params,
+            slots: None,
+            width: 0.0,
+            height: 0.0,
+            pending_frames: 0,
+            read_state: Arc::new(AtomicU8::new(READ_IDLE)),
+        })
+    }
+
+    // Copy all ball slots and the world size to the GPU, replacing what is there.
+    // Any frames stepped since the last read back are discarded.
+    pub fn upload(&mut self, world: &World) {
+        let count = world.balls.len() as u32;
+        let mut bytes = Vec::with_capacity(world.balls.len().max(1) * Ball::ENCODED_LEN);
+        for ball in &world.balls {
+            ball.encode(&mut bytes);
+        }
+        if bytes.is_empty() {
+            Ball::default().encode(&mut bytes);
+        }
+
+        // A read back in flight keeps the old buffers mapped, so start over with fresh ones
+        let reading = self.read_state.load(Ordering::Acquire) != READ_IDLE;
+        if reading || self.slots.as_ref().map(|slots| slots.count) != Some(count) {
+            self.read_state = Arc::new(AtomicU8::new(READ_IDLE));
+            self.slots = Some(self.create_slots(count, &bytes));
+        } else if let Some(slots) = &self.slots {
+            self.queue.write_buffer(&slots.balls, 0, &bytes);
+        }
+        self.width = world.width;
+        self.height = world.height;
+        self.pending_frames = 0;
+    }
+
+    // Advance the uploaded balls by `frames` frames in a single dispatch
+    pub fn step(&mut self, frames: u32) {
+        let Some(slots) = &self.slots else {
+            return;
+        };
+        if frames == 0 || self.read_state.load(Ordering::Acquire) == READ_PENDING {
+            return;
+        }
+        let mut params = Vec::with_capacity(16);
+        params.extend_from_slice(&self.width.to_le_bytes());
+        params.extend_from_slice(&self.height.to_le_bytes());
+        params.extend_from_slice(&slots.count.to_le_bytes());
+        params.extend_from_slice(&frames.to_le_bytes());
+        self.queue.write_buffer(&self.params, 0, &params);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("bouncing_balls step"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &slots.bind_group, &[]);
+            pass.dispatch_workgroups(slots.count.div_ceil(WORKGROUP_SIZE).max(1), 1, 1);
+        }
+        self.queue.submit([encoder.finish()]);
+        self.pending_frames = self.pending_frames.wrapping_add(frames);
+    }
+
+    // Start copying the GPU slots back. step() is ignored until the read back completes.
+    pub fn request_read_back(&mut self) {
+        let Some(slots) = &self.slots else {
+            return;
+        };
+        if self.read_state.load(Ordering::Acquire) == READ_PENDING {
+            return;
+        }
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&slots.balls, 0, &slots.readback, 0, None);
+        self.queue.submit([encoder.finish()]);
+
+        self.read_state.store(READ_PENDING, Ordering::Release);
+        let state = Arc::clone(&self.read_state);
+        slots
+            .readback
+            .map_async(wgpu::MapMode::Read, .., move |result| {
+                let next = if result.is_ok() {
+                    READ_READY
+                } else {
+                    READ_FAILED
+                };
+                state.store(next, Ordering::Release);
+            });
+    }
+
+    // Returns true once a requested read back has been written into the world.
+    // Only positions, velocities and just_split are taken from the GPU; the
+    // world must still have the slot layout it had at upload().
+    pub fn poll_read_back(&mut self, world: &mut World) -> Result<bool, WorldError> {
+        let _ = self.device.poll(wgpu::PollType::Poll);
+        self.finish_read_back(world)
+    }
+
+    pub fn slot_count(&self) -> u32 {
+        self.slots.as_ref().map_or(0, |slots| slots.count)
+    }
+
+    // Frames stepped on the GPU that the world has not seen yet
+    pub fn pending_frames(&self) -> u32 {
+        self.pending_frames
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GpuBackend {
+    // Request a read back and wait for it (native only; browsers cannot block)
+    pub fn read_back_blocking(&mut self, world: &mut World) -> Result<(), WorldError> {
+        self.request_read_back();
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|error| WorldError::GpuUnavailable(error.to_string()))?;
+        self.finish_read_back(world).map(|_| ())
+    }
+}
+
+impl GpuBackend {
+    fn create_slots(&self, count: u32, bytes: &[u8]) -> Slots {
+        let balls = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("bouncing_balls balls"),
+                contents: bytes,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bouncing_balls readback"),
+            size: bytes.len() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bouncing_balls step"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: balls.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.params.as_entire_binding(),
+                },
+            ],
+        });
+        Slots {
+            count,
+            balls,
+            readback,
+            bind_group,
+        }
+    }
+
+    fn finish_read_back(&mut self, world: &mut World) -> Result<bool, WorldError> {
+        let Some(slots) = &self.slots else {
+            return Ok(false);
+        };
+        match self.read_state.load(Ordering::Acquire) {
+            READ_READY => {}
+            READ_FAILED => {
+                self.read_state.store(READ_IDLE, Ordering::Release);
+                return Err(WorldError::GpuUnavailable(
+                    "mapping the read back buffer failed".into(),
+                ));
+            }
+            _ => return Ok(false),
+        }
+
+        {
+            let data = slots
+                .readback
+                .get_mapped_range(..)
+                .map_err(|error| WorldError::GpuUnavailable(error.to_string()))?;
+            let stamp = world.frame.wrapping_add(self.pending_frames);
+            let count = world.balls.len().min(slots.count as usize);
+            for (id, record) in data.chunks_exact(Ball::ENCODED_LEN).take(count).enumerate() {
+                let word = |index: usize| {
+                    u32::from_le_bytes(record[index * 4..index * 4 + 4].try_into().unwrap())
+                };
+                let ball = &mut world.balls[id];
+                if ball.alive == 0 || word(9) == 0 {
+                    continue;
+                }
+                let before = *ball;
+                ball.x = f32::from_bits(word(0));
+                ball.y = f32::from_bits(word(1));
+                ball.vx = f32::from_bits(word(2));
+                ball.vy = f32::from_bits(word(3));
+                ball.just_split = word(6);
+                if *ball != before {
+                    world.modified[id] = stamp;
+                }
+            }
+            world.frame = stamp;
+        }
+        slots.readback.unmap();
+        self.pending_frames = 0;
+        self.read_state.store(READ_IDLE, Ordering::Release);
+        world.sync_mirror();
+        Ok(true)
+    }
+}
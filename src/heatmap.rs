@@ -0,0 +1,167 @@
+// Wall impact heatmap. Each wall is cut into `segments` equal bins; every wall
+// hit adds one to its bin's count and the ball's speed into the wall to its
+// intensity. Bins are ordered left wall (top to bottom), right wall (top to
+// bottom), top wall (left to right) and bottom wall (left to right).
+//
+// With a thickness set, the renderer paints a strip of that many pixels along
+// each wall, colored from blue (no impacts) to white (the busiest bin).
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::Clip;
+use crate::{sim, thermal, Ball, World};
+
+const WALLS: usize = 4;
+const MAX_SEGMENTS: u32 = 4096;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WallHeat {
+    segments: usize, // 0 = off
+    thickness: u32,  // Width of the drawn strips (0 = not drawn)
+    counts: Vec<u32>,
+    intensity: Vec<f32>,
+}
+
+impl WallHeat {
+    pub(crate) fn enabled(&self) -> bool {
+        self.segments > 0
+    }
+
+    // Record the walls `ball` hit this step, after the bounce reflected its velocity
+    pub(crate) fn record(&mut self, ball: &Ball, config: &sim::SimConfig, hits: sim::WallHits) {
+        if !self.enabled() || !hits.any() {
+            return;
+        }
+        if hits.x {
+            let wall = if ball.x < config.width * 0.5 { 0 } else { 1 };
+            let bin = self.bin(ball.y, config.height);
+            self.add(wall, bin, ball.vx.abs());
+        }
+        if hits.y {
+            let wall = if ball.y < config.height * 0.5 { 2 } else { 3 };
+            let bin = self.bin(ball.x, config.width);
+            self.add(wall, bin, ball.vy.abs());
+        }
+    }
+
+    fn bin(&self, along: f32, length: f32) -> usize {
+        let share = if length > 0.0 { along / length } else { 0.0 };
+        // NaN and out-of-range positions land in the end bins
+        ((share * self.segments as f32).max(0.0) as usize).min(self.segments - 1)
+    }
+
+    fn add(&mut self, wall: usize, bin: usize, speed: f32) {
+        let index = wall * self.segments + bin;
+        self.counts[index] = self.counts[index].saturating_add(1);
+        if speed.is_finite() {
+            self.intensity[index] += speed;
+        }
+    }
+
+    // Paint the heat strips inside `clip`. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(
+        &self,
+        buffer: &mut [u8],
+        stride: usize,
+        clip: Clip,
+        width: f32,
+        height: f32,
+    ) {
+        if !self.enabled() || self.thickness == 0 {
+            return;
+        }
+        let peak = self.intensity.iter().copied().fold(0.0, f32::max);
+        let thickness = self.thickness as f32;
+        for wall in 0..WALLS {
+            let length = if wall < 2 { height } else { width };
+            let step = length / self.segments as f32;
+            for bin in 0..self.segments {
+                let heat = self.intensity[wall * self.segments + bin];
+                let share = if peak > 0.0 { heat / peak } else { 0.0 };
+                // heat_color spans blue -> red -> white over 0..2
+                let color = thermal::heat_color(share * 2.0);
+                let (from, to) = (bin as f32 * step, (bin + 1) as f32 * step);
+                let rect = match wall {
+                    0 => (0.0, from, thickness, to),
+                    1 => (width - thickness, from, width, to),
+                    2 => (from, 0.0, to, thickness),
+                    _ => (from, height - thickness, to, height),
+                };
+                fill_rect(buffer, stride, clip, rect, color);
+            }
+        }
+    }
+}
+
+fn fill_rect(buffer: &mut [u8], stride: usize, clip: Clip, rect: (f32, f32, f32, f32), color: u32) {
+    let pixel = [
+        ((color >> 16) & 0xFF) as u8,
+        ((color >> 8) & 0xFF) as u8,
+        (color & 0xFF) as u8,
+        255,
+    ];
+    let (x0, y0, x1, y1) = rect;
+    let x_from = (x0.max(clip.x0 as f32) as usize).max(clip.x0);
+    let x_to = (x1.ceil().min(clip.x1 as f32) as usize).min(clip.x1);
+    let y_from = (y0.max(clip.y0 as f32) as usize).max(clip.y0);
+    let y_to = (y1.ceil().min(clip.y1 as f32) as usize).min(clip.y1);
+    for py in y_from..y_to {
+        let row = (py - clip.y0) * stride;
+        for px in x_from..x_to {
+            let idx = row + px * 4;
+            buffer[idx..idx + 4].copy_from_slice(&pixel);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Start collecting wall impacts into `segments` bins per wall (0 turns the
+    // heatmap off, at most 4096). Changing the segment count clears what was
+    // collected.
+    pub fn set_wall_heatmap(&mut self, segments: u32) {
+        let segments = segments.min(MAX_SEGMENTS) as usize;
+        if segments != self.wall_heat.segments {
+            self.wall_heat.segments = segments;
+            self.wall_heat.counts = vec![0; segments * WALLS];
+            self.wall_heat.intensity = vec![0.0; segments * WALLS];
+        }
+    }
+
+    pub fn wall_heatmap_segments(&self) -> u32 {
+        self.wall_heat.segments as u32
+    }
+
+    // Summed impact speed per bin, 4 * segments values (Float32Array in JS)
+    pub fn wall_heatmap(&self) -> Vec<f32> {
+        self.wall_heat.intensity.clone()
+    }
+
+    // Number of impacts per bin, in the same order as wall_heatmap()
+    pub fn wall_heatmap_counts(&self) -> Vec<u32> {
+        self.wall_heat.counts.clone()
+    }
+
+    pub fn clear_wall_heatmap(&mut self) {
+        self.wall_heat.counts.fill(0);
+        self.wall_heat.intensity.fill(0.0);
+    }
+
+    // Draw the heatmap as strips `thickness` pixels wide along the walls (0 = don't draw)
+    pub fn set_wall_heatmap_thickness(&mut self, thickness: u32) {
+        self.wall_heat.thickness = thickness;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    #[test]
+    fn segments_are_capped() {
+        let mut world = World::new(200.0, 150.0, 64, 0.7);
+        world.set_wall_heatmap(u32::MAX);
+        assert_eq!(world.wall_heatmap_segments(), 4096);
+        assert_eq!(world.wall_heatmap_counts().len(), 4096 * 4);
+    }
+}
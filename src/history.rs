@@ -0,0 +1,416 @@
+// Undo/redo of editing calls, for level editors. With an undo limit set,
+// these calls are recorded (simulation steps never are):
+//
+//   add_ball, seed_ball, remove_ball, drag_ball (a run of drags of the same
+//   ball is one edit), add_circle_obstacle, add_rect_obstacle,
+//   add_polygon_obstacle, move_obstacle, remove_obstacle, clear_obstacles
+//
+// and so are changes to the simulation parameters (Params below: gravity,
+// split settings, walls, ...), which are picked up whenever the next edit is
+// recorded or undo()/redo() is called. A new edit drops the redo history.
+//
+// Undo works on the world as it is now, not as it was: undoing add_ball
+// removes that ball wherever it has moved since, and nothing if it is gone
+// already (a new ball in its slot is left alone). A removed ball comes back in
+// its old slot, or in another one (and later history follows it there) if a
+// split has taken that slot in the meantime; while max_balls live balls
+// exist, undo()/redo() of that edit fail until there is room again.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::obstacles::Obstacle;
+use crate::{sim, ArenaShape, Ball, CapPolicy, Integrator, MixRule, World};
+
+// Copies of every parameter editing undoes as a whole
+macro_rules! params {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub(crate) struct Params {
+            $($field: $ty,)*
+        }
+
+        impl World {
+            pub(crate) fn params(&self) -> Params {
+                Params {
+                    $($field: self.$field,)*
+                }
+            }
+
+            pub(crate) fn apply_params(&mut self, params: Params) {
+                $(self.$field = params.$field;)*
+            }
+        }
+    };
+}
+
+params! {
+    width: f32,
+    height: f32,
+    max_balls: usize,
+    split_ratio: f32,
+    split: sim::SplitConfig,
+    integrator: Integrator,
+    gravity: (f32, f32),
+    substeps: u32,
+    splitting: bool,
+    collisions: bool,
+    impact_split_speed: f32,
+    open_walls: u32,
+    min_radius: f32,
+    max_generation: u32,
+    max_splits_per_frame: Option<u32>,
+    cap_policy: CapPolicy,
+    wall_friction: f32,
+    wall_restitution: f32,
+    mix_rule: MixRule,
+    wall_thickness: f32,
+    arena_shape: ArenaShape,
+    corner_radius: f32,
+    magnus: f32,
+    heating: f32,
+    cooling: f32,
+    split_temperature: f32,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Edit {
+    AddBall {
+        id: u32,
+        ball: Ball,
+    }, // The ball as added
+    RemoveBall {
+        id: u32,
+        ball: Ball,
+    },
+    MoveBall {
+        id: u32,
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    AddObstacle {
+        index: usize,
+        obstacle: Obstacle,
+    },
+    RemoveObstacle {
+        index: usize,
+        obstacle: Obstacle,
+    },
+    // Boxed: a polygon on a path makes an obstacle several hundred bytes
+    MoveObstacle {
+        index: usize,
+        from: Box<Obstacle>,
+        to: Box<Obstacle>,
+    },
+    ClearObstacles {
+        obstacles: Vec<Obstacle>,
+    },
+    Params {
+        before: Params,
+        after: Params,
+    },
+}
+
+impl Edit {
+    fn ball_id(&mut self) -> Option<&mut u32> {
+        match self {
+            Edit::AddBall { id, .. } | Edit::RemoveBall { id, .. } | Edit::MoveBall { id, .. } => {
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct History {
+    limit: usize, // 0 = not recording
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    params: Option<Params>, // As of the last recorded edit
+}
+
+#[wasm_bindgen]
+impl World {
+    // Keep up to `limit` edits for undo() (0, the default, records nothing
+    // and drops the history)
+    pub fn set_undo_limit(&mut self, limit: u32) {
+        let limit = limit as usize;
+        self.history.limit = limit;
+        if limit == 0 {
+            self.history = History::default();
+            return;
+        }
+        let excess = self.history.undo.len().saturating_sub(limit);
+        self.history.undo.drain(..excess);
+        if self.history.params.is_none() {
+            self.history.params = Some(self.params());
+        }
+    }
+
+    pub fn undo_limit(&self) -> u32 {
+        self.history.limit as u32
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty() || self.params_changed()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    // Revert the most recent edit. Returns false if there is nothing to undo,
+    // or if that would bring a removed ball back into a full world.
+    pub fn undo(&mut self) -> bool {
+        self.record_params();
+        if matches!(self.history.undo.back(), Some(Edit::RemoveBall { .. })) && self.is_full() {
+            return false;
+        }
+        let Some(edit) = self.history.undo.pop_back() else {
+            return false;
+        };
+        let edit = self.revert(edit);
+        self.history.redo.push(edit);
+        self.history.params = Some(self.params());
+        true
+    }
+
+    // Re-apply the most recently undone edit (false like undo())
+    pub fn redo(&mut self) -> bool {
+        self.record_params();
+        if matches!(self.history.redo.last(), Some(Edit::AddBall { .. })) && self.is_full() {
+            return false;
+        }
+        let Some(edit) = self.history.redo.pop() else {
+            return false;
+        };
+        let edit = self.reapply(edit);
+        self.history.undo.push_back(edit);
+        self.history.params = Some(self.params());
+        true
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.undo.clear();
+        self.history.redo.clear();
+        self.history.params = (self.history.limit > 0).then(|| self.params());
+    }
+}
+
+impl World {
+    pub(crate) fn record_edit(&mut self, edit: Edit) {
+        if self.history.limit == 0 {
+            return;
+        }
+        self.record_params();
+        self.history.redo.clear();
+        if let (
+            Edit::MoveBall { id, to, .. },
+            Some(Edit::MoveBall {
+                id: last_id,
+                to: last_to,
+                ..
+            }),
+        ) = (&edit, self.history.undo.back_mut())
+        {
+            if id == last_id {
+                *last_to = *to;
+                return;
+            }
+        }
+        self.push_undo(edit);
+    }
+
+    fn params_changed(&self) -> bool {
+        self.history
+            .params
+            .is_some_and(|params| params != self.params())
+    }
+
+    // Turn parameter changes since the last edit into an edit of their own
+    fn record_params(&mut self) {
+        let Some(before) = self.history.params else {
+            return;
+        };
+        let after = self.params();
+        if before != after {
+            self.history.redo.clear();
+            self.push_undo(Edit::Params { before, after });
+            self.history.params = Some(after);
+        }
+    }
+
+    fn push_undo(&mut self, edit: Edit) {
+        if self.history.undo.len() >= self.history.limit {
+            self.history.undo.pop_front();
+        }
+        self.history.undo.push_back(edit);
+    }
+
+    // Undo `edit`, returning it for the redo stack
+    fn revert(&mut self, mut edit: Edit) -> Edit {
+        match &mut edit {
+            Edit::AddBall { id, ball } => self.free_same_ball(*id, ball),
+            Edit::RemoveBall { id, ball } => *id = self.restore_ball(*id, *ball),
+            Edit::MoveBall { id, from, .. } => self.place_ball(*id, *from),
+            Edit::AddObstacle { index, .. } => {
+                if *index < self.obstacles.len() {
+                    self.obstacles.remove(*index);
+                }
+            }
+            Edit::RemoveObstacle { index, obstacle } => {
+                let index = (*index).min(self.obstacles.len());
+                self.obstacles.insert(index, *obstacle);
+            }
+            Edit::MoveObstacle { index, from, .. } => {
+                if let Some(obstacle) = self.obstacles.get_mut(*index) {
+                    *obstacle = **from;
+                }
+            }
+            Edit::ClearObstacles { obstacles } => self.obstacles = obstacles.clone(),
+            Edit::Params { before, .. } => self.apply_params(*before),
+        }
+        edit
+    }
+
+    fn reapply(&mut self, mut edit: Edit) -> Edit {
+        match &mut edit {
+            Edit::AddBall { id, ball } => *id = self.restore_ball(*id, *ball),
+            Edit::RemoveBall { id, ball } => self.free_same_ball(*id, ball),
+            Edit::MoveBall { id, to, .. } => self.place_ball(*id, *to),
+            Edit::AddObstacle { index, obstacle } => {
+                let index = (*index).min(self.obstacles.len());
+                self.obstacles.insert(index, *obstacle);
+            }
+            Edit::RemoveObstacle { index, .. } => {
+                if *index < self.obstacles.len() {
+                    self.obstacles.remove(*index);
+                }
+            }
+            Edit::MoveObstacle { index, to, .. } => {
+                if let Some(obstacle) = self.obstacles.get_mut(*index) {
+                    *obstacle = **to;
+                }
+            }
+            Edit::ClearObstacles { .. } => self.obstacles.clear(),
+            Edit::Params { after, .. } => self.apply_params(*after),
+        }
+        edit
+    }
+
+    fn place_ball(&mut self, id: u32, (x, y): (f32, f32)) {
+        if let Some(ball) = self
+            .balls
+            .get_mut(id as usize)
+            .filter(|ball| ball.alive != 0)
+        {
+            ball.x = x;
+            ball.y = y;
+            self.touch(id as usize);
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.live_count() >= self.max_balls
+    }
+
+    // Free slot `id` if it still holds `ball`, not a later ball that was
+    // given the slot after it left
+    fn free_same_ball(&mut self, id: u32, ball: &Ball) {
+        let same = self.balls.get(id as usize).is_some_and(|live| {
+            live.alive != 0
+                && live.born_frame == ball.born_frame
+                && live.generation == ball.generation
+        });
+        if same {
+            self.free_slot(id);
+        }
+    }
+
+    // Put `ball` back in slot `id` if it is still free, otherwise in a new
+    // one, and return where it went (the caller checks max_balls)
+    fn restore_ball(&mut self, id: u32, ball: Ball) -> u32 {
+        let free = self.free.iter().position(|&slot| slot == id);
+        let Some(position) = free else {
+            let new_id = self.insert_ball(ball);
+            self.balls[new_id as usize] = ball;
+            self.remap_ball(id, new_id);
+            return new_id;
+        };
+        self.free.swap_remove(position);
+        self.balls[id as usize] = ball;
+        self.touch(id as usize);
+        id
+    }
+
+    fn remap_ball(&mut self, from: u32, to: u32) {
+        let history = &mut self.history;
+        for edit in history.undo.iter_mut().chain(history.redo.iter_mut()) {
+            if let Some(id) = edit.ball_id().filter(|id| **id == from) {
+                *id = to;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Emitter, World};
+
+    fn world(max_balls: usize) -> World {
+        let mut world = World::new_empty_seeded(200.0, 150.0, max_balls, 0.7, 1).unwrap();
+        world.set_splitting(false);
+        world.set_undo_limit(8);
+        world
+    }
+
+    // Fill the world from an emitter, which isn't recorded
+    fn fill(world: &mut World) {
+        world.add_emitter(&Emitter::new(100.0, 75.0, 0.0, 0.0, 4.0, 8.0));
+        world.update();
+        world.clear_emitters();
+    }
+
+    // The added ball left and its slot went to another one: undo leaves that one alone
+    #[test]
+    fn undo_add_spares_a_reused_slot() {
+        let mut world = world(4);
+        let id = world.add_ball(50.0, 50.0, 0.0, 0.0, 5.0, 0).unwrap();
+        world.update();
+        world.free_slot(id);
+        fill(&mut world);
+        let count = world.live_count();
+        assert!(world.is_alive(id));
+        assert!(world.undo());
+        assert!(world.is_alive(id));
+        assert_eq!(world.live_count(), count);
+    }
+
+    #[test]
+    fn undo_remove_needs_room() {
+        let mut world = world(2);
+        let id = world.add_ball(50.0, 50.0, 0.0, 0.0, 5.0, 0).unwrap();
+        world.remove_ball(id);
+        fill(&mut world);
+        assert_eq!(world.live_count(), 2);
+        assert!(!world.undo());
+        assert_eq!(world.live_count(), 2);
+
+        let (other, _) = world.live_balls().next().unwrap();
+        world.free_slot(other as u32);
+        assert!(world.undo());
+        assert_eq!(world.live_count(), 2);
+    }
+
+    #[test]
+    fn redo_add_needs_room() {
+        let mut world = world(1);
+        world.add_ball(50.0, 50.0, 0.0, 0.0, 5.0, 0).unwrap();
+        assert!(world.undo());
+        fill(&mut world);
+        assert!(!world.redo());
+        assert_eq!(world.live_count(), 1);
+    }
+}
@@ -0,0 +1,185 @@
+// Where the world's random draws (split jitter, emitter spread, random
+// colors, corner nudges) come from. Normally that is the internal RNG,
+// seeded or from entropy, but there are two alternatives.
+//
+// Host-supplied values, for tests and for hosts seeding from their own data:
+// set_rng_provider() hands the world values in [0, 1) that the next frame's
+// draws take in order. Once they run out the draws fall back to the internal
+// RNG, which doesn't advance while host values last; values the frame didn't
+// use are dropped. Each value stands for one 32-bit draw, v -> floor(v *
+// 2^32), so a draw of a float in [0, 1) gets v back to 24 bits. A draw can
+// take more than one value (e.g. a 64-bit one takes two).
+//
+// Hashed draws, for generative art: with set_hashed_random(true) there is no
+// random stream at all. A ball's draws are a hash of its id, how many times
+// it has bounced off the walls so far and how many draws it has made in this
+// step, so the same scene gives the same picture everywhere without any seed
+// to manage. Draws outside a ball's step (emitters) hash the frame number
+// instead. Host-supplied values still come first.
+
+use rand::RngCore;
+use rand_chacha::rand_core::{impls, Error};
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::{sim, Ball, World};
+
+// Marks frame keys, so they never equal a ball's
+const FRAME_KEY: u64 = 1 << 63;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WorldRng {
+    pub(crate) internal: ChaCha8Rng,
+    supplied: Vec<u32>,
+    next: usize, // Into `supplied`
+    hashed: bool,
+    key: u64,   // What the hashed draws are derived from now
+    draws: u32, // Hashed draws made since the key was set
+    // Per slot while hashed: 1 + born_frame of the ball in it (so a reused
+    // slot starts over) and its wall bounces so far
+    bounces: Vec<(u32, u32)>,
+}
+
+impl StateHash for WorldRng {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_bytes(&self.internal.get_seed());
+        hash.write_u64(self.internal.get_stream());
+        let word_pos = self.internal.get_word_pos();
+        hash.write(&(word_pos as u64, (word_pos >> 64) as u64));
+        hash.write(&self.supplied);
+        hash.write(&self.next);
+        hash.write(&self.hashed);
+        hash.write(&(self.key, self.draws));
+        hash.write(&self.bounces);
+    }
+}
+
+// SplitMix64's finalizer
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl WorldRng {
+    pub(crate) fn new(internal: ChaCha8Rng) -> WorldRng {
+        WorldRng {
+            internal,
+            supplied: Vec::new(),
+            next: 0,
+            hashed: false,
+            key: FRAME_KEY,
+            draws: 0,
+            bounces: Vec::new(),
+        }
+    }
+
+    pub(crate) fn begin_frame(&mut self, frame: u32) {
+        self.set_key(FRAME_KEY | frame as u64);
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.supplied.clear();
+        self.next = 0;
+    }
+
+    fn set_key(&mut self, key: u64) {
+        self.key = key;
+        self.draws = 0;
+    }
+
+    // Derive the hashed draws that follow from ball `id`
+    pub(crate) fn key_ball(&mut self, id: usize, ball: &Ball) {
+        if !self.hashed {
+            return;
+        }
+        let mark = ball.born_frame.wrapping_add(1);
+        let bounces = match self.bounces.get(id) {
+            Some(&(born, count)) if born == mark => count,
+            _ => 0,
+        };
+        self.set_key((id as u64) << 32 | bounces as u64);
+    }
+
+    // Count a wall bounce of ball `id`
+    pub(crate) fn record_bounce(&mut self, id: usize, ball: &Ball, hits: sim::WallHits) {
+        if !self.hashed || !hits.any() {
+            return;
+        }
+        if id >= self.bounces.len() {
+            self.bounces.resize(id + 1, (0, 0));
+        }
+        let mark = ball.born_frame.wrapping_add(1);
+        let (born, count) = self.bounces[id];
+        self.bounces[id] = (mark, if born == mark { count + 1 } else { 1 });
+    }
+}
+
+impl RngCore for WorldRng {
+    fn next_u32(&mut self) -> u32 {
+        match self.supplied.get(self.next) {
+            Some(&value) => {
+                self.next += 1;
+                value
+            }
+            None if self.hashed => {
+                self.draws = self.draws.wrapping_add(1);
+                (mix(self.key ^ mix(self.draws as u64)) >> 32) as u32
+            }
+            None => self.internal.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl sim::SimRng for WorldRng {
+    fn next_u32(&mut self) -> u32 {
+        RngCore::next_u32(self)
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Random values in [0, 1) for the next update()'s draws (see
+    // host_rng.rs), replacing any not used yet. Values outside the range are
+    // clamped into it; NaN counts as 0.
+    pub fn set_rng_provider(&mut self, values: &[f32]) {
+        self.rng.supplied = values
+            .iter()
+            .map(|&value| {
+                let value = if value.is_nan() { 0.0 } else { value as f64 };
+                (value.clamp(0.0, 1.0) * 4_294_967_296.0).min(u32::MAX as f64) as u32
+            })
+            .collect();
+        self.rng.next = 0;
+    }
+
+    // Host values still waiting to be drawn
+    pub fn rng_provided(&self) -> usize {
+        self.rng.supplied.len() - self.rng.next
+    }
+
+    // Derive random draws from hashes instead of the RNG (see host_rng.rs).
+    // Switching starts every ball's bounce count over.
+    pub fn set_hashed_random(&mut self, enabled: bool) {
+        self.rng.hashed = enabled;
+        self.rng.bounces.clear();
+    }
+
+    pub fn hashed_random(&self) -> bool {
+        self.rng.hashed
+    }
+}
@@ -1,8 +1,216 @@
+// With the default `std` feature this is the wasm crate around `World`.
+// Without it only `Ball` and the allocation-only core in `sim` are built,
+// for no_std targets.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "std")]
 use rand::prelude::*;
+#[cfg(feature = "std")]
+use rand_chacha::ChaCha8Rng;
+
+#[cfg(feature = "std")]
+#[macro_use]
+mod logging;
+
+#[cfg(feature = "std")]
+mod after_bounce;
+#[cfg(feature = "std")]
+mod alpha;
+#[cfg(feature = "std")]
+mod arena;
+#[cfg(feature = "std")]
+mod atlas;
+#[cfg(feature = "std")]
+mod background;
+#[cfg(feature = "std")]
+mod bench;
+#[cfg(feature = "std")]
+mod bins;
+#[cfg(feature = "std")]
+mod despawn;
+#[cfg(feature = "std")]
+mod diagnostics;
+#[cfg(feature = "std")]
+mod camera;
+#[cfg(feature = "std")]
+mod capacity;
+#[cfg(feature = "std")]
+mod checkpoint;
+#[cfg(feature = "std")]
+mod clusters;
+#[cfg(feature = "std")]
+mod collision;
+#[cfg(feature = "std")]
+mod colors;
+#[cfg(feature = "std")]
+mod corner_trap;
+#[cfg(feature = "std")]
+mod dynamics;
+#[cfg(feature = "worker")]
+mod driver;
+#[cfg(feature = "std")]
+mod editor;
+#[cfg(feature = "std")]
+mod emitters;
+#[cfg(feature = "std")]
+mod energy;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod events;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "fixed")]
+mod fixed;
+#[cfg(feature = "std")]
+mod flash;
+#[cfg(feature = "std")]
+mod goals;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "std")]
+mod gooey;
+#[cfg(feature = "std")]
+mod heatmap;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
+mod host_rng;
+#[cfg(feature = "std")]
+mod lockstep;
+#[cfg(feature = "std")]
+mod minimap;
+#[cfg(feature = "std")]
+mod mirror;
+#[cfg(feature = "std")]
+mod obstacles;
+#[cfg(feature = "std")]
+mod outlines;
+#[cfg(feature = "web")]
+mod orientation;
+#[cfg(feature = "std")]
+mod plexus;
+#[cfg(feature = "std")]
+mod precision;
+#[cfg(feature = "std")]
+mod profile;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod query;
+#[cfg(feature = "std")]
+mod render;
+pub mod sim;
+#[cfg(feature = "std")]
+mod scene;
+#[cfg(feature = "std")]
+mod schedule;
+#[cfg(feature = "scene")]
+mod scene_json;
+#[cfg(feature = "std")]
+mod settings;
+#[cfg(feature = "std")]
+mod shake;
+#[cfg(feature = "std")]
+mod shockwave;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod squash;
+#[cfg(feature = "std")]
+mod srgb;
+#[cfg(feature = "std")]
+mod teams;
+#[cfg(feature = "std")]
+mod telemetry;
+#[cfg(feature = "std")]
+mod thermal;
+#[cfg(feature = "std")]
+mod thumbnail;
+#[cfg(feature = "scene")]
+mod timeline;
+#[cfg(feature = "std")]
+mod storage;
+#[cfg(feature = "std")]
+mod trails;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(feature = "std")]
+mod views;
+#[cfg(feature = "std")]
+mod voronoi;
+#[cfg(feature = "std")]
+mod water;
+#[cfg(feature = "web")]
+mod web;
+
+#[cfg(feature = "std")]
+pub use alpha::OutputAlpha;
+#[cfg(feature = "std")]
+pub use arena::ArenaShape;
+#[cfg(feature = "std")]
+pub use atlas::Atlas;
+#[cfg(feature = "std")]
+pub use background::BackgroundFit;
+#[cfg(feature = "std")]
+pub use bench::{bench, BenchReport};
+#[cfg(feature = "std")]
+pub use capacity::CapPolicy;
+#[cfg(feature = "std")]
+pub use colors::AutoColor;
+#[cfg(feature = "std")]
+pub use despawn::DespawnStyle;
+#[cfg(feature = "std")]
+pub use clusters::Clusters;
+#[cfg(feature = "std")]
+pub use diagnostics::{build_info, crate_version, enabled_features, init_diagnostics};
+#[cfg(feature = "worker")]
+pub use driver::WorldDriver;
+#[cfg(feature = "console_log")]
+pub use logging::init_console_log;
+#[cfg(feature = "std")]
+pub use minimap::MinimapCorner;
+#[cfg(feature = "std")]
+pub use emitters::Emitter;
+#[cfg(feature = "std")]
+pub use energy::EnergyReport;
+#[cfg(feature = "std")]
+pub use error::WorldError;
+#[cfg(feature = "gpu")]
+pub use gpu::GpuBackend;
+#[cfg(feature = "std")]
+pub use events::{Event, EventKind};
+#[cfg(feature = "std")]
+pub use precision::Precision;
+#[cfg(feature = "std")]
+pub use profile::FrameTimings;
+#[cfg(feature = "std")]
+pub use query::{HitKind, RayHit};
+#[cfg(feature = "std")]
+pub use render::{ColorMode, DrawOrder};
+#[cfg(feature = "std")]
+pub use scene::preset_names;
+#[cfg(feature = "std")]
+pub use schedule::Action;
+#[cfg(feature = "std")]
+pub use telemetry::TelemetryFormat;
+#[cfg(feature = "std")]
+pub use validate::ValidationError;
+#[cfg(feature = "std")]
+pub use views::BallView;
+#[cfg(feature = "web")]
+pub use web::RunLoop;
+pub use sim::{
+    ChildColor, Integrator, MixRule, SplitConfig, SplitDirection, SplitKinematics, SANITIZED_POSITION,
+    SANITIZED_RADIUS, SANITIZED_VELOCITY, WALL_BOTTOM, WALL_LEFT, WALL_RIGHT, WALL_TOP,
+};
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Ball {
     pub x: f32,
     pub y: f32,
@@ -10,182 +218,529 @@ pub struct Ball {
     pub vy: f32,
     pub radius: f32,
     pub color: u32,
-    pub just_split: u32, // Using u32 instead of bool for C compatibility (0 = false, 1 = true)
+    pub just_split: u32,  // Using u32 instead of bool for C compatibility (0 = false, 1 = true)
+    pub tag: u32,         // Opaque host data (team, owner, type...), inherited by split children
+    pub layer: u8,        // Draw layer: lower layers are painted first, inherited by split children
+    pub alive: u32,       // 0 = free slot (radius is also 0), 1 = live ball
+    pub born_frame: u32,  // Frame the ball appeared in (World::frame() when added, or the frame of its split)
+    pub generation: u32,  // Splits since the original ball: both halves of a split count one more
+    pub spin: f32,        // Angular velocity in radians/frame (positive = clockwise on screen)
+    pub temperature: f32, // Raised by impacts, see thermal.rs (0 = cold)
+    pub restitution: f32, // Bounciness, mixed with the wall's or the other ball's (1 = elastic)
+    pub friction: f32,    // Grip, mixed with the wall friction (1 = the wall's as is)
+}
+
+// An empty slot. Restitution and friction start at 1, which leaves the
+// walls' coefficients as they are under the default MixRule::Product.
+impl Default for Ball {
+    fn default() -> Ball {
+        Ball {
+            x: 0.0,
+            y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            radius: 0.0,
+            color: 0,
+            just_split: 0,
+            tag: 0,
+            layer: 0,
+            alive: 0,
+            born_frame: 0,
+            generation: 0,
+            spin: 0.0,
+            temperature: 0.0,
+            restitution: 1.0,
+            friction: 1.0,
+        }
+    }
+}
+
+impl Ball {
+    pub fn new(x: f32, y: f32, vx: f32, vy: f32, radius: f32, color: u32) -> Ball {
+        Ball {
+            x,
+            y,
+            vx,
+            vy,
+            radius,
+            color,
+            alive: 1,
+            ..Ball::default()
+        }
+    }
 }
 
+#[cfg(feature = "std")]
+// Each World owns all of its state, including its RNG, so any number of
+// instances can coexist and a clone continues exactly like the original.
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct World {
     balls: Vec<Ball>,
     width: f32,
     height: f32,
     max_balls: usize,
     split_ratio: f32,
+    rng: host_rng::WorldRng,
+    deterministic: bool,
+    frame: u32,
+    modified: Vec<u32>, // Per ball: frame at which it last changed (for delta snapshots)
+    free: Vec<u32>,     // Free slots in `balls`, reused before the array grows
+    events: Vec<Event>,
+    render: render::RenderState,
+    mirror: Option<mirror::Mirror>,
+    profile: profile::Profile,
+    precise: Option<Vec<precision::Precise>>, // f64 shadow state in Precision::F64 mode
+    #[cfg(feature = "fixed")]
+    fixed: Option<Vec<fixed::FixedState>>, // Q16.16 shadow state with set_fixed_point(true)
+    integrator: sim::Integrator,
+    gravity: (f32, f32),
+    substeps: u32,
+    energy: energy::Ledger,
+    splitting: bool,
+    collisions: bool,
+    impact_split_speed: f32, // Ball-ball impacts this fast split too (0 = off)
+    emitters: Vec<Emitter>,
+    attractors: Vec<dynamics::Attractor>,
+    open_walls: u32,
+    obstacles: Vec<obstacles::Obstacle>,
+    min_radius: f32,
+    max_generation: u32,
+    split: sim::SplitConfig,
+    max_splits_per_frame: Option<u32>,
+    splits_left: u32, // Of max_splits_per_frame, during update()
+    cap_policy: CapPolicy,
+    wall_friction: f32,
+    wall_restitution: f32,
+    mix_rule: MixRule,
+    wall_thickness: f32,
+    wall_color: u32,
+    arena_shape: arena::ArenaShape,
+    corner_radius: f32,
+    magnus: f32,
+    force: (f32, f32), // apply_global_force() input for the next update()
+    heating: f32,
+    cooling: f32,
+    split_temperature: f32,
+    squash: squash::Squash,
+    corner_trap: corner_trap::CornerTrap,
+    editor: editor::Editor,
+    history: history::History,
+    checkpoints: checkpoint::Checkpoints,
+    shockwaves: shockwave::Shockwaves,
+    wall_heat: heatmap::WallHeat,
+    trails: trails::Trails,
+    despawns: despawn::Despawns,
+    minimap: minimap::Minimap,
+    outlines: outlines::Outlines,
+    plexus: plexus::Plexus,
+    gooey: gooey::Gooey,
+    water: water::Water,
+    voronoi: voronoi::Voronoi,
+    schedule: schedule::Schedule,
+    camera: Option<camera::Camera>,
+    shake: shake::Shake,
+    flashes: flash::Flashes,
+    bins: bins::Bins,
+    goals: Vec<goals::Goal>,
+    teams: teams::Teams,
+    telemetry: telemetry::Telemetry,
+    auto_color: colors::AutoColorState,
+    min_contrast: f32,
+    #[cfg(feature = "web")]
+    canvas: Option<web::Canvas>,
+    #[cfg(feature = "web")]
+    orientation: Option<orientation::Orientation>,
 }
 
+#[cfg(feature = "std")]
 #[wasm_bindgen]
 impl World {
     pub fn new(width: f32, height: f32, max_balls: usize, split_ratio: f32) -> World {
-        let mut balls = Vec::with_capacity(max_balls);
-        balls.push(Ball {
-            x: width / 2.0,
-            y: height / 2.0,
-            vx: 8.0,
-            vy: -6.0,
-            radius: 60.0,
-            color: 0xFF4444,
-            just_split: 0,
-        });
-        World {
-            balls,
-            width,
-            height,
-            max_balls,
-            split_ratio,
-        }
+        World::with_rng(width, height, max_balls, split_ratio, ChaCha8Rng::from_entropy(), false)
     }
 
-    pub fn update(&mut self) {
-        let mut new_balls = Vec::new();
-        let mut rng = rand::thread_rng();
-        let current_len = self.balls.len();
-
-        for ball in &mut self.balls {
-            // Reset the just_split flag at the start of each frame
-            let was_just_split = ball.just_split == 1;
-            ball.just_split = 0;
-            
-            ball.x += ball.vx;
-            ball.y += ball.vy;
-
-            let mut hit_x = false;
-            let mut hit_y = false;
-
-            // Bounce x
-            if ball.x - ball.radius < 0.0 {
-                ball.x = ball.radius;
-                ball.vx = ball.vx.abs(); // Force positive (right)
-                hit_x = true;
-            } else if ball.x + ball.radius > self.width {
-                ball.x = self.width - ball.radius;
-                ball.vx = -ball.vx.abs(); // Force negative (left)
-                hit_x = true;
-            }
+    // Like `new`, but rejects nonsense configurations (throws in JS)
+    pub fn try_new(width: f32, height: f32, max_balls: usize, split_ratio: f32) -> Result<World, WorldError> {
+        validate_config(width, height, max_balls, split_ratio)?;
+        Ok(World::new(width, height, max_balls, split_ratio))
+    }
 
-            // Bounce y
-            if ball.y - ball.radius < 0.0 {
-                ball.y = ball.radius;
-                ball.vy = ball.vy.abs(); // Force positive (down)
-                hit_y = true;
-            } else if ball.y + ball.radius > self.height {
-                ball.y = self.height - ball.radius;
-                ball.vy = -ball.vy.abs(); // Force negative (up)
-                hit_y = true;
-            }
+    // Fork the simulation: the copy shares nothing with the original and,
+    // since the RNG state is copied too, replays the same future until either diverges.
+    pub fn clone_world(&self) -> World {
+        self.clone()
+    }
 
-            // Split logic: only split if we hit a wall AND didn't just split in the previous frame
-            if (hit_x || hit_y) && !was_just_split && (current_len + new_balls.len() < self.max_balls) {
-                // Calculate new radius
-                let new_radius = ball.radius * self.split_ratio;
-                
-                // Only split if new radius would be >= 1.0 pixel
-                if new_radius >= 1.0 {
-                    ball.radius = new_radius;
-                    ball.just_split = 1;
-                    
-                    // Create new ball
-                    let mut new_ball = *ball;
-                    
-                    // Randomize velocity slightly but keep direction away from wall
-                    let speed_factor = 0.8 + rng.gen::<f32>() * 0.4;
-                    
-                    new_ball.vx = ball.vx * speed_factor;
-                    new_ball.vy = ball.vy * speed_factor;
-                    
-                    // Add slight angle jitter to make the split more visible
-                    if hit_x {
-                        // Perturb VY freely, but keep VX sign
-                        new_ball.vy += (rng.gen::<f32>() - 0.5) * 2.0;
-                    }
-                    if hit_y {
-                        // Perturb VX freely, but keep VY sign
-                        new_ball.vx += (rng.gen::<f32>() - 0.5) * 2.0;
-                    }
-
-                    // Random color for new ball
-                    new_ball.color = rng.gen::<u32>() & 0xFFFFFF;
-                    new_ball.just_split = 1;
-                    
-                    new_balls.push(new_ball);
-                } else {
-                    // Keep minimum radius of 1.0
-                    ball.radius = ball.radius.max(1.0);
-                }
+    pub fn update(&mut self) {
+        if self.editor.enabled {
+            // Frozen: only publish the edits made since the last call
+            self.sync_mirror();
+            return;
+        }
+        let stamp = self.frame.wrapping_add(1);
+        self.profile.begin_frame(stamp);
+        self.energy.begin_frame(stamp);
+        self.splits_left = self.max_splits_per_frame.unwrap_or(u32::MAX);
+        self.rng.begin_frame(stamp);
+        #[cfg(feature = "web")]
+        self.apply_orientation();
+        self.run_schedule(stamp);
+        self.emit(stamp);
+        self.propagate_shockwaves(stamp);
+        for _ in 0..self.substeps {
+            self.attract(stamp);
+            self.immerse(stamp);
+            #[cfg(feature = "fixed")]
+            let fixed = self.fixed.is_some();
+            #[cfg(not(feature = "fixed"))]
+            let fixed = false;
+            if fixed {
+                #[cfg(feature = "fixed")]
+                self.step_fixed(stamp);
+            } else if self.precise.is_some() {
+                self.step_f64(stamp);
+            } else if self.profile.enabled() {
+                self.step_profiled(stamp);
+            } else {
+                self.step(stamp);
+            }
+            if self.collisions {
+                self.collide(stamp);
             }
+            self.move_obstacles();
+            self.collide_obstacles(stamp);
+            self.remove_escaped(stamp);
+            #[cfg(feature = "debug")]
+            self.assert_valid();
         }
-
-        self.balls.append(&mut new_balls);
+        self.check_goals(stamp);
+        self.check_teams(stamp);
+        self.apply_auto_color(stamp);
+        self.force = (0.0, 0.0);
+        self.trails.record(&self.balls);
+        self.frame = stamp;
+        self.despawns.expire(stamp);
+        self.record_telemetry();
+        self.rng.end_frame();
+        self.sync_mirror();
     }
 
     pub fn get_balls_ptr(&self) -> *const Ball {
         self.balls.as_ptr()
     }
 
+    // Number of slots behind get_balls_ptr, including free ones (alive == 0)
     pub fn get_balls_len(&self) -> usize {
         self.balls.len()
     }
-    
-    // New: Render directly to pixel buffer (RGBA format for ImageData)
-    pub fn render_to_buffer(&self, buffer: &mut [u8], width: usize, height: usize) {
-        // Clear buffer (black background)
-        for pixel in buffer.chunks_exact_mut(4) {
-            pixel[0] = 26;  // R
-            pixel[1] = 26;  // G
-            pixel[2] = 26;  // B
-            pixel[3] = 255; // A
+
+    // Ball ids are slot indices into the balls array. They stay valid until the ball is
+    // removed, after which the slot may be reused. Returns false if the id is not a live ball.
+    pub fn set_tag(&mut self, id: u32, tag: u32) -> bool {
+        match self.balls.get_mut(id as usize).filter(|ball| ball.alive != 0) {
+            Some(ball) => {
+                ball.tag = tag;
+                self.touch(id as usize);
+                true
+            }
+            None => false,
         }
-        
-        // Draw each ball as filled circles
-        for ball in &self.balls {
-            let cx = ball.x;
-            let cy = ball.y;
-            let r = ball.radius;
-            let r_squared = r * r;
-            
-            // Extract RGB from color
-            let red = ((ball.color >> 16) & 0xFF) as u8;
-            let green = ((ball.color >> 8) & 0xFF) as u8;
-            let blue = (ball.color & 0xFF) as u8;
-            
-            // Bounding box for efficiency
-            let x_min = ((cx - r).max(0.0) as i32).max(0);
-            let x_max = ((cx + r).min(width as f32) as i32).min(width as i32);
-            let y_min = ((cy - r).max(0.0) as i32).max(0);
-            let y_max = ((cy + r).min(height as f32) as i32).min(height as i32);
-            
-            // Draw filled circle using distance check
-            for py in y_min..y_max {
-                for px in x_min..x_max {
-                    let dx = px as f32 - cx;
-                    let dy = py as f32 - cy;
-                    let dist_squared = dx * dx + dy * dy;
-                    
-                    // Only draw if inside circle
-                    if dist_squared <= r_squared {
-                        let idx = ((py as usize * width + px as usize) * 4) as usize;
-                        if idx + 3 < buffer.len() {
-                            buffer[idx] = red;
-                            buffer[idx + 1] = green;
-                            buffer[idx + 2] = blue;
-                            buffer[idx + 3] = 255;
-                        }
-                    }
-                }
+    }
+
+    // Move a ball to another draw layer. Returns false if the id does not exist.
+    pub fn set_layer(&mut self, id: u32, layer: u8) -> bool {
+        match self.balls.get_mut(id as usize).filter(|ball| ball.alive != 0) {
+            Some(ball) => {
+                ball.layer = layer;
+                self.touch(id as usize);
+                true
             }
+            None => false,
         }
     }
+
+    // Ids of all balls carrying the given tag (Uint32Array on the JS side)
+    pub fn balls_with_tag(&self, tag: u32) -> Vec<u32> {
+        self.live_balls()
+            .filter(|(_, ball)| ball.tag == tag)
+            .map(|(id, _)| id as u32)
+            .collect()
+    }
     
     // Get pointer to pixel buffer for zero-copy transfer
     pub fn get_buffer_ptr(&self) -> *const u8 {
         std::ptr::null() // Placeholder - buffer will be passed from JS
     }
 }
+
+#[cfg(feature = "std")]
+pub(crate) fn validate_config(width: f32, height: f32, max_balls: usize, split_ratio: f32) -> Result<(), WorldError> {
+    if !(width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite()) {
+        return Err(WorldError::InvalidDimensions { width, height });
+    }
+    if max_balls == 0 {
+        return Err(WorldError::InvalidMaxBalls);
+    }
+    if !(split_ratio > 0.0 && split_ratio < 1.0) {
+        return Err(WorldError::InvalidSplitRatio(split_ratio));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+impl World {
+    fn with_rng(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+        rng: ChaCha8Rng,
+        deterministic: bool,
+    ) -> World {
+        // The classic start: one big red ball in the middle, part of frame 0
+        let mut world = World::empty_with_rng(width, height, max_balls, split_ratio, rng, deterministic);
+        world.balls.push(Ball::new(width / 2.0, height / 2.0, 8.0, -6.0, 60.0, 0xFF4444));
+        world.modified.push(0);
+        world
+    }
+
+    fn empty_with_rng(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+        rng: ChaCha8Rng,
+        deterministic: bool,
+    ) -> World {
+        World {
+            balls: storage::preallocated(max_balls),
+            width,
+            height,
+            max_balls,
+            split_ratio,
+            rng: host_rng::WorldRng::new(rng),
+            deterministic,
+            frame: 0,
+            modified: Vec::new(),
+            free: Vec::new(),
+            events: Vec::new(),
+            render: render::RenderState::default(),
+            mirror: None,
+            profile: profile::Profile::default(),
+            precise: None,
+            #[cfg(feature = "fixed")]
+            fixed: None,
+            integrator: sim::Integrator::Euler,
+            gravity: (0.0, 0.0),
+            substeps: 1,
+            energy: energy::Ledger::default(),
+            splitting: true,
+            collisions: false,
+            impact_split_speed: 0.0,
+            emitters: Vec::new(),
+            attractors: Vec::new(),
+            open_walls: 0,
+            obstacles: Vec::new(),
+            min_radius: 1.0,
+            max_generation: u32::MAX,
+            split: sim::SplitConfig::default(),
+            max_splits_per_frame: None,
+            splits_left: u32::MAX,
+            cap_policy: CapPolicy::Reject,
+            wall_friction: 0.0,
+            wall_restitution: 1.0,
+            mix_rule: MixRule::default(),
+            wall_thickness: 0.0,
+            wall_color: arena::DEFAULT_WALL_COLOR,
+            arena_shape: arena::ArenaShape::Rectangle,
+            corner_radius: 0.0,
+            magnus: 0.0,
+            force: (0.0, 0.0),
+            heating: 0.0,
+            cooling: 0.0,
+            split_temperature: 0.0,
+            squash: squash::Squash::default(),
+            corner_trap: corner_trap::CornerTrap::default(),
+            editor: editor::Editor::default(),
+            history: history::History::default(),
+            checkpoints: checkpoint::Checkpoints::default(),
+            shockwaves: shockwave::Shockwaves::default(),
+            wall_heat: heatmap::WallHeat::default(),
+            trails: trails::Trails::default(),
+            despawns: despawn::Despawns::default(),
+            minimap: minimap::Minimap::default(),
+            outlines: outlines::Outlines::default(),
+            plexus: plexus::Plexus::default(),
+            gooey: gooey::Gooey::default(),
+            water: water::Water::default(),
+            voronoi: voronoi::Voronoi::default(),
+            schedule: schedule::Schedule::default(),
+            camera: None,
+            shake: shake::Shake::default(),
+            flashes: flash::Flashes::default(),
+            bins: bins::Bins::default(),
+            goals: Vec::new(),
+            teams: teams::Teams::default(),
+            telemetry: telemetry::Telemetry::default(),
+            auto_color: colors::AutoColorState::default(),
+            min_contrast: 1.0,
+            #[cfg(feature = "web")]
+            canvas: None,
+            #[cfg(feature = "web")]
+            orientation: None,
+        }
+    }
+
+    // One (sub-)step of update(): every live ball is advanced by config.dt
+    fn step(&mut self, stamp: u32) {
+        let config = self.sim_config();
+        let (width, height) = (self.width, self.height);
+        let (balls, modified, mut hook) = self.after_bounce(stamp);
+
+        for (id, (ball, modified)) in balls.iter_mut().zip(modified.iter_mut()).enumerate() {
+            if ball.alive == 0 {
+                continue;
+            }
+            let before = *ball;
+
+            // A single NaN would otherwise propagate forever (and make the ball vanish)
+            let sanitized = sim::sanitize_ball(ball, width, height);
+            if sanitized != 0 {
+                events::push_sanitized(hook.events, id, stamp, ball, sanitized);
+            }
+
+            let (hits, was_just_split) = sim::advance_motion(ball, &config);
+            hook.run(id, ball, hits, was_just_split);
+
+            if *ball != before {
+                *modified = stamp;
+            }
+        }
+
+        let (children, denied) = (hook.children, hook.denied);
+        self.insert_children(stamp, children, denied);
+    }
+
+    // How many children the next (sub-)step may add: what fits under max_balls
+    // (or, with a Replace* cap policy, could replace other balls), and what is
+    // left of this frame's max_splits_per_frame. `candidates` counts the live
+    // balls make_room may remove, those that haven't split in this step yet;
+    // sizing the splits up front means a parent never shrinks for a child
+    // that then has no room.
+    fn split_capacity(&self, candidates: usize) -> usize {
+        let free = self.max_balls.saturating_sub(self.live_count());
+        let fits = match self.cap_policy {
+            CapPolicy::Reject => free,
+            // Past the free slots, every child needs a ball freed that doesn't split itself
+            CapPolicy::ReplaceOldest | CapPolicy::ReplaceSmallest => free.max((free + candidates) / 2),
+        };
+        fits.min(self.splits_left as usize)
+    }
+
+    // Children that made it into the world this (sub-)step
+    fn count_splits(&mut self, count: usize) {
+        self.splits_left = self.splits_left.saturating_sub(count as u32);
+        self.telemetry.record_splits(count);
+    }
+
+    fn log_denied(&self, stamp: u32, denied: usize) {
+        if denied > 0 {
+            log_debug!(
+                "frame {stamp}: {denied} splits denied (max_balls {}, max_splits_per_frame {:?})",
+                self.max_balls,
+                self.max_splits_per_frame
+            );
+        }
+    }
+
+    fn sim_config(&self) -> sim::SimConfig {
+        sim::SimConfig {
+            width: self.width,
+            height: self.height,
+            max_balls: self.max_balls,
+            split_ratio: self.split_ratio,
+            split: self.split,
+            integrator: self.integrator,
+            gravity_x: self.gravity.0 + self.force.0,
+            gravity_y: self.gravity.1 + self.force.1,
+            dt: 1.0 / self.substeps as f32,
+            splitting: self.splitting,
+            open_walls: self.open_walls,
+            min_radius: self.min_radius,
+            max_generation: self.max_generation,
+            wall_friction: self.wall_friction,
+            magnus: self.magnus,
+            heating: self.heating,
+            cooling: self.cooling,
+            split_temperature: self.split_temperature,
+            wall_inset: self.wall_inset(),
+            corner_radius: self.arena_corner_radius(),
+            wall_restitution: self.wall_restitution,
+            mix_rule: self.mix_rule,
+            background: sim::CLEAR_COLOR,
+            min_contrast: self.min_contrast,
+        }
+    }
+
+    // Mark a ball as changed outside of update() so the next delta snapshot includes it
+    fn touch(&mut self, id: usize) {
+        if let Some(modified) = self.modified.get_mut(id) {
+            *modified = self.frame.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl sim::SimRng for ChaCha8Rng {
+    fn next_u32(&mut self) -> u32 {
+        RngCore::next_u32(self)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{SplitConfig, SplitDirection, SplitKinematics, World, WorldError};
+
+    #[test]
+    fn try_new_rejects_broken_configs() {
+        for (width, height) in [(f32::NAN, 1.0), (1.0, f32::INFINITY), (-1.0, 1.0), (0.0, 1.0)] {
+            assert!(matches!(
+                World::try_new(width, height, 1, 0.5),
+                Err(WorldError::InvalidDimensions { .. })
+            ));
+        }
+        assert!(matches!(World::try_new(1.0, 1.0, 0, 0.5), Err(WorldError::InvalidMaxBalls)));
+        for ratio in [f32::NAN, 0.0, 1.0, f32::INFINITY] {
+            assert!(matches!(World::try_new(1.0, 1.0, 1, ratio), Err(WorldError::InvalidSplitRatio(_))));
+        }
+    }
+
+    // An absurd max_balls just can't preallocate
+    #[test]
+    fn huge_max_balls_is_allowed() {
+        assert!(World::try_new(1.0, 1.0, usize::MAX, 0.5).is_ok());
+    }
+
+    // Splitting in half doesn't speed the pair up: their mean velocity is the
+    // parent's and neither is faster than the parent plus half the kick
+    #[test]
+    fn momentum_splits_keep_the_parents_speed() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 8, 0.5, 1).unwrap();
+        world.set_split_config(SplitConfig {
+            kinematics: SplitKinematics::Momentum,
+            ..SplitConfig::new(SplitDirection::Parent, 0.0)
+        });
+        world.add_ball(190.0, 75.0, 8.0, 0.0, 20.0, 0).unwrap();
+        world.update();
+        assert_eq!(world.live_count(), 2);
+        let speed = |vx: f32, vy: f32| (vx * vx + vy * vy).sqrt();
+        let (a, b) = (world.ball(0).unwrap(), world.ball(1).unwrap());
+        let mean = (0.5 * (a.vx() + b.vx()), 0.5 * (a.vy() + b.vy()));
+        assert!((speed(mean.0, mean.1) - 8.0).abs() < 1e-3, "{mean:?}");
+        let kick = 0.5 * speed(b.vx() - a.vx(), b.vy() - a.vy());
+        for fragment in [a, b] {
+            assert!(speed(fragment.vx(), fragment.vy()) <= 8.0 + kick + 1e-3);
+        }
+    }
+}
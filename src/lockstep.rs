@@ -0,0 +1,349 @@
+// Deterministic lockstep support.
+//
+// A world created with `new_seeded` draws every random number from a ChaCha8
+// stream seeded by the caller, and `update()` always advances by exactly one
+// fixed frame (velocities are in pixels/frame, there is no wall-clock dt).
+// Most of the step is IEEE-754 add/sub/mul/div and sqrt on f32, which are
+// correctly rounded on every target, and Rust never fuses a multiply-add on
+// its own. The rest isn't: tilt (dynamics.rs), rotating obstacles
+// (obstacles.rs) and corner-trap nudges call sin_cos, and water calls acos,
+// whose last bits depend on the math library the build links (for wasm32,
+// the libm port inside Rust's std, so the same in every browser). So two
+// peers running the same build for the same target with the same seed and
+// the same inputs produce bit-identical worlds; a wasm peer and a native
+// one, or two native platforms, can drift apart. Compare `state_hash()`
+// every frame to detect desyncs.
+//
+// The hash covers everything a later update() reads, field by field, floats
+// by their bits: the balls, the random streams, the settings, the editor's
+// freeze and the stateful features (the f64 and Q16.16 shadows, obstacles,
+// emitters, attractors, shockwaves, the schedule, water, goals, corner-trap
+// and auto-color state). Left out, as update() only writes them for the
+// host: events, the energy ledger, telemetry, team totals, trails, despawn
+// fades, squash, shake, flashes, the wall heat map, render settings, the
+// camera and the mirror. So are profiling (same results either way), undo
+// history and checkpoints, `is_deterministic()` and the device-orientation
+// reading of web builds, which reaches the step as gravity. Adding a hashed
+// field changes every hash, so peers have to run the same version anyway.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::sim::{SimConfig, SplitConfig};
+use crate::{World, WorldError};
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+pub(crate) struct Fnv1a(u32);
+
+impl Fnv1a {
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u32;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub(crate) fn write(&mut self, value: &(impl StateHash + ?Sized)) {
+        value.hash_into(self);
+    }
+}
+
+// What state_hash() feeds in for a piece of state. Each stateful type
+// writes its own fields next to its definition.
+pub(crate) trait StateHash {
+    fn hash_into(&self, hash: &mut Fnv1a);
+}
+
+impl StateHash for u32 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u32(*self);
+    }
+}
+
+impl StateHash for i32 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u32(*self as u32);
+    }
+}
+
+impl StateHash for u64 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u64(*self);
+    }
+}
+
+impl StateHash for usize {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u64(*self as u64);
+    }
+}
+
+impl StateHash for bool {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u32(*self as u32);
+    }
+}
+
+impl StateHash for f32 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_f32(*self);
+    }
+}
+
+impl StateHash for f64 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u64(self.to_bits());
+    }
+}
+
+impl<A: StateHash, B: StateHash> StateHash for (A, B) {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        self.0.hash_into(hash);
+        self.1.hash_into(hash);
+    }
+}
+
+impl<T: StateHash> StateHash for Option<T> {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        match self {
+            None => hash.write_u32(0),
+            Some(value) => {
+                hash.write_u32(1);
+                value.hash_into(hash);
+            }
+        }
+    }
+}
+
+// Length first, so [a, b] + [] and [a] + [b] differ
+impl<T: StateHash> StateHash for [T] {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u64(self.len() as u64);
+        for value in self {
+            value.hash_into(hash);
+        }
+    }
+}
+
+impl<T: StateHash> StateHash for Vec<T> {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        self.as_slice().hash_into(hash);
+    }
+}
+
+// sim.rs builds without std, so its types are hashed here
+impl StateHash for SplitConfig {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u32(self.direction as u32);
+        hash.write_f32(self.cone_angle);
+        hash.write_u32(self.kinematics as u32);
+        hash.write_u32(self.grow_frames);
+        hash.write_u32(self.child_color as u32);
+    }
+}
+
+impl StateHash for SimConfig {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&(self.width, self.height));
+        hash.write(&self.max_balls);
+        hash.write_f32(self.split_ratio);
+        hash.write(&self.split);
+        hash.write_u32(self.integrator as u32);
+        hash.write(&(self.gravity_x, self.gravity_y));
+        hash.write_f32(self.dt);
+        hash.write(&self.splitting);
+        hash.write_u32(self.open_walls);
+        hash.write_f32(self.min_radius);
+        hash.write_u32(self.max_generation);
+        for value in [
+            self.wall_friction,
+            self.magnus,
+            self.heating,
+            self.cooling,
+            self.split_temperature,
+            self.wall_inset,
+            self.corner_radius,
+            self.wall_restitution,
+        ] {
+            hash.write_f32(value);
+        }
+        hash.write_u32(self.mix_rule as u32);
+        hash.write_u32(self.background);
+        hash.write_f32(self.min_contrast);
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Same as `new`, but fully reproducible from `seed`
+    pub fn new_seeded(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+        seed: u32,
+    ) -> World {
+        World::with_rng(
+            width,
+            height,
+            max_balls,
+            split_ratio,
+            ChaCha8Rng::seed_from_u64(seed as u64),
+            true,
+        )
+    }
+
+    // Validated variant of `new_seeded`
+    pub fn try_new_seeded(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+        seed: u32,
+    ) -> Result<World, WorldError> {
+        crate::validate_config(width, height, max_balls, split_ratio)?;
+        Ok(World::new_seeded(
+            width,
+            height,
+            max_balls,
+            split_ratio,
+            seed,
+        ))
+    }
+
+    // Restart the random stream from `seed` and switch the world to deterministic mode
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rng.internal = ChaCha8Rng::seed_from_u64(seed as u64);
+        self.deterministic = true;
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    // Number of update() calls since construction
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    // FNV-1a checksum of the frame counter, every ball's bit pattern, the
+    // random streams and all other state that affects the simulation (see
+    // lockstep.rs)
+    pub fn state_hash(&self) -> u32 {
+        let mut hash = Fnv1a(FNV_OFFSET);
+        hash.write_u32(self.frame);
+        hash.write_u32(self.balls.len() as u32);
+        let mut record = Vec::with_capacity(crate::Ball::ENCODED_LEN);
+        for ball in &self.balls {
+            record.clear();
+            ball.encode(&mut record);
+            hash.write_bytes(&record);
+        }
+        hash.write(&self.free);
+        hash.write(&self.sim_config());
+        hash.write(&(self.gravity, self.force));
+        hash.write(&self.substeps);
+        hash.write(&(self.collisions, self.impact_split_speed));
+        hash.write(&self.max_splits_per_frame);
+        hash.write_u32(self.cap_policy as u32);
+        hash.write(&self.editor.enabled);
+        hash.write(&self.rng);
+        hash.write(&self.precise);
+        #[cfg(feature = "fixed")]
+        hash.write(&self.fixed);
+        hash.write(&self.emitters);
+        hash.write(&self.attractors);
+        hash.write(&self.obstacles);
+        hash.write(&self.corner_trap);
+        hash.write(&self.shockwaves);
+        hash.write(&self.schedule);
+        hash.write(&self.water);
+        hash.write(&self.goals);
+        hash.write(&self.auto_color);
+        hash.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Emitter, World};
+
+    fn scene(seed: u32) -> World {
+        let mut world = World::new_seeded(400.0, 300.0, 500, 0.7, seed);
+        world.set_collisions(true);
+        world.set_gravity(0.0, 0.2);
+        world.set_substeps(2);
+        world.set_hashed_random(true);
+        world.set_corner_trap(3, 2.0);
+        world.add_circle_obstacle(200.0, 150.0, 30.0).unwrap();
+        world.add_rect_obstacle(50.0, 250.0, 80.0, 10.0).unwrap();
+        world.add_emitter(&Emitter::new(100.0, 20.0, 1.0, 0.0, 4.0, 0.1));
+        world
+    }
+
+    // Two peers replaying the same inputs stay bit-identical, frame by frame
+    #[test]
+    fn seeded_replay_matches() {
+        let (mut a, mut b) = (scene(42), scene(42));
+        for frame in 0..300 {
+            if frame == 120 {
+                for world in [&mut a, &mut b] {
+                    world.shockwave(200.0, 100.0, 80.0, 3.0);
+                    world.set_gravity(0.1, 0.1);
+                }
+            }
+            a.update();
+            b.update();
+            assert_eq!(a.state_hash(), b.state_hash(), "desync at frame {frame}");
+        }
+        let balls = |world: &World| {
+            world
+                .live_balls()
+                .map(|(_, ball)| *ball)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(balls(&a), balls(&b));
+    }
+
+    // Inputs that haven't moved a ball yet still change the hash
+    #[test]
+    fn hash_covers_settings_and_features() {
+        let base = scene(42);
+        let changes: [fn(&mut World); 8] = [
+            |world| world.set_gravity(0.0, 0.3),
+            |world| world.set_substeps(3),
+            |world| world.set_hashed_random(false),
+            |world| {
+                world.add_circle_obstacle(10.0, 10.0, 5.0);
+            },
+            |world| {
+                world.add_emitter(&Emitter::new(10.0, 10.0, 0.0, 0.0, 2.0, 1.0));
+            },
+            |world| world.set_integrator(crate::Integrator::Verlet),
+            |world| world.set_edit_mode(true),
+            |world| {
+                world.shockwave(10.0, 10.0, 20.0, 1.0);
+            },
+        ];
+        for change in changes {
+            let mut world = base.clone();
+            change(&mut world);
+            assert_ne!(world.state_hash(), base.state_hash());
+        }
+    }
+}
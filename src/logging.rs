@@ -0,0 +1,48 @@
+// Logging hooks for notable events (denied splits, sanitized balls, buffer
+// mismatches). With the `log` feature they forward to the `log` crate, so any
+// logger sees them (`init_console_log` with the `console_log` feature);
+// without it they compile to nothing.
+
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) };
+}
+
+#[cfg(feature = "log")]
+macro_rules! log_warn {
+    ($($arg:tt)*) => { log::warn!($($arg)*) };
+}
+
+// Type-check the arguments (and keep them "used") without evaluating them
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+// Route log records to the browser console. `level` is one of "error", "warn",
+// "info", "debug" or "trace" (default "info"). Calling it twice is an error.
+#[cfg(feature = "console_log")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn init_console_log(level: Option<String>) -> Result<(), wasm_bindgen::JsValue> {
+    let level = match level.as_deref() {
+        Some(level) => level
+            .parse()
+            .map_err(|_| wasm_bindgen::JsError::new(&format!("unknown log level {level:?}")))?,
+        None => log::Level::Info,
+    };
+    console_log::init_with_level(level)
+        .map_err(|error| wasm_bindgen::JsError::new(&error.to_string()).into())
+}
@@ -0,0 +1,124 @@
+// Minimap overlay. With a corner set, every render draws a thumbnail of the
+// whole arena (render_thumbnail) into that corner of the framebuffer, framed,
+// with an outline around the part of the arena the framebuffer shows. Only
+// useful when that is part of the world, i.e. the framebuffer is smaller
+// than the arena.
+//
+// The minimap is re-rendered each frame, which costs roughly one extra full
+// render of the arena. It is skipped on surfaces too small to hold it.
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::{Clip, Surface};
+use crate::World;
+
+// Gap between the minimap and the framebuffer's edges
+const MARGIN: usize = 8;
+const MAX_WIDTH: u32 = 1024;
+const FRAME_COLOR: [u8; 3] = [128, 128, 128];
+const VIEWPORT_COLOR: [u8; 3] = [255, 255, 255];
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MinimapCorner {
+    #[default]
+    Off = 0,
+    TopLeft = 1,
+    TopRight = 2,
+    BottomLeft = 3,
+    BottomRight = 4,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Minimap {
+    corner: MinimapCorner,
+    width: u32,
+}
+
+#[wasm_bindgen]
+impl World {
+    // Overlay a `width` pixels wide minimap (clamped to 1..=1024; its height
+    // follows the arena's aspect ratio) in the given corner. Off removes it.
+    pub fn set_minimap(&mut self, corner: MinimapCorner, width: u32) {
+        self.minimap = Minimap {
+            corner,
+            width: width.clamp(1, MAX_WIDTH),
+        };
+    }
+
+    pub fn minimap_corner(&self) -> MinimapCorner {
+        self.minimap.corner
+    }
+
+    pub fn minimap_width(&self) -> u32 {
+        self.minimap.width
+    }
+}
+
+impl World {
+    // Drawn over the rendered frame, inside `clip`
+    pub(crate) fn draw_minimap(&self, buffer: &mut [u8], surface: Surface, clip: Clip) {
+        let Minimap { corner, width } = self.minimap;
+        if corner == MinimapCorner::Off {
+            return;
+        }
+        let aspect = self.height / self.width;
+        let height = ((width as f32 * aspect).round() as u32).clamp(1, MAX_WIDTH);
+        let (w, h) = (width as usize, height as usize);
+        // Room for the minimap plus its one pixel frame
+        if w + 2 + 2 * MARGIN > surface.width || h + 2 + 2 * MARGIN > surface.height {
+            return;
+        }
+        let x0 = match corner {
+            MinimapCorner::TopLeft | MinimapCorner::BottomLeft => MARGIN + 1,
+            _ => surface.width - MARGIN - 1 - w,
+        };
+        let y0 = match corner {
+            MinimapCorner::TopLeft | MinimapCorner::TopRight => MARGIN + 1,
+            _ => surface.height - MARGIN - 1 - h,
+        };
+        let mut put = |x: usize, y: usize, rgb: &[u8]| {
+            if (clip.x0..clip.x1).contains(&x) && (clip.y0..clip.y1).contains(&y) {
+                let idx = y * surface.stride + x * 4;
+                buffer[idx..idx + 3].copy_from_slice(&rgb[..3]);
+                buffer[idx + 3] = 255;
+            }
+        };
+
+        let thumbnail = self.render_thumbnail(width, height);
+        for (row, pixels) in thumbnail.chunks_exact(w * 4).enumerate() {
+            for (col, pixel) in pixels.chunks_exact(4).enumerate() {
+                put(x0 + col, y0 + row, pixel);
+            }
+        }
+        outline(&mut put, (x0 - 1, y0 - 1, x0 + w, y0 + h), &FRAME_COLOR);
+
+        // The framebuffer shows arena pixels [0, surface.width) x [0, surface.height)
+        let scale = |side: usize, arena: f32, size: usize| {
+            ((side as f32 / arena).min(1.0) * size as f32).round() as usize
+        };
+        let vw = scale(surface.width, self.width, w).max(1);
+        let vh = scale(surface.height, self.height, h).max(1);
+        outline(
+            &mut put,
+            (x0, y0, x0 + vw - 1, y0 + vh - 1),
+            &VIEWPORT_COLOR,
+        );
+    }
+}
+
+// One pixel rectangle through both corners (inclusive)
+fn outline(
+    put: &mut impl FnMut(usize, usize, &[u8]),
+    (x0, y0, x1, y1): (usize, usize, usize, usize),
+    rgb: &[u8],
+) {
+    for x in x0..=x1 {
+        put(x, y0, rgb);
+        put(x, y1, rgb);
+    }
+    for y in y0..=y1 {
+        put(x0, y, rgb);
+        put(x1, y, rgb);
+    }
+}
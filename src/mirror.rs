@@ -0,0 +1,105 @@
+// Opt-in mirroring of the ball slots into a caller-provided SharedArrayBuffer,
+// so a render worker can read positions while the physics worker keeps
+// stepping. The region starts with a 4-word Int32 header followed by the slot
+// words (same layout as `views.rs`):
+//
+//   [0] sequence  odd while the mirror is being written, even when stable
+//   [1] frame     world frame the data belongs to
+//   [2] slots     number of slots that follow (truncated to what fits)
+//   [3] stride    32-bit words per slot
+//
+// Readers should load `sequence` with Atomics.load, copy what they need, and
+// retry if it was odd or changed in the meantime (a seqlock).
+
+use js_sys::{Atomics, Int32Array, SharedArrayBuffer, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::{Ball, World, WorldError};
+
+const HEADER_WORDS: u32 = 4;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Mirror {
+    header: Int32Array,
+    data: Uint8Array,
+}
+
+#[wasm_bindgen]
+impl World {
+    // Start mirroring into `buffer[byte_offset..byte_offset + byte_length]` after every update()
+    pub fn set_mirror(
+        &mut self,
+        buffer: SharedArrayBuffer,
+        byte_offset: u32,
+        byte_length: u32,
+    ) -> Result<(), WorldError> {
+        let end = byte_offset as u64 + byte_length as u64;
+        if !byte_offset.is_multiple_of(4)
+            || byte_length < HEADER_WORDS * 4
+            || end > buffer.byte_length() as u64
+        {
+            return Err(WorldError::InvalidMirrorRegion {
+                byte_offset,
+                byte_length,
+            });
+        }
+        let header =
+            Int32Array::new_with_byte_offset_and_length(&buffer, byte_offset, HEADER_WORDS);
+        let data = Uint8Array::new_with_byte_offset_and_length(
+            &buffer,
+            byte_offset + HEADER_WORDS * 4,
+            byte_length - HEADER_WORDS * 4,
+        );
+        self.mirror = Some(Mirror { header, data });
+        self.sync_mirror();
+        Ok(())
+    }
+
+    pub fn clear_mirror(&mut self) {
+        self.mirror = None;
+    }
+
+    // Bytes a mirror region needs to hold `slots` slots (saturates at u32::MAX)
+    pub fn mirror_bytes_for(&self, slots: u32) -> u32 {
+        slots
+            .saturating_mul(self.ball_stride_words() as u32)
+            .saturating_add(HEADER_WORDS)
+            .saturating_mul(4)
+    }
+
+    // Write the current state to the mirror now (update() already does this)
+    pub fn sync_mirror(&mut self) {
+        let Some(mirror) = &self.mirror else {
+            return;
+        };
+        let slot_bytes = std::mem::size_of::<Ball>() as u32;
+        let slots = (self.balls.len() as u32).min(mirror.data.length() / slot_bytes);
+        // Raw view of the slot array: padding bytes are never read on the Rust side
+        let source = unsafe {
+            Uint8Array::view_mut_raw(
+                self.balls.as_mut_ptr() as *mut u8,
+                (slots * slot_bytes) as usize,
+            )
+        };
+
+        // Atomics on a non-shared buffer throw, but set_mirror only accepts SharedArrayBuffers
+        let _ = Atomics::add(&mirror.header, 0, 1);
+        mirror.data.set(&source, 0);
+        let _ = Atomics::store(&mirror.header, 1, self.frame as i32);
+        let _ = Atomics::store(&mirror.header, 2, slots as i32);
+        let _ = Atomics::store(&mirror.header, 3, (slot_bytes / 4) as i32);
+        let _ = Atomics::add(&mirror.header, 0, 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    #[test]
+    fn mirror_bytes_saturate() {
+        let world = World::new(200.0, 150.0, 64, 0.7);
+        assert_eq!(world.mirror_bytes_for(u32::MAX), u32::MAX);
+        assert!(world.mirror_bytes_for(1) < world.mirror_bytes_for(2));
+    }
+}
@@ -0,0 +1,875 @@
+// Obstacles inside the arena: circles, rectangles and convex polygons of up
+// to MAX_VERTICES corners (ramps, funnel walls, pinball guides). After each
+// (sub-)step a ball overlapping an obstacle is pushed out along the surface
+// normal and, if it was moving into it, reflected like off a wall. Obstacles don't make balls
+// split; only the arena walls do. They are drawn in a flat gray
+// (OBSTACLE_COLOR) before the balls; an editor's ghost obstacle is drawn on
+// top of them, half transparent.
+//
+// Obstacles stand still unless set moving: one can rotate about its center
+// at a fixed angular speed (windmill bars), travel along a path of up to
+// MAX_WAYPOINTS waypoints at a fixed speed (moving platforms), or both. Both
+// advance every (sub-)step before contacts are resolved, and a ball bounces
+// off the surface relative to its velocity at the contact point, so a
+// sweeping bar bats balls away. The energy moving obstacles put in isn't
+// itemized.
+//
+// A flipper (add_flipper) is a tapered bar hinged at its wide end. flip()
+// swings it from its rest angle to its up angle at FLIP_SPEED and lets it
+// fall back, so a ball lying on it is launched like in pinball. The tip
+// moves FLIP_SPEED times the length per frame; give small balls more
+// substeps so the bar can't sweep past them in one.
+
+use wasm_bindgen::prelude::*;
+
+use crate::history::Edit;
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::render::Clip;
+use crate::{arena, sim, Ball, World};
+
+const OBSTACLE_COLOR: [u8; 4] = [0x80, 0x80, 0x80, 255];
+const GHOST_COLOR: [u8; 3] = [0xC0, 0xC0, 0xC0];
+const MAX_WAYPOINTS: usize = 16;
+const MAX_VERTICES: usize = 16;
+const FUNNEL_THICKNESS: f32 = 6.0;
+const FLIP_SPEED: f32 = 0.3; // Radians per frame
+const FLIPPER_WIDTH: f32 = 0.2; // At the hinge, times the length; half that at the tip
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ObstacleShape {
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    // (x, y) is the top-left corner, before any rotation
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    // (x, y) is the center the corners are given relative to, which the
+    // polygon rotates about. Corners wind so that the outward normal of the
+    // edge from a to b is (b.y - a.y, a.x - b.x).
+    Polygon {
+        x: f32,
+        y: f32,
+        corners: [(f32, f32); MAX_VERTICES],
+        len: usize,
+    },
+}
+
+impl StateHash for ObstacleShape {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        match *self {
+            ObstacleShape::Circle { x, y, radius } => {
+                hash.write_u32(0);
+                hash.write(&(x, y));
+                hash.write_f32(radius);
+            }
+            ObstacleShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                hash.write_u32(1);
+                hash.write(&(x, y));
+                hash.write(&(width, height));
+            }
+            ObstacleShape::Polygon {
+                x,
+                y,
+                ref corners,
+                len,
+            } => {
+                hash.write_u32(2);
+                hash.write(&(x, y));
+                hash.write(&corners[..len]);
+            }
+        }
+    }
+}
+
+// A route through the waypoints, walked at a fixed speed
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Path {
+    points: [(f32, f32); MAX_WAYPOINTS],
+    len: usize,
+    speed: f32,    // Pixels per frame
+    looped: bool,  // After the last waypoint: on to the first, or back the way it came
+    traveled: f32, // Distance along the route so far
+}
+
+impl StateHash for Path {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&self.points[..self.len]);
+        hash.write(&(self.speed, self.traveled));
+        hash.write(&self.looped);
+    }
+}
+
+impl Path {
+    // Waypoints in the order they're visited, back to the first
+    fn route(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let points = &self.points[..self.len];
+        let back = if self.looped {
+            &points[..1]
+        } else {
+            &points[..points.len() - 1]
+        };
+        points.iter().chain(back.iter().rev()).copied()
+    }
+
+    fn legs(&self) -> impl Iterator<Item = ((f32, f32), (f32, f32), f32)> + '_ {
+        self.route().zip(self.route().skip(1)).map(|(a, b)| {
+            let length = ((b.0 - a.0) * (b.0 - a.0) + (b.1 - a.1) * (b.1 - a.1)).sqrt();
+            (a, b, length)
+        })
+    }
+
+    // Point `distance` along the route
+    fn at(&self, mut distance: f32) -> (f32, f32) {
+        for (a, b, length) in self.legs() {
+            if distance <= length && length > 0.0 {
+                let t = distance / length;
+                return (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+            }
+            distance -= length;
+        }
+        self.points[0]
+    }
+
+    // Move on by `dt` frames' worth and return the new point
+    fn advance(&mut self, dt: f32) -> (f32, f32) {
+        let length: f32 = self.legs().map(|(_, _, length)| length).sum();
+        if length > 0.0 {
+            self.traveled = (self.traveled + self.speed * dt).rem_euclid(length);
+        }
+        self.at(self.traveled)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Obstacle {
+    pub(crate) shape: ObstacleShape,
+    angle: f32, // About the center, radians (positive = clockwise on screen)
+    spin: f32,  // Radians per frame
+    path: Option<Path>,
+    velocity: (f32, f32), // Of the center over the last (sub-)step, pixels/frame
+    flipper: Option<Flipper>,
+}
+
+impl StateHash for Obstacle {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&self.shape);
+        hash.write(&(self.angle, self.spin));
+        hash.write(&self.path);
+        hash.write(&self.velocity);
+        hash.write(&self.flipper);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Flipper {
+    rest: f32, // Angles
+    up: f32,
+    rising: bool, // Swinging up, rather than back to rest or resting
+}
+
+impl StateHash for Flipper {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&(self.rest, self.up));
+        hash.write(&self.rising);
+    }
+}
+
+impl Obstacle {
+    pub(crate) fn new(shape: ObstacleShape) -> Obstacle {
+        Obstacle {
+            shape,
+            angle: 0.0,
+            spin: 0.0,
+            path: None,
+            velocity: (0.0, 0.0),
+            flipper: None,
+        }
+    }
+
+    fn center(&self) -> (f32, f32) {
+        match self.shape {
+            ObstacleShape::Circle { x, y, .. } | ObstacleShape::Polygon { x, y, .. } => (x, y),
+            ObstacleShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => (x + width / 2.0, y + height / 2.0),
+        }
+    }
+
+    // Where move_to() puts the obstacle
+    fn position(&self) -> (f32, f32) {
+        match self.shape {
+            ObstacleShape::Circle { x, y, .. }
+            | ObstacleShape::Rect { x, y, .. }
+            | ObstacleShape::Polygon { x, y, .. } => (x, y),
+        }
+    }
+
+    // A point relative to the center, in the unrotated obstacle's frame
+    fn local_point(&self, px: f32, py: f32) -> (f32, f32) {
+        let (cx, cy) = self.center();
+        let (sin, cos) = self.angle.sin_cos();
+        let (dx, dy) = (px - cx, py - cy);
+        (dx * cos + dy * sin, dy * cos - dx * sin)
+    }
+
+    // A direction in the unrotated frame, back on screen
+    fn screen_direction(&self, dx: f32, dy: f32) -> (f32, f32) {
+        let (sin, cos) = self.angle.sin_cos();
+        (dx * cos - dy * sin, dx * sin + dy * cos)
+    }
+
+    pub(crate) fn contains(&self, px: f32, py: f32) -> bool {
+        match self.shape {
+            ObstacleShape::Circle { x, y, radius } => {
+                let (dx, dy) = (px - x, py - y);
+                dx * dx + dy * dy <= radius * radius
+            }
+            ObstacleShape::Rect { width, height, .. } => {
+                let (lx, ly) = self.local_point(px, py);
+                lx.abs() <= width / 2.0 && ly.abs() <= height / 2.0
+            }
+            ObstacleShape::Polygon { corners, len, .. } => {
+                let point = self.local_point(px, py);
+                edges(&corners[..len]).all(|(a, normal)| outside(point, a, normal) <= 0.0)
+            }
+        }
+    }
+
+    // Circles by their center, rectangles by their top-left corner
+    pub(crate) fn move_to(&mut self, to_x: f32, to_y: f32) {
+        match &mut self.shape {
+            ObstacleShape::Circle { x, y, .. }
+            | ObstacleShape::Rect { x, y, .. }
+            | ObstacleShape::Polygon { x, y, .. } => {
+                *x = to_x;
+                *y = to_y;
+            }
+        }
+    }
+
+    // Rotate and travel for `dt` frames
+    fn advance(&mut self, dt: f32) {
+        if let Some(flipper) = &mut self.flipper {
+            // Spin is the swing, so balls are batted along with it
+            let target = if flipper.rising {
+                flipper.up
+            } else {
+                flipper.rest
+            };
+            let left = target - self.angle;
+            if left.abs() <= FLIP_SPEED * dt {
+                self.spin = left / dt;
+                self.angle = target;
+                flipper.rising = false;
+            } else {
+                self.spin = FLIP_SPEED.copysign(left);
+                self.angle += self.spin * dt;
+            }
+        } else if self.spin != 0.0 {
+            self.angle = (self.angle + self.spin * dt).rem_euclid(std::f32::consts::TAU);
+        }
+        let (from_x, from_y) = self.position();
+        let Some(path) = &mut self.path else {
+            self.velocity = (0.0, 0.0);
+            return;
+        };
+        let (x, y) = path.advance(dt);
+        self.move_to(x, y);
+        self.velocity = ((x - from_x) / dt, (y - from_y) / dt);
+    }
+
+    // Velocity of the obstacle's surface at (px, py)
+    fn surface_velocity(&self, px: f32, py: f32) -> (f32, f32) {
+        let (cx, cy) = self.center();
+        let (vx, vy) = self.velocity;
+        (vx - self.spin * (py - cy), vy + self.spin * (px - cx))
+    }
+
+    // Unit normal pointing out of the obstacle towards the ball and the
+    // overlap depth, or None if they don't touch
+    fn contact(&self, ball: &Ball) -> Option<(f32, f32, f32)> {
+        match self.shape {
+            ObstacleShape::Circle { x, y, radius } => {
+                let (dx, dy) = (ball.x - x, ball.y - y);
+                let reach = radius + ball.radius;
+                let distance2 = dx * dx + dy * dy;
+                if distance2 >= reach * reach {
+                    return None;
+                }
+                let distance = distance2.sqrt();
+                if distance > 0.0 {
+                    Some((dx / distance, dy / distance, reach - distance))
+                } else {
+                    Some((0.0, -1.0, reach))
+                }
+            }
+            ObstacleShape::Rect { width, height, .. } => {
+                // In the rectangle's own frame, centered on the origin
+                let (bx, by) = self.local_point(ball.x, ball.y);
+                let (x1, y1) = (width / 2.0, height / 2.0);
+                let (x, y) = (-x1, -y1);
+                let closest_x = bx.clamp(x, x1);
+                let closest_y = by.clamp(y, y1);
+                let (dx, dy) = (bx - closest_x, by - closest_y);
+                let distance2 = dx * dx + dy * dy;
+                if distance2 > 0.0 {
+                    if distance2 >= ball.radius * ball.radius {
+                        return None;
+                    }
+                    let distance = distance2.sqrt();
+                    let (nx, ny) = self.screen_direction(dx / distance, dy / distance);
+                    return Some((nx, ny, ball.radius - distance));
+                }
+                // Center inside the rectangle: leave through the nearest edge
+                let edges = [
+                    (bx - x, -1.0, 0.0),
+                    (x1 - bx, 1.0, 0.0),
+                    (by - y, 0.0, -1.0),
+                    (y1 - by, 0.0, 1.0),
+                ];
+                let (depth, nx, ny) = edges
+                    .into_iter()
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .unwrap_or(edges[0]);
+                let (nx, ny) = self.screen_direction(nx, ny);
+                Some((nx, ny, depth + ball.radius))
+            }
+            ObstacleShape::Polygon { corners, len, .. } => {
+                let corners = &corners[..len];
+                let point = self.local_point(ball.x, ball.y);
+                // The edge the center is furthest out of
+                let (a, normal) = edges(corners)
+                    .max_by(|e, f| outside(point, e.0, e.1).total_cmp(&outside(point, f.0, f.1)))?;
+                let out = outside(point, a, normal);
+                if out <= 0.0 {
+                    // Center inside: leave through that edge
+                    let (nx, ny) = self.screen_direction(normal.0, normal.1);
+                    return Some((nx, ny, ball.radius - out));
+                }
+                // Outside: the nearest point of the outline
+                let closest = corners
+                    .iter()
+                    .zip(corners.iter().cycle().skip(1))
+                    .map(|(&a, &b)| closest_on_segment(point, a, b))
+                    .min_by(|p, q| distance2(point, *p).total_cmp(&distance2(point, *q)))?;
+                let d2 = distance2(point, closest);
+                if d2 >= ball.radius * ball.radius || d2 == 0.0 {
+                    return None;
+                }
+                let distance = d2.sqrt();
+                let (dx, dy) = (point.0 - closest.0, point.1 - closest.1);
+                let (nx, ny) = self.screen_direction(dx / distance, dy / distance);
+                Some((nx, ny, ball.radius - distance))
+            }
+        }
+    }
+
+    // Distance along the ray from `origin` in unit `direction` to the surface
+    // and the outward normal there, or None if it misses. A ray starting
+    // inside hits at distance 0, facing back along the ray.
+    pub(crate) fn raycast(
+        &self,
+        origin: (f32, f32),
+        direction: (f32, f32),
+    ) -> Option<(f32, (f32, f32))> {
+        match self.shape {
+            ObstacleShape::Circle { x, y, radius } => {
+                let (ox, oy) = (origin.0 - x, origin.1 - y);
+                let b = ox * direction.0 + oy * direction.1;
+                let c = ox * ox + oy * oy - radius * radius;
+                if c <= 0.0 {
+                    return Some((0.0, (-direction.0, -direction.1)));
+                }
+                let disc = b * b - c;
+                if b > 0.0 || disc < 0.0 {
+                    return None;
+                }
+                let t = -b - disc.sqrt();
+                let (hx, hy) = (ox + direction.0 * t, oy + direction.1 * t);
+                Some((t, (hx / radius, hy / radius)))
+            }
+            ObstacleShape::Rect { width, height, .. } => {
+                let (x1, y1) = (width / 2.0, height / 2.0);
+                let corners = [(-x1, -y1), (x1, -y1), (x1, y1), (-x1, y1)];
+                self.raycast_convex(&corners, origin, direction)
+            }
+            ObstacleShape::Polygon { corners, len, .. } => {
+                self.raycast_convex(&corners[..len], origin, direction)
+            }
+        }
+    }
+
+    // raycast() for a convex outline in the obstacle's own frame: clip the
+    // ray against every edge's half plane
+    fn raycast_convex(
+        &self,
+        corners: &[(f32, f32)],
+        origin: (f32, f32),
+        direction: (f32, f32),
+    ) -> Option<(f32, (f32, f32))> {
+        let point = self.local_point(origin.0, origin.1);
+        let (sin, cos) = self.angle.sin_cos();
+        let local = (
+            direction.0 * cos + direction.1 * sin,
+            direction.1 * cos - direction.0 * sin,
+        );
+        let (mut enter, mut exit) = (f32::NEG_INFINITY, f32::INFINITY);
+        let mut normal = (0.0, 0.0);
+        for (a, edge_normal) in edges(corners) {
+            let out = outside(point, a, edge_normal);
+            let along = local.0 * edge_normal.0 + local.1 * edge_normal.1;
+            if along == 0.0 {
+                if out > 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let t = -out / along;
+            if along < 0.0 {
+                if t > enter {
+                    enter = t;
+                    normal = edge_normal;
+                }
+            } else {
+                exit = exit.min(t);
+            }
+        }
+        if enter > exit || exit < 0.0 {
+            return None;
+        }
+        if enter <= 0.0 {
+            return Some((0.0, (-direction.0, -direction.1)));
+        }
+        Some((enter, self.screen_direction(normal.0, normal.1)))
+    }
+}
+
+// Each edge of a polygon as its start corner and unit outward normal
+fn edges(corners: &[(f32, f32)]) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + '_ {
+    corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(&a, &b)| {
+            let (nx, ny) = (b.1 - a.1, a.0 - b.0);
+            let length = (nx * nx + ny * ny).sqrt();
+            (a, (nx / length, ny / length))
+        })
+}
+
+// Signed distance of `point` outside the line through `a` with `normal`
+fn outside(point: (f32, f32), a: (f32, f32), normal: (f32, f32)) -> f32 {
+    (point.0 - a.0) * normal.0 + (point.1 - a.1) * normal.1
+}
+
+fn closest_on_segment(point: (f32, f32), a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    let (ex, ey) = (b.0 - a.0, b.1 - a.1);
+    let t = ((point.0 - a.0) * ex + (point.1 - a.1) * ey) / (ex * ex + ey * ey);
+    let t = t.clamp(0.0, 1.0);
+    (a.0 + ex * t, a.1 + ey * t)
+}
+
+fn distance2(p: (f32, f32), q: (f32, f32)) -> f32 {
+    (p.0 - q.0) * (p.0 - q.0) + (p.1 - q.1) * (p.1 - q.1)
+}
+
+// The shape for corners given as x, y pairs, or None unless they are 3 to
+// MAX_VERTICES finite points making a convex polygon with some area
+fn polygon(points: &[f32]) -> Option<ObstacleShape> {
+    let len = points.len() / 2;
+    if !points.len().is_multiple_of(2)
+        || !(3..=MAX_VERTICES).contains(&len)
+        || !points.iter().all(|value| value.is_finite())
+    {
+        return None;
+    }
+    let mut corners = [(0.0, 0.0); MAX_VERTICES];
+    for (corner, xy) in corners.iter_mut().zip(points.chunks_exact(2)) {
+        *corner = (xy[0], xy[1]);
+    }
+    let corners = &mut corners[..len];
+    let turns: Vec<f32> = (0..len)
+        .map(|i| {
+            let (a, b, c) = (corners[i], corners[(i + 1) % len], corners[(i + 2) % len]);
+            (b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0)
+        })
+        .collect();
+    let area2: f32 = (0..len)
+        .map(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % len]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    let convex = turns.iter().all(|&turn| turn >= 0.0) || turns.iter().all(|&turn| turn <= 0.0);
+    if !convex || area2 == 0.0 {
+        return None;
+    }
+    if area2 < 0.0 {
+        corners.reverse();
+    }
+    let (sx, sy) = corners
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+    let (x, y) = (sx / len as f32, sy / len as f32);
+    let mut relative = [(0.0, 0.0); MAX_VERTICES];
+    for (to, &(cx, cy)) in relative.iter_mut().zip(corners.iter()) {
+        *to = (cx - x, cy - y);
+    }
+    Some(ObstacleShape::Polygon {
+        x,
+        y,
+        corners: relative,
+        len,
+    })
+}
+
+#[wasm_bindgen]
+impl World {
+    // Returns the obstacle's index, or None unless the arguments are finite
+    // and the size positive
+    pub fn add_circle_obstacle(&mut self, x: f32, y: f32, radius: f32) -> Option<u32> {
+        if !([x, y, radius].iter().all(|value| value.is_finite()) && radius > 0.0) {
+            return None;
+        }
+        Some(self.add_obstacle(Obstacle::new(ObstacleShape::Circle { x, y, radius })))
+    }
+
+    // Axis-aligned rectangle with its top-left corner at (x, y)
+    pub fn add_rect_obstacle(&mut self, x: f32, y: f32, width: f32, height: f32) -> Option<u32> {
+        let finite = [x, y, width, height].iter().all(|value| value.is_finite());
+        if !(finite && width > 0.0 && height > 0.0) {
+            return None;
+        }
+        Some(self.add_obstacle(Obstacle::new(ObstacleShape::Rect {
+            x,
+            y,
+            width,
+            height,
+        })))
+    }
+
+    // Convex polygon through `points`, x, y pairs of its corners in order
+    // (either way round). None unless they are 3 to 16 finite points making
+    // a convex polygon with some area.
+    pub fn add_polygon_obstacle(&mut self, points: Vec<f32>) -> Option<u32> {
+        let shape = polygon(&points)?;
+        Some(self.add_obstacle(Obstacle::new(shape)))
+    }
+
+    // A funnel: two slanted walls from a `width` wide top edge centered on
+    // (x, y) down to a `gap` wide mouth `height` lower. Returns the index of
+    // the left wall; the right one comes next. None unless every argument is
+    // finite, the height positive and the mouth narrower than the top.
+    pub fn add_funnel(&mut self, x: f32, y: f32, width: f32, gap: f32, height: f32) -> Option<u32> {
+        let finite = [x, y, width, gap, height]
+            .iter()
+            .all(|value| value.is_finite());
+        if !(finite && height > 0.0 && gap >= 0.0 && gap < width) {
+            return None;
+        }
+        let (top, mouth, bottom) = (width / 2.0, gap / 2.0, y + height);
+        // Each wall is FUNNEL_THICKNESS wide on the outside of its slope
+        let t = FUNNEL_THICKNESS;
+        let left = polygon(&[
+            x - top,
+            y,
+            x - mouth,
+            bottom,
+            x - mouth - t,
+            bottom,
+            x - top - t,
+            y,
+        ])?;
+        let right = polygon(&[
+            x + top,
+            y,
+            x + top + t,
+            y,
+            x + mouth + t,
+            bottom,
+            x + mouth,
+            bottom,
+        ])?;
+        let index = self.add_obstacle(Obstacle::new(left));
+        self.add_obstacle(Obstacle::new(right));
+        Some(index)
+    }
+
+    // A flipper hinged at (x, y), `length` long and pointing `rest_angle`
+    // radians clockwise from the +x axis; flip() swings it to `up_angle`. None
+    // unless every argument is finite and the length positive.
+    pub fn add_flipper(
+        &mut self,
+        x: f32,
+        y: f32,
+        length: f32,
+        rest_angle: f32,
+        up_angle: f32,
+    ) -> Option<u32> {
+        let finite = [x, y, length, rest_angle, up_angle]
+            .iter()
+            .all(|value| value.is_finite());
+        if !(finite && length > 0.0) {
+            return None;
+        }
+        // Pointing +x from the hinge, wound like polygon() leaves corners
+        let half = length * FLIPPER_WIDTH / 2.0;
+        let mut corners = [(0.0, 0.0); MAX_VERTICES];
+        corners[..4].copy_from_slice(&[
+            (0.0, -half),
+            (length, -half / 2.0),
+            (length, half / 2.0),
+            (0.0, half),
+        ]);
+        let mut flipper = Obstacle::new(ObstacleShape::Polygon {
+            x,
+            y,
+            corners,
+            len: 4,
+        });
+        flipper.angle = rest_angle;
+        flipper.flipper = Some(Flipper {
+            rest: rest_angle,
+            up: up_angle,
+            rising: false,
+        });
+        Some(self.add_obstacle(flipper))
+    }
+
+    // Swing a flipper up (and back). Returns false if `index` isn't a flipper.
+    pub fn flip(&mut self, index: u32) -> bool {
+        match self
+            .obstacles
+            .get_mut(index as usize)
+            .and_then(|obstacle| obstacle.flipper.as_mut())
+        {
+            Some(flipper) => {
+                flipper.rising = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear_obstacles(&mut self) {
+        let obstacles = std::mem::take(&mut self.obstacles);
+        self.record_edit(Edit::ClearObstacles { obstacles });
+    }
+
+    pub fn obstacle_count(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    // Spin an obstacle about its center at `speed` radians/frame (positive =
+    // clockwise; 0 stops it at its current angle). Returns false if there is
+    // no such obstacle or the speed isn't finite.
+    pub fn set_obstacle_rotation(&mut self, index: u32, speed: f32) -> bool {
+        if !speed.is_finite() {
+            return false;
+        }
+        self.edit_obstacle(index, |obstacle| obstacle.spin = speed)
+    }
+
+    // Current angle in radians (0 if there is no such obstacle)
+    pub fn obstacle_angle(&self, index: u32) -> f32 {
+        self.obstacles
+            .get(index as usize)
+            .map_or(0.0, |obstacle| obstacle.angle)
+    }
+
+    // Send an obstacle along `path`, x, y pairs of where move_obstacle() would
+    // put it (see there), at `speed` pixels/frame: it jumps to the first
+    // waypoint and after the last one goes on to the first (`looped`) or back
+    // the way it came.
+    // An empty path stops it. Returns false if there is no such obstacle, the
+    // speed isn't finite or the path isn't up to 16 finite points.
+    pub fn set_obstacle_motion(
+        &mut self,
+        index: u32,
+        path: Vec<f32>,
+        speed: f32,
+        looped: bool,
+    ) -> bool {
+        let len = path.len() / 2;
+        let valid = path.len().is_multiple_of(2)
+            && len <= MAX_WAYPOINTS
+            && speed.is_finite()
+            && path.iter().all(|value| value.is_finite());
+        if !valid {
+            return false;
+        }
+        let route = (len > 0).then(|| {
+            let mut points = [(0.0, 0.0); MAX_WAYPOINTS];
+            for (point, xy) in points.iter_mut().zip(path.chunks_exact(2)) {
+                *point = (xy[0], xy[1]);
+            }
+            Path {
+                points,
+                len,
+                speed,
+                looped,
+                traveled: 0.0,
+            }
+        });
+        self.edit_obstacle(index, |obstacle| {
+            if let Some(route) = route {
+                obstacle.move_to(route.points[0].0, route.points[0].1);
+            }
+            obstacle.path = route;
+            obstacle.velocity = (0.0, 0.0);
+        })
+    }
+}
+
+impl World {
+    fn add_obstacle(&mut self, obstacle: Obstacle) -> u32 {
+        self.obstacles.push(obstacle);
+        let index = self.obstacles.len() - 1;
+        self.record_edit(Edit::AddObstacle { index, obstacle });
+        index as u32
+    }
+
+    // Change an obstacle as one undoable edit
+    fn edit_obstacle(&mut self, index: u32, edit: impl FnOnce(&mut Obstacle)) -> bool {
+        let Some(obstacle) = self.obstacles.get_mut(index as usize) else {
+            return false;
+        };
+        let from = *obstacle;
+        edit(obstacle);
+        let to = *obstacle;
+        self.record_edit(Edit::MoveObstacle {
+            index: index as usize,
+            from: Box::new(from),
+            to: Box::new(to),
+        });
+        true
+    }
+
+    // Rotate and move the obstacles for one (sub-)step
+    pub(crate) fn move_obstacles(&mut self) {
+        let dt = 1.0 / self.substeps as f32;
+        for obstacle in &mut self.obstacles {
+            obstacle.advance(dt);
+        }
+    }
+
+    pub(crate) fn collide_obstacles(&mut self, stamp: u32) {
+        if self.obstacles.is_empty() {
+            return;
+        }
+        let config = self.sim_config();
+        for (ball, modified) in self.balls.iter_mut().zip(self.modified.iter_mut()) {
+            if ball.alive == 0 {
+                continue;
+            }
+            for obstacle in &self.obstacles {
+                let Some((nx, ny, depth)) = obstacle.contact(ball) else {
+                    continue;
+                };
+                ball.x += nx * depth;
+                ball.y += ny * depth;
+                // Bounce relative to the surface where the ball touches it
+                let touch = (ball.x - nx * ball.radius, ball.y - ny * ball.radius);
+                let (sx, sy) = obstacle.surface_velocity(touch.0, touch.1);
+                let into = (ball.vx - sx) * nx + (ball.vy - sy) * ny;
+                if into < 0.0 {
+                    let restitution = sim::wall_restitution(ball, &config);
+                    ball.vx -= (1.0 + restitution) * into * nx;
+                    ball.vy -= (1.0 + restitution) * into * ny;
+                    let lost = sim::restitution_loss(ball.radius, into, restitution);
+                    self.energy.record_restitution(lost);
+                }
+                arena::keep_inside(ball, &config);
+                *modified = stamp;
+            }
+        }
+    }
+}
+
+// Fill an obstacle's pixels inside `clip`. `buffer` starts at row `clip.y0`.
+pub(crate) fn fill_obstacle(buffer: &mut [u8], stride: usize, clip: Clip, obstacle: &Obstacle) {
+    for_each_pixel(buffer, stride, clip, obstacle, |pixel| {
+        pixel.copy_from_slice(&OBSTACLE_COLOR)
+    });
+}
+
+// Blend an editor's ghost obstacle 50% over what is already drawn
+pub(crate) fn fill_ghost(buffer: &mut [u8], stride: usize, clip: Clip, obstacle: &Obstacle) {
+    for_each_pixel(buffer, stride, clip, obstacle, |pixel| {
+        for (channel, ghost) in pixel.iter_mut().zip(GHOST_COLOR) {
+            *channel = ((*channel as u16 + ghost as u16) / 2) as u8;
+        }
+        pixel[3] = ((pixel[3] as u16 + 255) / 2) as u8;
+    });
+}
+
+fn for_each_pixel(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    obstacle: &Obstacle,
+    mut paint: impl FnMut(&mut [u8]),
+) {
+    let (x0, y0, x1, y1) = match obstacle.shape {
+        ObstacleShape::Circle { x, y, radius } => (x - radius, y - radius, x + radius, y + radius),
+        ObstacleShape::Rect {
+            x,
+            y,
+            width,
+            height,
+        } if obstacle.angle == 0.0 => (x, y, x + width, y + height),
+        ObstacleShape::Polygon {
+            x, y, corners, len, ..
+        } => {
+            let (sin, cos) = obstacle.angle.sin_cos();
+            corners[..len]
+                .iter()
+                .fold((x, y, x, y), |(x0, y0, x1, y1), &(dx, dy)| {
+                    let (px, py) = (x + dx * cos - dy * sin, y + dx * sin + dy * cos);
+                    (x0.min(px), y0.min(py), x1.max(px), y1.max(py))
+                })
+        }
+        ObstacleShape::Rect { width, height, .. } => {
+            let (cx, cy) = obstacle.center();
+            let (sin, cos) = obstacle.angle.sin_cos();
+            let (hw, hh) = (width / 2.0, height / 2.0);
+            let reach_x = (hw * cos).abs() + (hh * sin).abs();
+            let reach_y = (hw * sin).abs() + (hh * cos).abs();
+            (cx - reach_x, cy - reach_y, cx + reach_x, cy + reach_y)
+        }
+    };
+    let x_from = (x0.max(clip.x0 as f32) as usize).max(clip.x0);
+    let x_to = (x1.ceil().min(clip.x1 as f32) as usize).min(clip.x1);
+    let y_from = (y0.max(clip.y0 as f32) as usize).max(clip.y0);
+    let y_to = (y1.ceil().min(clip.y1 as f32) as usize).min(clip.y1);
+    for py in y_from..y_to {
+        let row = (py - clip.y0) * stride;
+        for px in x_from..x_to {
+            let inside = match obstacle.shape {
+                ObstacleShape::Circle { .. } | ObstacleShape::Polygon { .. } => {
+                    obstacle.contains(px as f32, py as f32)
+                }
+                // Unrotated rectangles cover their whole pixel span
+                ObstacleShape::Rect { .. } if obstacle.angle == 0.0 => true,
+                ObstacleShape::Rect { .. } => obstacle.contains(px as f32, py as f32),
+            };
+            if inside {
+                let idx = row + px * 4;
+                paint(&mut buffer[idx..idx + 4]);
+            }
+        }
+    }
+}
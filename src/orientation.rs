@@ -0,0 +1,105 @@
+// Tilt-controlled gravity from the device's orientation (`web` feature).
+// bind_device_orientation() listens for the window's "deviceorientation"
+// events; each update() then points gravity the way the device is tilted:
+// left-right tilt (gamma) drives gravity_x and front-back tilt (beta)
+// drives gravity_y, each as strength * sin(angle). A phone lying flat gives
+// no gravity, one held upright gives full strength straight down.
+//
+// The event handler only stores the latest reading, so the World itself
+// stays owned by JS. iOS additionally wants
+// DeviceOrientationEvent.requestPermission() from a user gesture first.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::DeviceOrientationEvent;
+
+use crate::{dynamics, World};
+
+const EVENT: &str = "deviceorientation";
+
+// Removes the event listener once the last World sharing it is dropped
+struct Listener {
+    closure: Closure<dyn FnMut(DeviceOrientationEvent)>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            let _ = window
+                .remove_event_listener_with_callback(EVENT, self.closure.as_ref().unchecked_ref());
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Orientation {
+    reading: Rc<Cell<Option<(f64, f64)>>>, // Latest (beta, gamma) in degrees
+    strength: f32,
+    _listener: Rc<Listener>,
+}
+
+impl std::fmt::Debug for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Orientation")
+            .field("reading", &self.reading.get())
+            .field("strength", &self.strength)
+            .finish()
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Drive gravity from the device orientation. The strength is the current
+    // gravity's, or the tilt() default while gravity is off. Throws outside a
+    // browser window.
+    pub fn bind_device_orientation(&mut self) -> Result<(), JsValue> {
+        let window = web_sys::window()
+            .ok_or_else(|| JsError::new("bind_device_orientation() needs a window"))?;
+        let reading = Rc::new(Cell::new(None));
+        let sink = Rc::clone(&reading);
+        let closure = Closure::<dyn FnMut(DeviceOrientationEvent)>::new(
+            move |event: DeviceOrientationEvent| {
+                if let (Some(beta), Some(gamma)) = (event.beta(), event.gamma()) {
+                    sink.set(Some((beta, gamma)));
+                }
+            },
+        );
+        window.add_event_listener_with_callback(EVENT, closure.as_ref().unchecked_ref())?;
+
+        let (gx, gy) = self.gravity;
+        let strength = (gx * gx + gy * gy).sqrt();
+        self.orientation = Some(Orientation {
+            reading,
+            strength: if strength > 0.0 {
+                strength
+            } else {
+                dynamics::DEFAULT_TILT_GRAVITY
+            },
+            _listener: Rc::new(Listener { closure }),
+        });
+        Ok(())
+    }
+
+    // Stop following the device; gravity keeps its last value
+    pub fn unbind_device_orientation(&mut self) {
+        self.orientation = None;
+    }
+}
+
+impl World {
+    // Called at the start of update()
+    pub(crate) fn apply_orientation(&mut self) {
+        let Some(orientation) = &self.orientation else {
+            return;
+        };
+        if let Some((beta, gamma)) = orientation.reading.get() {
+            let strength = orientation.strength;
+            let gx = strength * (gamma as f32).to_radians().sin();
+            let gy = strength * (beta as f32).to_radians().sin();
+            self.set_gravity(gx, gy);
+        }
+    }
+}
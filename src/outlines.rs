@@ -0,0 +1,157 @@
+// Per-ball outlines and highlights, for selection UX on top of the picking
+// API. set_outline() gives a ball a colored ring along the inside of its
+// edge; highlight() toggles a bright white ring drawn over it. Both stay
+// inside the ball's radius, so they never spill into neighbouring bands.
+//
+// The style belongs to the ball, not the slot: it is dropped when the ball is
+// removed and isn't inherited by split children.
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::Clip;
+use crate::{Ball, World};
+
+const HIGHLIGHT_COLOR: u32 = 0xFFFFFF;
+const HIGHLIGHT_WIDTH: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Outline {
+    color: u32,
+    width: f32, // 0 = no outline
+    highlight: bool,
+}
+
+impl Outline {
+    fn is_plain(&self) -> bool {
+        self.width == 0.0 && !self.highlight
+    }
+
+    // Draw the rings over the already filled ball. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(&self, buffer: &mut [u8], stride: usize, clip: Clip, ball: &Ball) {
+        if self.width > 0.0 {
+            fill_ring(buffer, stride, clip, ball, self.width, self.color);
+        }
+        if self.highlight {
+            fill_ring(buffer, stride, clip, ball, HIGHLIGHT_WIDTH, HIGHLIGHT_COLOR);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Outlines {
+    slots: Vec<Outline>,
+}
+
+impl Outlines {
+    pub(crate) fn get(&self, id: usize) -> Option<&Outline> {
+        self.slots.get(id).filter(|outline| !outline.is_plain())
+    }
+
+    // Called when the ball in slot `id` is removed
+    pub(crate) fn forget(&mut self, id: usize) {
+        if let Some(outline) = self.slots.get_mut(id) {
+            *outline = Outline::default();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    fn edit(&mut self, id: usize, edit: impl FnOnce(&mut Outline)) {
+        if self.slots.len() <= id {
+            self.slots.resize(id + 1, Outline::default());
+        }
+        edit(&mut self.slots[id]);
+    }
+}
+
+// Pixels between `width` inside the edge and the edge itself
+fn fill_ring(buffer: &mut [u8], stride: usize, clip: Clip, ball: &Ball, width: f32, color: u32) {
+    let (cx, cy, r) = (ball.x, ball.y, ball.radius);
+    let inner = (r - width).max(0.0);
+    let pixel = [
+        ((color >> 16) & 0xFF) as u8,
+        ((color >> 8) & 0xFF) as u8,
+        (color & 0xFF) as u8,
+        255,
+    ];
+    let x_min = (cx - r).max(clip.x0 as f32) as i64;
+    let x_max = (cx + r).min(clip.x1 as f32) as i64;
+    let y_min = (cy - r).max(clip.y0 as f32) as i64;
+    let y_max = (cy + r).min(clip.y1 as f32) as i64;
+    for py in y_min..y_max {
+        let row = (py as usize - clip.y0) * stride;
+        for px in x_min..x_max {
+            let (dx, dy) = (px as f32 - cx, py as f32 - cy);
+            let d = dx * dx + dy * dy;
+            if d <= r * r && d > inner * inner {
+                let idx = row + px as usize * 4;
+                buffer[idx..idx + 4].copy_from_slice(&pixel);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Ring `width` pixels thick (0 removes it) in `color` (0xRRGGBB) along the
+    // inside of the ball's edge. Returns false if the id is not a live ball.
+    pub fn set_outline(&mut self, id: u32, color: u32, width: f32) -> bool {
+        if !self.is_alive(id) {
+            return false;
+        }
+        let width = if width.is_finite() {
+            width.max(0.0)
+        } else {
+            0.0
+        };
+        self.outlines.edit(id as usize, |outline| {
+            outline.color = color & 0xFF_FFFF;
+            outline.width = width;
+        });
+        true
+    }
+
+    // 0 without an outline
+    pub fn outline_color(&self, id: u32) -> u32 {
+        self.outlines
+            .get(id as usize)
+            .map_or(0, |outline| outline.color)
+    }
+
+    pub fn outline_width(&self, id: u32) -> f32 {
+        self.outlines
+            .get(id as usize)
+            .map_or(0.0, |outline| outline.width)
+    }
+
+    // Toggle the selection ring. Returns false if the id is not a live ball.
+    pub fn highlight(&mut self, id: u32, on: bool) -> bool {
+        if !self.is_alive(id) {
+            return false;
+        }
+        self.outlines
+            .edit(id as usize, |outline| outline.highlight = on);
+        true
+    }
+
+    pub fn is_highlighted(&self, id: u32) -> bool {
+        self.outlines
+            .get(id as usize)
+            .is_some_and(|outline| outline.highlight)
+    }
+
+    // Ids of all highlighted balls
+    pub fn highlighted(&self) -> Vec<u32> {
+        (0..self.outlines.slots.len() as u32)
+            .filter(|&id| self.is_highlighted(id))
+            .collect()
+    }
+
+    pub fn clear_highlights(&mut self) {
+        for outline in &mut self.outlines.slots {
+            outline.highlight = false;
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Plexus effect: the "particle network" look. With a distance set, every
+// render draws a line between each pair of live balls whose centers are
+// closer than it, fully `opacity` at zero distance and fading to nothing at
+// the threshold. Lines go behind the balls.
+//
+// Pairs come from the collision broadphase grid with the threshold as cell
+// size, so the cost grows with the number of close pairs rather than with
+// the square of the ball count. Past MAX_LINES pairs the rest are dropped.
+
+use wasm_bindgen::prelude::*;
+
+use crate::collision::Grid;
+use crate::render::Clip;
+use crate::srgb::{self, Blending};
+use crate::trails::blend_line;
+use crate::{Ball, World};
+
+const MAX_LINES: usize = 1 << 16;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Plexus {
+    distance: f32, // 0 = off
+    color: u32,
+    opacity: f32,
+}
+
+// One line to draw
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Link {
+    from: (f32, f32),
+    to: (f32, f32),
+    alpha: f32,
+}
+
+impl Plexus {
+    // Lines for the current positions (empty when off)
+    pub(crate) fn links(&self, balls: &[Ball]) -> Vec<Link> {
+        let mut links = Vec::new();
+        if self.distance <= 0.0 || self.opacity <= 0.0 {
+            return links;
+        }
+        let grid = Grid::new(balls, self.distance);
+        let limit = self.distance * self.distance;
+        for (a, ball) in balls.iter().enumerate().filter(|(_, ball)| ball.alive != 0) {
+            for b in grid.neighbors(ball).filter(|&b| b > a) {
+                let other = &balls[b];
+                let (dx, dy) = (other.x - ball.x, other.y - ball.y);
+                let squared = dx * dx + dy * dy;
+                if squared >= limit {
+                    continue;
+                }
+                if links.len() == MAX_LINES {
+                    return links;
+                }
+                links.push(Link {
+                    from: (ball.x, ball.y),
+                    to: (other.x, other.y),
+                    alpha: self.opacity * (1.0 - squared.sqrt() / self.distance),
+                });
+            }
+        }
+        links
+    }
+
+    // Blend the lines into the band. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(
+        &self,
+        buffer: &mut [u8],
+        stride: usize,
+        clip: Clip,
+        links: &[Link],
+        blending: Blending,
+    ) {
+        let rgb = srgb::rgb(self.color);
+        let (top, bottom) = (clip.y0 as f32, clip.y1 as f32);
+        for link in links {
+            if link.from.1.max(link.to.1) < top || link.from.1.min(link.to.1) >= bottom {
+                continue;
+            }
+            let segment = (link.from, link.to);
+            blend_line(buffer, stride, clip, segment, rgb, link.alpha, blending);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Connect balls closer than `distance` (center to center; 0 turns it
+    // off) with `color` (0xRRGGBB) lines up to `opacity` (0..=1) opaque
+    pub fn set_plexus(&mut self, distance: f32, color: u32, opacity: f32) {
+        if !(distance.is_finite() && opacity.is_finite()) {
+            return;
+        }
+        self.plexus = Plexus {
+            distance: distance.max(0.0),
+            color: color & 0xFF_FFFF,
+            opacity: opacity.clamp(0.0, 1.0),
+        };
+    }
+
+    pub fn plexus_distance(&self) -> f32 {
+        self.plexus.distance
+    }
+
+    pub fn plexus_color(&self) -> u32 {
+        self.plexus.color
+    }
+
+    pub fn plexus_opacity(&self) -> f32 {
+        self.plexus.opacity
+    }
+
+    // The lines as x0, y0, x1, y1, opacity quintuples, for hosts drawing them
+    // with their own renderer
+    pub fn plexus_lines(&self) -> Vec<f32> {
+        self.plexus
+            .links(&self.balls)
+            .iter()
+            .flat_map(|link| [link.from.0, link.from.1, link.to.0, link.to.1, link.alpha])
+            .collect()
+    }
+}
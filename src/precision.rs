@@ -0,0 +1,305 @@
+// Double precision integration. In F64 mode every ball's position and
+// velocity live in an f64 shadow array that update() integrates and bounces;
+// the Ball records (what get_balls_ptr, views and rendering see) get the
+// rounded f32 values after each step. Radii, colors and splitting stay f32.
+//
+// External edits to a ball (add_ball, apply_snapshot, set_* ...) show up as
+// a mismatch between the ball and its rounded shadow, and simply reload the
+// shadow from the ball.
+
+use wasm_bindgen::prelude::*;
+
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::{events, profile, sim, Ball, Integrator, World};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Precision {
+    #[default]
+    F32 = 0,
+    F64 = 1,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Precise {
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+}
+
+impl StateHash for Precise {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        for value in [self.x, self.y, self.vx, self.vy] {
+            hash.write(&value);
+        }
+    }
+}
+
+impl Precise {
+    fn from_ball(ball: &Ball) -> Precise {
+        Precise {
+            x: ball.x as f64,
+            y: ball.y as f64,
+            vx: ball.vx as f64,
+            vy: ball.vy as f64,
+        }
+    }
+
+    fn matches(&self, ball: &Ball) -> bool {
+        self.x as f32 == ball.x
+            && self.y as f32 == ball.y
+            && self.vx as f32 == ball.vx
+            && self.vy as f32 == ball.vy
+    }
+
+    fn store(&self, ball: &mut Ball) {
+        ball.x = self.x as f32;
+        ball.y = self.y as f32;
+        ball.vx = self.vx as f32;
+        ball.vy = self.vy as f32;
+    }
+
+    // Same rules as sim::integrate, in f64
+    fn integrate(&mut self, spin: f32, config: &sim::SimConfig) {
+        let dt = config.dt as f64;
+        let (ax, ay) = (config.gravity_x as f64 * dt, config.gravity_y as f64 * dt);
+        match config.integrator {
+            Integrator::Euler => {
+                self.x += self.vx * dt;
+                self.y += self.vy * dt;
+                self.vx += ax;
+                self.vy += ay;
+                self.magnus(spin, config);
+            }
+            Integrator::SemiImplicitEuler | Integrator::Verlet => {
+                self.vx += ax;
+                self.vy += ay;
+                self.magnus(spin, config);
+                self.x += self.vx * dt;
+                self.y += self.vy * dt;
+            }
+        }
+    }
+
+    // Same rotation as the Magnus turn in sim::integrate
+    fn magnus(&mut self, spin: f32, config: &sim::SimConfig) {
+        if config.magnus == 0.0 || spin == 0.0 {
+            return;
+        }
+        let half = 0.5 * config.magnus as f64 * spin as f64 * config.dt as f64;
+        let scale = 1.0 / (1.0 + half * half);
+        let (vx, vy) = (self.vx, self.vy);
+        self.vx = ((1.0 - half * half) * vx - 2.0 * half * vy) * scale;
+        self.vy = ((1.0 - half * half) * vy + 2.0 * half * vx) * scale;
+    }
+
+    // Same rules as sim::bounce_walls, in f64
+    fn bounce_walls(&mut self, ball: &Ball, config: &sim::SimConfig) -> sim::WallHits {
+        let radius = ball.radius as f64;
+        let low = config.wall_inset as f64;
+        let right = config.width as f64 - low;
+        let bottom = config.height as f64 - low;
+        let closed = |wall: u32| config.open_walls & wall == 0;
+        let restitution = sim::wall_restitution(ball, config) as f64;
+        let mut hits = sim::WallHits::default();
+        let mut lost = 0.0;
+        // Speed away from the wall for a velocity component of `into` towards it
+        let mut rebound = |into: f64| {
+            if into <= 0.0 || restitution == 1.0 {
+                return into.abs();
+            }
+            lost += 0.5 * radius * radius * into * into * (1.0 - restitution * restitution);
+            into * restitution
+        };
+        if closed(sim::WALL_LEFT) && self.x - radius < low {
+            self.x = low + radius;
+            self.vx = rebound(-self.vx);
+            hits.x = true;
+        } else if closed(sim::WALL_RIGHT) && self.x + radius > right {
+            self.x = right - radius;
+            self.vx = -rebound(self.vx);
+            hits.x = true;
+        }
+        if closed(sim::WALL_TOP) && self.y - radius < low {
+            self.y = low + radius;
+            self.vy = rebound(-self.vy);
+            hits.y = true;
+        } else if closed(sim::WALL_BOTTOM) && self.y + radius > bottom {
+            self.y = bottom - radius;
+            self.vy = -rebound(self.vy);
+            hits.y = true;
+        }
+        hits.lost = lost as f32;
+        self.round_corner(radius, restitution, config, &mut hits);
+        hits
+    }
+
+    // Same rules as sim's rounded corners, in f64
+    fn round_corner(
+        &mut self,
+        radius: f64,
+        restitution: f64,
+        config: &sim::SimConfig,
+        hits: &mut sim::WallHits,
+    ) {
+        let Some((cx, cy)) = sim::corner_center(self.x as f32, self.y as f32, config) else {
+            return;
+        };
+        let (cx, cy) = (cx as f64, cy as f64);
+        let limit = config.corner_radius as f64 - radius;
+        let (dx, dy) = (self.x - cx, self.y - cy);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if limit <= 0.0 || distance <= limit {
+            return;
+        }
+        let (ox, oy) = (dx / distance, dy / distance);
+        self.x = cx + ox * limit;
+        self.y = cy + oy * limit;
+        let out = self.vx * ox + self.vy * oy;
+        if out > 0.0 {
+            self.vx -= (1.0 + restitution) * out * ox;
+            self.vy -= (1.0 + restitution) * out * oy;
+            hits.lost +=
+                (0.5 * radius * radius * out * out * (1.0 - restitution * restitution)) as f32;
+        }
+        hits.x = true;
+        hits.y = true;
+        hits.corner = Some((-ox as f32, -oy as f32));
+    }
+
+    // Same rules as sim::wall_friction, in f64 (the spin itself stays f32)
+    fn wall_friction(&mut self, ball: &mut Ball, config: &sim::SimConfig, hits: sim::WallHits) {
+        let share = config
+            .mix_rule
+            .mix(config.wall_friction, ball.friction)
+            .min(1.0);
+        if share <= 0.0 || !hits.any() {
+            return;
+        }
+        let share = share as f64;
+        let radius = ball.radius as f64;
+        let mut spin = ball.spin as f64;
+        if let Some((nx, ny)) = hits.corner {
+            let (nx, ny) = (nx as f64, ny as f64);
+            let slip = -self.vx * ny + self.vy * nx - spin * radius;
+            self.vx += share * slip / 3.0 * ny;
+            self.vy -= share * slip / 3.0 * nx;
+            spin += 2.0 * share * slip / (3.0 * radius);
+            ball.spin = spin as f32;
+            return;
+        }
+        if hits.x {
+            let nx = (config.width as f64 * 0.5 - self.x).signum();
+            let slip = self.vy * nx - spin * radius;
+            self.vy -= share * slip / 3.0 * nx;
+            spin += 2.0 * share * slip / (3.0 * radius);
+        }
+        if hits.y {
+            let ny = (config.height as f64 * 0.5 - self.y).signum();
+            let slip = -self.vx * ny - spin * radius;
+            self.vx += share * slip / 3.0 * ny;
+            spin += 2.0 * share * slip / (3.0 * radius);
+        }
+        ball.spin = spin as f32;
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Switch the integrator between f32 and f64. Switching to F64 starts from
+    // the current f32 state (and turns fixed point off); switching back keeps
+    // the rounded positions.
+    pub fn set_precision(&mut self, precision: Precision) {
+        #[cfg(feature = "fixed")]
+        if precision == Precision::F64 {
+            self.fixed = None;
+        }
+        self.precise = match precision {
+            Precision::F32 => None,
+            Precision::F64 => Some(self.balls.iter().map(Precise::from_ball).collect()),
+        };
+    }
+
+    pub fn precision(&self) -> Precision {
+        match self.precise {
+            Some(_) => Precision::F64,
+            None => Precision::F32,
+        }
+    }
+}
+
+impl World {
+    // One (sub-)step of update() in F64 mode. With profiling on, the whole
+    // step is reported as integration.
+    pub(crate) fn step_f64(&mut self, stamp: u32) {
+        let Some(mut precise) = self.precise.take() else {
+            return;
+        };
+        let start = self.profile.enabled().then(profile::now_ms);
+        precise.resize(self.balls.len(), Precise::default());
+
+        let config = self.sim_config();
+        let (width, height) = (self.width, self.height);
+        let (balls, modified, mut hook) = self.after_bounce(stamp);
+
+        for (id, (ball, modified)) in balls.iter_mut().zip(modified.iter_mut()).enumerate() {
+            if ball.alive == 0 {
+                continue;
+            }
+            let before = *ball;
+
+            let sanitized = sim::sanitize_ball(ball, width, height);
+            if sanitized != 0 {
+                events::push_sanitized(hook.events, id, stamp, ball, sanitized);
+            }
+            let state = &mut precise[id];
+            if !state.matches(ball) {
+                *state = Precise::from_ball(ball);
+            }
+
+            let was_just_split = ball.just_split == 1;
+            ball.just_split = 0;
+            let start = (state.x, state.y);
+            state.integrate(ball.spin, &config);
+            let hits = state.bounce_walls(ball, &config);
+            if config.integrator == Integrator::Verlet {
+                let dt = config.dt as f64;
+                if !hits.x {
+                    state.vx = (state.x - start.0) / dt;
+                }
+                if !hits.y {
+                    state.vy = (state.y - start.1) / dt;
+                }
+            }
+            state.wall_friction(ball, &config, hits);
+            state.store(ball);
+            sim::heat(ball, &config, hits);
+            hook.run(id, ball, hits, was_just_split);
+
+            if *ball != before {
+                *modified = stamp;
+            }
+        }
+
+        let (children, denied) = (hook.children, hook.denied);
+        for id in self.insert_children(stamp, children, denied) {
+            let id = id as usize;
+            if id >= precise.len() {
+                precise.resize(id + 1, Precise::default());
+            }
+            precise[id] = Precise::from_ball(&self.balls[id]);
+        }
+        self.precise = Some(precise);
+
+        if let Some(start) = start {
+            self.profile.add_step(profile::now_ms() - start);
+        }
+    }
+
+    pub(crate) fn precise_memory_bytes(&self) -> usize {
+        self.precise.as_ref().map_or(0, |precise| {
+            precise.capacity() * std::mem::size_of::<Precise>()
+        })
+    }
+}
@@ -0,0 +1,177 @@
+// Optional per-phase profiling. While enabled, update() runs its phases as
+// separate passes over the balls (same results, a little slower) so each can
+// be timed, and rendering records its own time. Read the last frame's numbers
+// with frame_timings().
+
+use std::cell::Cell;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{events, sim, World};
+
+// Milliseconds from an arbitrary origin: performance.now() in the browser
+// (window or worker), a monotonic Instant natively.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_ms() -> f64 {
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = now)]
+        fn performance_now() -> f64;
+    }
+    performance_now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+// Milliseconds spent in each phase of the most recent frame
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTimings {
+    pub frame: u32,          // Frame the update timings belong to
+    pub integration_ms: f64, // Sanitizing + moving every ball
+    pub wall_ms: f64,        // Wall bounces
+    pub split_ms: f64,       // Splitting and inserting the children
+    pub collision_ms: f64,   // Ball-ball collisions
+    pub render_ms: f64,      // Last render_* call
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Profile {
+    enabled: bool,
+    timings: Cell<FrameTimings>,
+}
+
+impl Profile {
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Start a new frame's update timings; sub-steps add to them
+    pub(crate) fn begin_frame(&self, frame: u32) {
+        if self.enabled {
+            self.timings.set(FrameTimings {
+                frame,
+                render_ms: self.timings.get().render_ms,
+                ..FrameTimings::default()
+            });
+        }
+    }
+
+    fn add(&self, integration_ms: f64, wall_ms: f64, split_ms: f64) {
+        let mut timings = self.timings.get();
+        timings.integration_ms += integration_ms;
+        timings.wall_ms += wall_ms;
+        timings.split_ms += split_ms;
+        self.timings.set(timings);
+    }
+
+    // A step that was run in one piece counts as integration
+    pub(crate) fn add_step(&self, update_ms: f64) {
+        self.add(update_ms, 0.0, 0.0);
+    }
+
+    pub(crate) fn add_collision(&self, collision_ms: f64) {
+        let mut timings = self.timings.get();
+        timings.collision_ms += collision_ms;
+        self.timings.set(timings);
+    }
+
+    pub(crate) fn record_render(&self, render_ms: f64) {
+        let mut timings = self.timings.get();
+        timings.render_ms = render_ms;
+        self.timings.set(timings);
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile.enabled = enabled;
+        if !enabled {
+            self.profile.timings.set(FrameTimings::default());
+        }
+    }
+
+    pub fn is_profiling(&self) -> bool {
+        self.profile.enabled
+    }
+
+    // All zero until profiling is enabled and a frame has run
+    pub fn frame_timings(&self) -> FrameTimings {
+        self.profile.timings.get()
+    }
+}
+
+impl World {
+    // One (sub-)step of update() as one timed pass per phase
+    pub(crate) fn step_profiled(&mut self, stamp: u32) {
+        let config = self.sim_config();
+        let before = self.balls.clone();
+
+        let t0 = now_ms();
+        let mut starts = vec![(0.0, 0.0); self.balls.len()];
+        let mut sanitized = Vec::new();
+        for (id, ball) in self.balls.iter_mut().enumerate() {
+            if ball.alive == 0 {
+                continue;
+            }
+            let flags = sim::sanitize_ball(ball, self.width, self.height);
+            if flags != 0 {
+                sanitized.push((id, *ball, flags));
+            }
+            ball.just_split = 0;
+            starts[id] = (ball.x, ball.y);
+            sim::integrate(ball, &config);
+        }
+
+        let t1 = now_ms();
+        let hits: Vec<sim::WallHits> = self
+            .balls
+            .iter_mut()
+            .zip(&starts)
+            .map(|(ball, &start)| {
+                if ball.alive == 0 {
+                    return sim::WallHits::default();
+                }
+                let hits = sim::bounce_walls(ball, &config);
+                if config.integrator == sim::Integrator::Verlet {
+                    sim::verlet_velocity(ball, start, hits, config.dt);
+                }
+                sim::wall_friction(ball, &config, hits);
+                sim::heat(ball, &config, hits);
+                hits
+            })
+            .collect();
+
+        // The shared post-bounce hook; sanitize events are pushed here so
+        // they land in the same order as in the unprofiled step
+        let t2 = now_ms();
+        let (balls, modified, mut hook) = self.after_bounce(stamp);
+        let mut sanitized = sanitized.into_iter().peekable();
+        for (id, ball) in balls.iter_mut().enumerate() {
+            if ball.alive == 0 {
+                continue;
+            }
+            if let Some((_, at, flags)) = sanitized.next_if(|&(at, _, _)| at == id) {
+                events::push_sanitized(hook.events, id, stamp, &at, flags);
+            }
+            hook.run(id, ball, hits[id], before[id].just_split == 1);
+            if *ball != before[id] {
+                modified[id] = stamp;
+            }
+        }
+        let (children, denied) = (hook.children, hook.denied);
+        self.insert_children(stamp, children, denied);
+
+        let t3 = now_ms();
+
+        self.profile.add(t1 - t0, t2 - t1, t3 - t2);
+    }
+}
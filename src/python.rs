@@ -0,0 +1,158 @@
+// Python bindings (feature "python"), e.g. for notebooks:
+//
+//   import bouncing_balls
+//   world = bouncing_balls.World(800, 600, seed=1)
+//   world.update(100)
+//   xy, r = world.positions(), world.radii()   # numpy arrays, one row per live ball
+//   image = world.render(800, 600)             # (height, width, 4) uint8 RGBA
+//
+// Build the extension with `maturin develop` (see pyproject.toml).
+
+use numpy::{PyArray1, PyArray2, PyArray3, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{World, WorldError};
+
+impl From<WorldError> for PyErr {
+    fn from(error: WorldError) -> PyErr {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+#[pyclass(unsendable, name = "World", module = "bouncing_balls")]
+pub struct PyWorld {
+    world: World,
+}
+
+#[pymethods]
+impl PyWorld {
+    // A seed makes the run reproducible (see World::new_seeded)
+    #[new]
+    #[pyo3(signature = (width, height, max_balls = 10_000, split_ratio = 0.8, seed = None))]
+    fn new(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+        seed: Option<u32>,
+    ) -> PyResult<PyWorld> {
+        let world = match seed {
+            Some(seed) => World::try_new_seeded(width, height, max_balls, split_ratio, seed)?,
+            None => World::try_new(width, height, max_balls, split_ratio)?,
+        };
+        Ok(PyWorld { world })
+    }
+
+    // Advance by `steps` frames
+    #[pyo3(signature = (steps = 1))]
+    fn update(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.world.update();
+        }
+    }
+
+    #[getter]
+    fn frame(&self) -> u32 {
+        self.world.frame()
+    }
+
+    #[getter]
+    fn width(&self) -> f32 {
+        self.world.width
+    }
+
+    #[getter]
+    fn height(&self) -> f32 {
+        self.world.height
+    }
+
+    fn __len__(&self) -> usize {
+        self.world.live_count()
+    }
+
+    // Ball ids of the rows returned by the array accessors below
+    fn ids<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        let ids = self.world.live_balls().map(|(id, _)| id as u32).collect();
+        PyArray1::from_vec(py, ids)
+    }
+
+    // (n, 2) float32 array of x, y
+    fn positions<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        self.pairs(py, |ball| [ball.x, ball.y])
+    }
+
+    // (n, 2) float32 array of vx, vy
+    fn velocities<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        self.pairs(py, |ball| [ball.vx, ball.vy])
+    }
+
+    fn radii<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        let radii = self
+            .world
+            .live_balls()
+            .map(|(_, ball)| ball.radius)
+            .collect();
+        PyArray1::from_vec(py, radii)
+    }
+
+    // 0xRRGGBB per ball
+    fn colors<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        let colors = self
+            .world
+            .live_balls()
+            .map(|(_, ball)| ball.color)
+            .collect();
+        PyArray1::from_vec(py, colors)
+    }
+
+    // Rasterize into a new (height, width, 4) uint8 RGBA array
+    fn render<'py>(
+        &self,
+        py: Python<'py>,
+        width: usize,
+        height: usize,
+    ) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        let mut pixels = vec![0; width * height * 4];
+        self.world
+            .render_to_buffer(&mut pixels, width, height, None)?;
+        PyArray1::from_vec(py, pixels).reshape([height, width, 4])
+    }
+
+    fn state_hash(&self) -> u32 {
+        self.world.state_hash()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "World({} x {}, {} balls, frame {})",
+            self.world.width,
+            self.world.height,
+            self.world.live_count(),
+            self.world.frame()
+        )
+    }
+}
+
+impl PyWorld {
+    fn pairs<'py>(
+        &self,
+        py: Python<'py>,
+        pick: impl Fn(&crate::Ball) -> [f32; 2],
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let values: Vec<f32> = self
+            .world
+            .live_balls()
+            .flat_map(|(_, ball)| pick(ball))
+            .collect();
+        let rows = values.len() / 2;
+        PyArray1::from_vec(py, values).reshape([rows, 2])
+    }
+}
+
+#[pymodule]
+fn bouncing_balls(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyWorld>()?;
+    module.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -0,0 +1,347 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{sim, World};
+
+// density_grid's limit (64 MiB of f32)
+const MAX_GRID_CELLS: usize = 1 << 24;
+
+#[wasm_bindgen]
+impl World {
+    // Ids of balls overlapping the circle (x, y, r)
+    pub fn balls_in_circle(&self, x: f32, y: f32, r: f32) -> Vec<u32> {
+        self.live_balls()
+            .filter(|(_, ball)| {
+                let dx = ball.x - x;
+                let dy = ball.y - y;
+                let reach = r + ball.radius;
+                dx * dx + dy * dy <= reach * reach
+            })
+            .map(|(id, _)| id as u32)
+            .collect()
+    }
+
+    // Ids of balls whose speed (pixels/frame) is strictly above `speed`
+    pub fn balls_faster_than(&self, speed: f32) -> Vec<u32> {
+        let speed_squared = speed * speed;
+        self.live_balls()
+            .filter(|(_, ball)| {
+                speed < 0.0 || ball.vx * ball.vx + ball.vy * ball.vy > speed_squared
+            })
+            .map(|(id, _)| id as u32)
+            .collect()
+    }
+
+    // Live balls per generation: index g holds how many balls have been through g splits
+    pub fn generation_counts(&self) -> Vec<u32> {
+        let mut counts = Vec::new();
+        for (_, ball) in self.live_balls() {
+            let generation = ball.generation as usize;
+            if generation >= counts.len() {
+                counts.resize(generation + 1, 0);
+            }
+            counts[generation] += 1;
+        }
+        counts
+    }
+
+    // Ball area (pi r^2) binned by center into a cells_x x cells_y grid over
+    // the arena, row-major from the top-left (Float32Array in JS). Divide by
+    // the cell area for a coverage density; overlapping balls can exceed 1.
+    // Empty if either count is 0 or there would be more than 2^24 cells.
+    pub fn density_grid(&self, cells_x: u32, cells_y: u32) -> Vec<f32> {
+        self.area_grid(cells_x as usize, cells_y as usize)
+    }
+
+    // Id of the ball with the largest radius (first one wins on ties), as a
+    // typed array like the other queries: one id, or empty without balls
+    pub fn largest_ball(&self) -> Vec<u32> {
+        let mut best: Option<(usize, f32)> = None;
+        for (id, ball) in self.live_balls() {
+            if best.is_none_or(|(_, radius)| ball.radius > radius) {
+                best = Some((id, ball.radius));
+            }
+        }
+        best.map(|(id, _)| id as u32).into_iter().collect()
+    }
+
+    // Id of the ball whose center is closest to (x, y), the same way
+    pub fn nearest_ball(&self, x: f32, y: f32) -> Vec<u32> {
+        let mut best: Option<(usize, f32)> = None;
+        for (id, ball) in self.live_balls() {
+            let dx = ball.x - x;
+            let dy = ball.y - y;
+            let dist_squared = dx * dx + dy * dy;
+            if best.is_none_or(|(_, d)| dist_squared < d) {
+                best = Some((id, dist_squared));
+            }
+        }
+        best.map(|(id, _)| id as u32).into_iter().collect()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitKind {
+    Ball = 0,
+    Wall = 1,
+    Obstacle = 2,
+}
+
+// Result of World::raycast. For wall hits `id` is the wall index
+// (0 = left, 1 = right, 2 = top, 3 = bottom), for obstacle hits the
+// obstacle's index.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub kind: HitKind,
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub distance: f32,
+    pub normal_x: f32,
+    pub normal_y: f32,
+}
+
+#[wasm_bindgen]
+impl World {
+    // First ball, obstacle or wall hit by the ray starting at (x, y) going along (dx, dy).
+    // The direction does not need to be normalized. A ray starting inside a ball or obstacle hits it at distance 0.
+    pub fn raycast(&self, x: f32, y: f32, dx: f32, dy: f32) -> Option<RayHit> {
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 || !len.is_finite() {
+            return None;
+        }
+        let (dx, dy) = (dx / len, dy / len);
+
+        let mut best = self.raycast_walls(x, y, dx, dy);
+
+        for (id, obstacle) in self.obstacles.iter().enumerate() {
+            let Some((t, (nx, ny))) = obstacle.raycast((x, y), (dx, dy)) else {
+                continue;
+            };
+            if best.is_some_and(|hit| hit.distance <= t) {
+                continue;
+            }
+            best = Some(RayHit {
+                kind: HitKind::Obstacle,
+                id: id as u32,
+                x: x + dx * t,
+                y: y + dy * t,
+                distance: t,
+                normal_x: nx,
+                normal_y: ny,
+            });
+        }
+
+        for (id, ball) in self.live_balls() {
+            let ox = x - ball.x;
+            let oy = y - ball.y;
+            let b = ox * dx + oy * dy;
+            let c = ox * ox + oy * oy - ball.radius * ball.radius;
+            if c > 0.0 && b > 0.0 {
+                continue; // Outside and pointing away
+            }
+            let disc = b * b - c;
+            if disc < 0.0 {
+                continue;
+            }
+            let t = (-b - disc.sqrt()).max(0.0);
+            if best.is_some_and(|hit| hit.distance <= t) {
+                continue;
+            }
+            let hx = x + dx * t;
+            let hy = y + dy * t;
+            let (nx, ny) = if ball.radius > 0.0 {
+                ((hx - ball.x) / ball.radius, (hy - ball.y) / ball.radius)
+            } else {
+                (-dx, -dy)
+            };
+            best = Some(RayHit {
+                kind: HitKind::Ball,
+                id: id as u32,
+                x: hx,
+                y: hy,
+                distance: t,
+                normal_x: nx,
+                normal_y: ny,
+            });
+        }
+
+        best
+    }
+}
+
+impl World {
+    fn raycast_walls(&self, x: f32, y: f32, dx: f32, dy: f32) -> Option<RayHit> {
+        let mut best: Option<RayHit> = None;
+        // Rays stop at the wall bars' inner face
+        let inset = self.wall_inset();
+        // (wall index, distance along the ray, normal pointing back into the arena)
+        let candidates = [
+            (
+                0,
+                if dx < 0.0 {
+                    (inset - x) / dx
+                } else {
+                    f32::INFINITY
+                },
+                (1.0, 0.0),
+            ),
+            (
+                1,
+                if dx > 0.0 {
+                    (self.width - inset - x) / dx
+                } else {
+                    f32::INFINITY
+                },
+                (-1.0, 0.0),
+            ),
+            (
+                2,
+                if dy < 0.0 {
+                    (inset - y) / dy
+                } else {
+                    f32::INFINITY
+                },
+                (0.0, 1.0),
+            ),
+            (
+                3,
+                if dy > 0.0 {
+                    (self.height - inset - y) / dy
+                } else {
+                    f32::INFINITY
+                },
+                (0.0, -1.0),
+            ),
+        ];
+        for (id, t, (nx, ny)) in candidates {
+            if !t.is_finite() || t < 0.0 || best.is_some_and(|hit| hit.distance <= t) {
+                continue;
+            }
+            best = Some(RayHit {
+                kind: HitKind::Wall,
+                id,
+                x: x + dx * t,
+                y: y + dy * t,
+                distance: t,
+                normal_x: nx,
+                normal_y: ny,
+            });
+        }
+        // Past a rounded corner's arc the ray has already left the arena: it
+        // crossed the arc on the way, at the far root of |o + t d - c| = rc
+        let config = self.sim_config();
+        if let Some(hit) = &mut best {
+            if let Some((cx, cy)) = sim::corner_center(hit.x, hit.y, &config) {
+                let radius = config.corner_radius;
+                let (ox, oy) = (x - cx, y - cy);
+                let b = ox * dx + oy * dy;
+                let c = ox * ox + oy * oy - radius * radius;
+                let t = (-b + (b * b - c).max(0.0).sqrt()).max(0.0);
+                hit.x = x + dx * t;
+                hit.y = y + dy * t;
+                hit.distance = t;
+                hit.normal_x = (cx - hit.x) / radius;
+                hit.normal_y = (cy - hit.y) / radius;
+            }
+        }
+        best
+    }
+
+    // Shared by density_grid and anything else that wants ball area per cell;
+    // balls whose center is outside the arena count in the nearest edge cell
+    pub(crate) fn area_grid(&self, cells_x: usize, cells_y: usize) -> Vec<f32> {
+        let cells = cells_x.saturating_mul(cells_y);
+        if cells == 0 || cells > MAX_GRID_CELLS {
+            return Vec::new();
+        }
+        let mut grid = vec![0.0; cells];
+        let (scale_x, scale_y) = (cells_x as f32 / self.width, cells_y as f32 / self.height);
+        for (_, ball) in self.live_balls() {
+            let cx = ((ball.x * scale_x).max(0.0) as usize).min(cells_x - 1);
+            let cy = ((ball.y * scale_y).max(0.0) as usize).min(cells_y - 1);
+            grid[cy * cells_x + cx] += std::f32::consts::PI * ball.radius * ball.radius;
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HitKind, World};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn density_grid_limits() {
+        let world = World::new(200.0, 150.0, 64, 0.7);
+        assert!(world.density_grid(0, 10).is_empty());
+        assert!(world.density_grid(u32::MAX, u32::MAX).is_empty());
+        assert!(world.density_grid(1 << 12, 1 << 13).is_empty());
+        assert_eq!(world.density_grid(1 << 12, 1 << 12).len(), 1 << 24);
+    }
+
+    #[test]
+    fn raycast_hits_obstacles() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        world.add_circle_obstacle(100.0, 75.0, 10.0).unwrap();
+        let hit = world.raycast(20.0, 75.0, 2.0, 0.0).unwrap();
+        assert_eq!((hit.kind, hit.id), (HitKind::Obstacle, 0));
+        assert!(close(hit.x, 90.0) && close(hit.distance, 70.0));
+        assert!(close(hit.normal_x, -1.0) && close(hit.normal_y, 0.0));
+
+        // The outer face of a funnel's right wall, from below
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        let left = world.add_funnel(100.0, 20.0, 100.0, 20.0, 80.0).unwrap();
+        let hit = world.raycast(130.0, 140.0, 0.0, -1.0).unwrap();
+        assert_eq!((hit.kind, hit.id), (HitKind::Obstacle, left + 1));
+        assert!(close(hit.y, 72.0) && hit.normal_x > 0.0 && hit.normal_y > 0.0);
+
+        // Missing every obstacle ends at the wall
+        let hit = world.raycast(100.0, 140.0, 0.0, -1.0).unwrap();
+        assert_eq!((hit.kind, hit.id), (HitKind::Wall, 2));
+    }
+
+    #[test]
+    fn raycast_follows_rotation() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        world.add_rect_obstacle(80.0, 55.0, 40.0, 40.0).unwrap();
+        let hit = world.raycast(20.0, 75.0, 1.0, 0.0).unwrap();
+        assert!(close(hit.x, 80.0) && close(hit.normal_x, -1.0));
+
+        world.set_obstacle_rotation(0, std::f32::consts::FRAC_PI_4);
+        world.update();
+        let hit = world.raycast(20.0, 75.0, 1.0, 0.0).unwrap();
+        assert!(close(hit.x, 100.0 - 20.0 * std::f32::consts::SQRT_2));
+        // Starting inside
+        let hit = world.raycast(100.0, 75.0, 0.0, 1.0).unwrap();
+        assert_eq!((hit.kind, hit.distance), (HitKind::Obstacle, 0.0));
+    }
+
+    // Whatever is nearest along the ray wins
+    #[test]
+    fn raycast_picks_the_nearest_hit() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        world.add_circle_obstacle(100.0, 75.0, 10.0).unwrap();
+        let ball = world.add_ball(60.0, 75.0, 0.0, 0.0, 5.0, 0).unwrap();
+        let hit = world.raycast(20.0, 75.0, 1.0, 0.0).unwrap();
+        assert_eq!((hit.kind, hit.id), (HitKind::Ball, ball));
+        let hit = world.raycast(180.0, 75.0, -1.0, 0.0).unwrap();
+        assert_eq!((hit.kind, hit.id), (HitKind::Obstacle, 0));
+    }
+
+    #[test]
+    fn single_ball_queries_are_arrays() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        assert!(world.largest_ball().is_empty());
+        assert!(world.nearest_ball(0.0, 0.0).is_empty());
+        world.add_ball(20.0, 20.0, 0.0, 0.0, 5.0, 0).unwrap();
+        let big = world.add_ball(150.0, 100.0, 0.0, 0.0, 9.0, 0).unwrap();
+        world.add_ball(160.0, 100.0, 0.0, 0.0, 9.0, 0).unwrap();
+        assert_eq!(world.largest_ball(), [big]);
+        assert_eq!(world.nearest_ball(10.0, 10.0), [0]);
+    }
+}
@@ -0,0 +1,890 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::alpha::{self, OutputAlpha};
+use crate::arena::fill_walls;
+use crate::background::{Background, BackgroundFit};
+use crate::collision::Grid;
+use crate::despawn::Despawns;
+use crate::flash::Flashes;
+use crate::gooey::{fill_necks, Neck};
+use crate::heatmap::WallHeat;
+use crate::obstacles::{fill_ghost, fill_obstacle, Obstacle};
+use crate::outlines::Outlines;
+use crate::plexus::{Link, Plexus};
+use crate::squash::{Shape, Squash};
+use crate::srgb::Blending;
+use crate::trails::{fill_trail, Trails};
+use crate::voronoi::Voronoi;
+use crate::water::Water;
+use crate::{camera, profile, sim, thermal, Ball, World, WorldError};
+
+// Renderer settings and caches owned by each World
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RenderState {
+    pub draw_order: DrawOrder,
+    pub color_mode: ColorMode,
+    pub filter: Option<RenderFilter>,
+    pub lod: Lod,
+    pub mask_cache: bool,
+    pub background: Option<Background>,
+    pub background_fit: BackgroundFit,
+    pub blending: Blending,
+    pub output_alpha: OutputAlpha,
+    pub transparent: bool, // Clear to transparent instead of CLEAR_COLOR
+    // Filled lazily while rendering, hence the RefCell (rendering only borrows the World)
+    pub masks: RefCell<HashMap<u32, CircleMask>>,
+}
+
+// Painter's algorithm order: balls later in the order are drawn on top
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DrawOrder {
+    #[default]
+    Insertion = 0, // Ball id order (children drawn over their parents)
+    ByRadiusDesc = 1, // Big balls behind small ones
+    ByY = 2,          // Top of the screen first, for a pseudo-depth look
+}
+
+// Balls outside these limits are skipped when rasterizing (physics is unaffected)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RenderFilter {
+    min_radius: f32,
+    max_radius: f32,
+    min_speed: f32,
+}
+
+impl RenderFilter {
+    fn keeps(&self, ball: &Ball) -> bool {
+        let speed2 = ball.vx * ball.vx + ball.vy * ball.vy;
+        ball.radius >= self.min_radius
+            && ball.radius <= self.max_radius
+            && speed2 >= self.min_speed * self.min_speed
+    }
+}
+
+// Level of detail for tiny balls: below `radius` a ball is drawn as a single
+// pixel (radius < 1) or a 2x2 block instead of a circle. With `aggregate`,
+// single-pixel balls add their color weighted by their area to the pixel, so
+// a dense cluster of them shows up as one brighter dot.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Lod {
+    radius: f32, // 0 = off
+    aggregate: bool,
+}
+
+// What a ball's fill color comes from
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Ball = 0, // Its own `color`
+    Temperature = 1, // Blue (cold) to red to white (hot), see thermal.rs
+}
+
+impl ColorMode {
+    pub(crate) fn fill(self, ball: &Ball) -> u32 {
+        match self {
+            ColorMode::Ball => ball.color,
+            ColorMode::Temperature => thermal::heat_color(ball.temperature),
+        }
+    }
+}
+
+// Stop caching once this many distinct radii were seen; the cache is rebuilt from scratch
+const MAX_CACHED_MASKS: usize = 1024;
+// Larger balls are always rasterized directly: a mask costs 8 bytes per pixel of radius
+const MAX_MASK_RADIUS: f32 = 4096.0;
+
+// Radii are quantized to quarter pixels for the cache key
+const MASK_STEPS_PER_PIXEL: f32 = 4.0;
+
+// Precomputed coverage of a circle centered on a pixel: for every row from
+// -reach to +reach, the half-width of the covered span (-1 for an empty row)
+#[derive(Clone, Debug)]
+pub(crate) struct CircleMask {
+    reach: i32,
+    half_widths: Vec<i32>,
+}
+
+impl RenderState {
+    pub(crate) fn memory_usage_bytes(&self) -> usize {
+        self.masks
+            .borrow()
+            .values()
+            .map(|mask| {
+                std::mem::size_of::<(u32, CircleMask)>()
+                    + mask.half_widths.capacity() * std::mem::size_of::<i32>()
+            })
+            .sum()
+    }
+}
+
+impl CircleMask {
+    fn key(radius: f32) -> u32 {
+        (radius * MASK_STEPS_PER_PIXEL).round() as u32
+    }
+
+    fn new(key: u32) -> CircleMask {
+        let r = key as f32 / MASK_STEPS_PER_PIXEL;
+        let r_squared = r * r;
+        let reach = r as i32;
+        let half_widths = (-reach..=reach)
+            .map(|dy| {
+                let rest = r_squared - (dy * dy) as f32;
+                if rest < 0.0 {
+                    -1
+                } else {
+                    rest.sqrt() as i32
+                }
+            })
+            .collect();
+        CircleMask { reach, half_widths }
+    }
+}
+
+// Target surface description, validated once before any pixel is written
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Surface {
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize, // Bytes per row (>= width * 4)
+}
+
+impl Surface {
+    pub(crate) fn new(
+        buffer_len: usize,
+        width: usize,
+        height: usize,
+        stride: Option<usize>,
+    ) -> Result<Surface, WorldError> {
+        // Saturating: sizes that overflow usize can't match any buffer
+        let row_bytes = width.saturating_mul(4);
+        let surface = Surface {
+            width,
+            height,
+            stride: stride.unwrap_or(row_bytes),
+        };
+        if surface.stride < row_bytes {
+            return Err(WorldError::InvalidStride {
+                stride: surface.stride,
+                min: row_bytes,
+            });
+        }
+        // Tightly packed buffers must match exactly; padded ones may omit the last row's padding
+        let expected = match stride {
+            None => row_bytes.saturating_mul(height),
+            Some(_) if height == 0 => 0,
+            Some(_) => surface
+                .stride
+                .saturating_mul(height - 1)
+                .saturating_add(row_bytes),
+        };
+        let size_ok = match stride {
+            None => buffer_len == expected,
+            Some(_) => buffer_len >= expected,
+        };
+        if !size_ok {
+            log_warn!("pixel buffer has {buffer_len} bytes but a {width}x{height} surface needs {expected}");
+            return Err(WorldError::BufferSizeMismatch {
+                expected,
+                actual: buffer_len,
+            });
+        }
+        Ok(surface)
+    }
+}
+
+// Pixel rectangle [x0, x1) x [y0, y1), always clamped to its surface
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Clip {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Clip {
+    pub(crate) fn full(surface: &Surface) -> Clip {
+        Clip {
+            x0: 0,
+            y0: 0,
+            x1: surface.width,
+            y1: surface.height,
+        }
+    }
+
+    pub(crate) fn new(surface: &Surface, x: usize, y: usize, w: usize, h: usize) -> Clip {
+        Clip {
+            x0: x.min(surface.width),
+            y0: y.min(surface.height),
+            x1: x.saturating_add(w).min(surface.width),
+            y1: y.saturating_add(h).min(surface.height),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Render directly to pixel buffer (RGBA format for ImageData).
+    // `stride` is the row pitch in bytes and defaults to width * 4.
+    pub fn render_to_buffer(
+        &self,
+        buffer: &mut [u8],
+        width: usize,
+        height: usize,
+        stride: Option<usize>,
+    ) -> Result<(), WorldError> {
+        let surface = Surface::new(buffer.len(), width, height, stride)?;
+        self.render_surface(buffer, surface, Clip::full(&surface));
+        Ok(())
+    }
+
+    // Draw into a padded surface whose rows are `stride_bytes` apart, without an intermediate copy
+    pub fn render_to_buffer_strided(
+        &self,
+        buffer: &mut [u8],
+        width: usize,
+        height: usize,
+        stride_bytes: usize,
+    ) -> Result<(), WorldError> {
+        self.render_to_buffer(buffer, width, height, Some(stride_bytes))
+    }
+
+    pub fn set_draw_order(&mut self, order: DrawOrder) {
+        self.render.draw_order = order;
+    }
+
+    pub fn draw_order(&self) -> DrawOrder {
+        self.render.draw_order
+    }
+
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.render.color_mode = mode;
+    }
+
+    pub fn color_mode(&self) -> ColorMode {
+        self.render.color_mode
+    }
+
+    // Only draw balls with min_radius <= radius <= max_radius moving at least
+    // min_speed pixels/frame. Tiny or resting balls add little to the picture
+    // but can dominate fill time in saturated worlds. Ignored unless every
+    // argument is a number (max_radius may be infinite) and min <= max.
+    pub fn set_render_filter(&mut self, min_radius: f32, max_radius: f32, min_speed: f32) {
+        if min_radius.is_nan()
+            || max_radius.is_nan()
+            || min_speed.is_nan()
+            || min_radius > max_radius
+        {
+            return;
+        }
+        self.render.filter = Some(RenderFilter {
+            min_radius,
+            max_radius,
+            min_speed: min_speed.max(0.0),
+        });
+    }
+
+    // Draw every ball again
+    pub fn clear_render_filter(&mut self) {
+        self.render.filter = None;
+    }
+
+    // Draw balls smaller than `radius` (e.g. 2) as a pixel or a 2x2 block; 0
+    // turns level of detail off. See `Lod` for `aggregate`.
+    pub fn set_lod(&mut self, radius: f32, aggregate: bool) {
+        let radius = if radius.is_finite() {
+            radius.max(0.0)
+        } else {
+            0.0
+        };
+        self.render.lod = Lod { radius, aggregate };
+    }
+
+    pub fn lod_radius(&self) -> f32 {
+        self.render.lod.radius
+    }
+
+    // Blit cached per-radius span masks instead of testing every pixel's distance.
+    // Positions and radii snap to the pixel grid (radius to 1/4 px), which is
+    // visually identical at 1:1 scale and much faster for many equal-size balls.
+    pub fn set_mask_cache(&mut self, enabled: bool) {
+        self.render.mask_cache = enabled;
+        if !enabled {
+            self.render.masks.borrow_mut().clear();
+        }
+    }
+
+    // Clear and draw only inside the clip rectangle; pixels outside it are left untouched.
+    // The buffer still covers the whole width x height surface.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_region(
+        &self,
+        buffer: &mut [u8],
+        width: usize,
+        height: usize,
+        clip_x: usize,
+        clip_y: usize,
+        clip_w: usize,
+        clip_h: usize,
+    ) -> Result<(), WorldError> {
+        let surface = Surface::new(buffer.len(), width, height, None)?;
+        let clip = Clip::new(&surface, clip_x, clip_y, clip_w, clip_h);
+        self.render_surface(buffer, surface, clip);
+        Ok(())
+    }
+}
+
+// Rows per band for tiled rasterization. Each band owns a disjoint slice of the
+// framebuffer, so bands can be rasterized independently (and in parallel).
+const BAND_ROWS: usize = 64;
+
+// Everything a band needs to rasterize, shareable across threads
+struct Frame<'a> {
+    clear: [u8; 4],
+    background: Option<&'a Background>,
+    background_fit: BackgroundFit,
+    blending: Blending,
+    voronoi: &'a Voronoi,
+    regions: Option<&'a Grid>, // The Voronoi grid, while shading is on
+    balls: &'a [Ball],
+    masks: Option<&'a HashMap<u32, CircleMask>>,
+    obstacles: &'a [Obstacle],
+    ghost: Option<Obstacle>,
+    squash: Option<&'a Squash>,
+    color_mode: ColorMode,
+    wall_heat: &'a WallHeat,
+    trails: Option<&'a Trails>,
+    despawns: &'a Despawns,
+    flashes: &'a Flashes,
+    outlines: &'a Outlines,
+    plexus: &'a Plexus,
+    links: &'a [Link],
+    necks: &'a [Neck],
+    water: &'a Water,
+    lod: Lod,
+    arena: (f32, f32),
+    walls: sim::SimConfig, // Wall geometry, for the wall bars and corners
+    wall_color: u32,
+    frame: u32,
+    grow_frames: u32,
+}
+
+impl World {
+    pub(crate) fn render_surface(&self, buffer: &mut [u8], surface: Surface, clip: Clip) {
+        let start = self.profile.enabled().then(profile::now_ms);
+        match self.camera_view() {
+            // Paint the whole arena 1:1, then look at it through the camera
+            Some(view) => {
+                let (width, height) = (surface.width, surface.height);
+                let scene_surface = Surface {
+                    width,
+                    height,
+                    stride: width * 4,
+                };
+                let mut scene = vec![0; width * height * 4];
+                self.paint(&mut scene, scene_surface, Clip::full(&scene_surface));
+                let arena = (self.width, self.height);
+                let clear = alpha::clear_pixel(self.render.transparent);
+                camera::sample(
+                    (&scene, scene_surface),
+                    buffer,
+                    surface.stride,
+                    clip,
+                    view,
+                    arena,
+                    clear,
+                );
+            }
+            None => self.paint(buffer, surface, clip),
+        }
+        self.draw_minimap(buffer, surface, clip);
+        self.finish_output(buffer, surface.stride, clip);
+        if let Some(start) = start {
+            self.profile.record_render(profile::now_ms() - start);
+        }
+    }
+
+    fn paint(&self, buffer: &mut [u8], surface: Surface, clip: Clip) {
+        let ids = self.draw_list();
+        self.with_frame(|frame| {
+            #[cfg(feature = "parallel")]
+            if clip.y1 - clip.y0 > BAND_ROWS {
+                let bins = self.bin_into_bands(clip, &ids);
+                frame.render_bands_parallel(buffer, surface, clip, &bins);
+                return;
+            }
+
+            let origin = clip.y0 * surface.stride;
+            frame.render_band(&mut buffer[origin..], surface.stride, clip, &ids);
+        });
+    }
+
+    // Render a width x height surface BAND_ROWS rows at a time into one
+    // reused buffer, handing each band (tightly packed, starting at row y0)
+    // to `sink`
+    pub(crate) fn paint_bands(
+        &self,
+        width: usize,
+        height: usize,
+        mut sink: impl FnMut(usize, &[u8]),
+    ) {
+        let ids = self.draw_list();
+        let stride = width * 4;
+        let mut band = vec![0; stride * BAND_ROWS.min(height)];
+        self.with_frame(|frame| {
+            for y0 in (0..height).step_by(BAND_ROWS) {
+                let y1 = (y0 + BAND_ROWS).min(height);
+                let clip = Clip {
+                    x0: 0,
+                    y0,
+                    x1: width,
+                    y1,
+                };
+                let rows = &mut band[..(y1 - y0) * stride];
+                frame.render_band(rows, stride, clip, &ids);
+                sink(y0, rows);
+            }
+        });
+    }
+
+    // Fill the mask cache and describe the current frame for the rasterizer
+    fn with_frame<R>(&self, paint: impl FnOnce(&Frame) -> R) -> R {
+        let mut masks = self.render.masks.borrow_mut();
+        if self.render.mask_cache {
+            if masks.len() > MAX_CACHED_MASKS {
+                masks.clear();
+            }
+            for ball in self
+                .balls
+                .iter()
+                .filter(|ball| ball.radius <= MAX_MASK_RADIUS)
+            {
+                let key = CircleMask::key(ball.radius);
+                masks.entry(key).or_insert_with(|| CircleMask::new(key));
+            }
+        }
+        let links = self.plexus.links(&self.balls);
+        let regions = self.voronoi.grid(&self.balls, self.width, self.height);
+        let necks = self.gooey.necks(
+            &self.balls,
+            self.frame,
+            self.split.grow_frames,
+            self.render.color_mode,
+        );
+        let frame = Frame {
+            clear: alpha::clear_pixel(self.render.transparent),
+            background: self.render.background.as_ref(),
+            background_fit: self.render.background_fit,
+            blending: self.render.blending,
+            voronoi: &self.voronoi,
+            regions: regions.as_ref(),
+            balls: &self.balls,
+            masks: self.render.mask_cache.then_some(&*masks),
+            obstacles: &self.obstacles,
+            ghost: self.editor.ghost,
+            squash: self.squash.enabled().then_some(&self.squash),
+            color_mode: self.render.color_mode,
+            wall_heat: &self.wall_heat,
+            trails: self.trails.drawn(),
+            despawns: &self.despawns,
+            flashes: &self.flashes,
+            outlines: &self.outlines,
+            plexus: &self.plexus,
+            links: &links,
+            necks: &necks,
+            water: &self.water,
+            lod: self.render.lod,
+            arena: (self.width, self.height),
+            walls: self.sim_config(),
+            wall_color: self.wall_color,
+            frame: self.frame,
+            grow_frames: self.split.grow_frames,
+        };
+        paint(&frame)
+    }
+
+    // Ids of the balls passing the render filter, in the order they should be
+    // painted: by layer, then by the draw order within a layer (stable sorts
+    // keep id order on ties)
+    pub(crate) fn draw_list(&self) -> Vec<u32> {
+        let filter = self.render.filter;
+        let mut ids: Vec<u32> = self
+            .live_balls()
+            .filter(|(_, ball)| filter.is_none_or(|filter| filter.keeps(ball)))
+            .map(|(id, _)| id as u32)
+            .collect();
+        let balls = &self.balls;
+        match self.render.draw_order {
+            DrawOrder::Insertion => {}
+            DrawOrder::ByRadiusDesc => ids.sort_by(|&a, &b| {
+                balls[b as usize]
+                    .radius
+                    .total_cmp(&balls[a as usize].radius)
+            }),
+            DrawOrder::ByY => {
+                ids.sort_by(|&a, &b| balls[a as usize].y.total_cmp(&balls[b as usize].y))
+            }
+        }
+        if balls.iter().any(|ball| ball.layer != 0) {
+            ids.sort_by_key(|&id| balls[id as usize].layer);
+        }
+        ids
+    }
+
+    // Assign every ball to the bands its bounding box touches, keeping draw order within each band
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    fn bin_into_bands(&self, clip: Clip, ids: &[u32]) -> Vec<Vec<u32>> {
+        let band_count = (clip.y1 - clip.y0).div_ceil(BAND_ROWS);
+        let mut bins = vec![Vec::new(); band_count];
+        for &id in ids {
+            let ball = &self.balls[id as usize];
+            // One pixel of slack covers pixel-snapped mask blits
+            let reach = ball.radius * self.squash.max_stretch() + 1.0;
+            let top = (ball.y - reach).max(clip.y0 as f32);
+            let bottom = (ball.y + reach).min(clip.y1 as f32 - 1.0);
+            if top.is_nan() || bottom.is_nan() || top > bottom {
+                continue;
+            }
+            let first = (top as usize - clip.y0) / BAND_ROWS;
+            let last = ((bottom as usize - clip.y0) / BAND_ROWS).min(band_count - 1);
+            for bin in &mut bins[first..=last] {
+                bin.push(id);
+            }
+        }
+        bins
+    }
+}
+
+impl Frame<'_> {
+    fn color(&self, ball: &Ball) -> u32 {
+        self.color_mode.fill(ball)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn render_bands_parallel(
+        &self,
+        buffer: &mut [u8],
+        surface: Surface,
+        clip: Clip,
+        bins: &[Vec<u32>],
+    ) {
+        use rayon::prelude::*;
+
+        let stride = surface.stride;
+        buffer[clip.y0 * stride..]
+            .par_chunks_mut(BAND_ROWS * stride)
+            .zip(bins.par_iter())
+            .enumerate()
+            .for_each(|(band, (band_buffer, ids))| {
+                let y0 = clip.y0 + band * BAND_ROWS;
+                let band_clip = Clip {
+                    y0,
+                    y1: (y0 + BAND_ROWS).min(clip.y1),
+                    ..clip
+                };
+                self.render_band(band_buffer, stride, band_clip, ids);
+            });
+    }
+
+    // Clear and draw the given balls inside `clip`. `buffer` starts at row `clip.y0`.
+    fn render_band(&self, buffer: &mut [u8], stride: usize, clip: Clip, ids: &[u32]) {
+        // Clear buffer (dark background or transparent, then the image),
+        // leaving row padding untouched
+        for py in clip.y0..clip.y1 {
+            let row = (py - clip.y0) * stride;
+            for pixel in buffer[row + clip.x0 * 4..row + clip.x1 * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&self.clear);
+            }
+        }
+        if let Some(background) = self.background {
+            let fit = self.background_fit;
+            background.fill(buffer, stride, clip, fit, self.arena, self.blending);
+        }
+        if let Some(regions) = self.regions {
+            let regions = (regions, self.balls);
+            self.voronoi.fill(
+                buffer,
+                stride,
+                clip,
+                regions,
+                self.color_mode,
+                self.blending,
+            );
+        }
+
+        fill_walls(buffer, stride, clip, &self.walls, self.wall_color);
+        for obstacle in self.obstacles {
+            fill_obstacle(buffer, stride, clip, obstacle);
+        }
+        let (width, height) = self.arena;
+        self.wall_heat.fill(buffer, stride, clip, width, height);
+
+        // Trails reach outside their ball's bands, so every band draws all of them
+        if let Some(trails) = self.trails {
+            for (id, ball) in self.balls.iter().enumerate() {
+                if let Some(trail) = trails.get(id).filter(|_| ball.alive != 0) {
+                    fill_trail(buffer, stride, clip, trail, self.color(ball), self.blending);
+                }
+            }
+        }
+
+        self.plexus
+            .fill(buffer, stride, clip, self.links, self.blending);
+        self.despawns.fill(
+            buffer,
+            stride,
+            clip,
+            self.frame,
+            self.color_mode,
+            self.blending,
+        );
+
+        fill_necks(buffer, stride, clip, self.necks);
+
+        // Draw each ball as filled circles
+        for &id in ids {
+            let ball = &self.balls[id as usize];
+            let ball = &Ball {
+                radius: grown_radius(ball, self.frame, self.grow_frames),
+                ..*ball
+            };
+            let color = self.color(ball);
+            if ball.radius < self.lod.radius {
+                plot_tiny(buffer, stride, clip, ball, color, self.lod.aggregate);
+                continue;
+            }
+            let shape = self
+                .squash
+                .and_then(|squash| squash.shape(id as usize, ball, self.frame));
+            let mask = self
+                .masks
+                .and_then(|masks| masks.get(&CircleMask::key(ball.radius)));
+            match (shape, mask) {
+                (Some(shape), _) => fill_ellipse(buffer, stride, clip, shape, color),
+                (None, Some(mask)) => blit_mask(buffer, stride, clip, ball, color, mask),
+                (None, None) => fill_circle(buffer, stride, clip, ball, color),
+            }
+            // Rings follow the round outline, so squashed balls go without
+            if let Some(outline) = self.outlines.get(id as usize).filter(|_| shape.is_none()) {
+                outline.fill(buffer, stride, clip, ball);
+            }
+        }
+
+        self.flashes
+            .fill(buffer, stride, clip, self.frame, self.blending);
+        self.water.fill(buffer, stride, clip, self.blending);
+
+        if let Some(ghost) = &self.ghost {
+            fill_ghost(buffer, stride, clip, ghost);
+        }
+    }
+}
+
+// A solid disc, pixel centers within the radius
+fn fill_circle(buffer: &mut [u8], stride: usize, clip: Clip, ball: &Ball, color: u32) {
+    let cx = ball.x;
+    let cy = ball.y;
+    let r = ball.radius;
+    let r_squared = r * r;
+
+    // Extract RGB from color
+    let red = ((color >> 16) & 0xFF) as u8;
+    let green = ((color >> 8) & 0xFF) as u8;
+    let blue = (color & 0xFF) as u8;
+
+    // Bounding box for efficiency
+    let x_min = ((cx - r).max(clip.x0 as f32) as i32).max(clip.x0 as i32);
+    let x_max = ((cx + r).min(clip.x1 as f32) as i32).min(clip.x1 as i32);
+    let y_min = ((cy - r).max(clip.y0 as f32) as i32).max(clip.y0 as i32);
+    let y_max = ((cy + r).min(clip.y1 as f32) as i32).min(clip.y1 as i32);
+
+    // Draw filled circle using distance check
+    for py in y_min..y_max {
+        let row = (py as usize - clip.y0) * stride;
+        for px in x_min..x_max {
+            let dx = px as f32 - cx;
+            let dy = py as f32 - cy;
+            let dist_squared = dx * dx + dy * dy;
+
+            // Only draw if inside circle
+            if dist_squared <= r_squared {
+                let idx = row + px as usize * 4;
+                buffer[idx] = red;
+                buffer[idx + 1] = green;
+                buffer[idx + 2] = blue;
+                buffer[idx + 3] = 255;
+            }
+        }
+    }
+}
+
+// A split child's radius on screen while it grows in (SplitConfig::grow_frames):
+// eases out from a sliver in its first frame to the full radius
+pub(crate) fn grown_radius(ball: &Ball, frame: u32, grow_frames: u32) -> f32 {
+    let age = frame.wrapping_sub(ball.born_frame);
+    if ball.generation == 0 || age >= grow_frames {
+        return ball.radius;
+    }
+    let left = 1.0 - (age + 1) as f32 / grow_frames as f32;
+    ball.radius * (1.0 - left * left)
+}
+
+// Level-of-detail stand-in for a tiny ball: one pixel, or 2x2 from radius 1 up
+fn plot_tiny(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    ball: &Ball,
+    color: u32,
+    aggregate: bool,
+) {
+    if !ball.x.is_finite() || !ball.y.is_finite() {
+        return;
+    }
+    let rgb = [
+        ((color >> 16) & 0xFF) as u8,
+        ((color >> 8) & 0xFF) as u8,
+        (color & 0xFF) as u8,
+    ];
+    let (x0, y0, size) = if ball.radius < 1.0 {
+        (ball.x.floor() as i64, ball.y.floor() as i64, 1)
+    } else {
+        (
+            (ball.x.round() as i64).saturating_sub(1),
+            (ball.y.round() as i64).saturating_sub(1),
+            2,
+        )
+    };
+    let weight = (std::f32::consts::PI * ball.radius * ball.radius).min(1.0);
+    for py in y0.max(clip.y0 as i64)..y0.saturating_add(size).min(clip.y1 as i64) {
+        let row = (py as usize - clip.y0) * stride;
+        for px in x0.max(clip.x0 as i64)..x0.saturating_add(size).min(clip.x1 as i64) {
+            let idx = row + px as usize * 4;
+            if aggregate && size == 1 {
+                for (channel, &value) in buffer[idx..idx + 3].iter_mut().zip(&rgb) {
+                    *channel = channel.saturating_add((value as f32 * weight) as u8);
+                }
+            } else {
+                buffer[idx..idx + 3].copy_from_slice(&rgb);
+            }
+            buffer[idx + 3] = 255;
+        }
+    }
+}
+
+// Fill an axis-aligned ellipse by testing every pixel in its bounding box
+fn fill_ellipse(buffer: &mut [u8], stride: usize, clip: Clip, shape: Shape, color: u32) {
+    let pixel = [
+        ((color >> 16) & 0xFF) as u8,
+        ((color >> 8) & 0xFF) as u8,
+        (color & 0xFF) as u8,
+        255,
+    ];
+    let x_min = ((shape.cx - shape.rx).max(clip.x0 as f32) as i32).max(clip.x0 as i32);
+    let x_max = ((shape.cx + shape.rx).min(clip.x1 as f32) as i32).min(clip.x1 as i32);
+    let y_min = ((shape.cy - shape.ry).max(clip.y0 as f32) as i32).max(clip.y0 as i32);
+    let y_max = ((shape.cy + shape.ry).min(clip.y1 as f32) as i32).min(clip.y1 as i32);
+    let (inv_rx, inv_ry) = (1.0 / shape.rx, 1.0 / shape.ry);
+    for py in y_min..y_max {
+        let row = (py as usize - clip.y0) * stride;
+        let dy = (py as f32 - shape.cy) * inv_ry;
+        for px in x_min..x_max {
+            let dx = (px as f32 - shape.cx) * inv_rx;
+            if dx * dx + dy * dy <= 1.0 {
+                let idx = row + px as usize * 4;
+                buffer[idx..idx + 4].copy_from_slice(&pixel);
+            }
+        }
+    }
+}
+
+// Fill the mask's spans row by row around the ball's pixel-snapped center
+fn blit_mask(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    ball: &Ball,
+    color: u32,
+    mask: &CircleMask,
+) {
+    if !ball.x.is_finite() || !ball.y.is_finite() {
+        return;
+    }
+    let cx = ball.x.round() as i64;
+    let cy = ball.y.round() as i64;
+    let pixel = [
+        ((color >> 16) & 0xFF) as u8,
+        ((color >> 8) & 0xFF) as u8,
+        (color & 0xFF) as u8,
+        255,
+    ];
+    let reach = mask.reach as i64;
+    let y_from = cy.saturating_sub(reach).max(clip.y0 as i64);
+    let y_to = cy.saturating_add(reach + 1).min(clip.y1 as i64);
+    for py in y_from..y_to {
+        let half_width = mask.half_widths[(py - cy + reach) as usize] as i64;
+        if half_width < 0 {
+            continue;
+        }
+        let x_from = cx.saturating_sub(half_width).max(clip.x0 as i64);
+        let x_to = cx.saturating_add(half_width + 1).min(clip.x1 as i64);
+        if x_from >= x_to {
+            continue;
+        }
+        let row = (py as usize - clip.y0) * stride;
+        for out in buffer[row + x_from as usize * 4..row + x_to as usize * 4].chunks_exact_mut(4) {
+            out.copy_from_slice(&pixel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{World, WorldError};
+
+    #[test]
+    fn empty_surfaces_draw_nothing() {
+        let world = World::new(200.0, 150.0, 64, 0.7);
+        assert!(world.render_to_buffer(&mut [], 0, 0, None).is_ok());
+        assert!(world.render_to_buffer(&mut [], 0, 10, None).is_ok());
+        assert!(world.render_to_buffer(&mut [], 10, 0, Some(64)).is_ok());
+    }
+
+    // Sizes whose byte counts overflow are a mismatch, not a panic
+    #[test]
+    fn overflowing_sizes_are_errors() {
+        let world = World::new(200.0, 150.0, 64, 0.7);
+        let mut buffer = vec![0; 200 * 150 * 4];
+        for (width, height, stride) in [
+            (usize::MAX, usize::MAX, None),
+            (usize::MAX / 2, 3, None),
+            (200, 150, Some(usize::MAX)),
+        ] {
+            assert!(matches!(
+                world.render_to_buffer(&mut buffer, width, height, stride),
+                Err(WorldError::BufferSizeMismatch { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn bad_buffers_are_errors() {
+        let world = World::new(200.0, 150.0, 64, 0.7);
+        let mut buffer = vec![0; 200 * 150 * 4];
+        assert!(matches!(
+            world.render_to_buffer(&mut buffer, 200, 150, Some(4)),
+            Err(WorldError::InvalidStride {
+                stride: 4,
+                min: 800
+            })
+        ));
+        assert!(matches!(
+            world.render_to_buffer(&mut buffer[1..], 200, 150, None),
+            Err(WorldError::BufferSizeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+// Starting arrangements. `new` and `new_seeded` begin with the classic single
+// red ball; the `new_empty*` constructors start with no balls at all, and
+// `seed_ball` lays out whatever the demo wants before the first update().
+// `from_preset` builds one of the ready-made scenes in PRESETS.
+
+use std::f32::consts::TAU;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::{sim, validate_config, Ball, Emitter, World, WorldError};
+
+const PRESETS: [&str; 6] = [
+    "classic",
+    "rain",
+    "orbit",
+    "billiards",
+    "fireworks",
+    "galton",
+];
+
+// Names accepted by World::from_preset
+#[wasm_bindgen]
+pub fn preset_names() -> Vec<String> {
+    PRESETS.iter().map(|name| name.to_string()).collect()
+}
+
+#[wasm_bindgen]
+impl World {
+    // Like `try_new`, without the initial ball
+    pub fn new_empty(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+    ) -> Result<World, WorldError> {
+        validate_config(width, height, max_balls, split_ratio)?;
+        Ok(World::empty_with_rng(
+            width,
+            height,
+            max_balls,
+            split_ratio,
+            ChaCha8Rng::from_entropy(),
+            false,
+        ))
+    }
+
+    // Like `try_new_seeded`, without the initial ball
+    pub fn new_empty_seeded(
+        width: f32,
+        height: f32,
+        max_balls: usize,
+        split_ratio: f32,
+        seed: u32,
+    ) -> Result<World, WorldError> {
+        validate_config(width, height, max_balls, split_ratio)?;
+        Ok(World::empty_with_rng(
+            width,
+            height,
+            max_balls,
+            split_ratio,
+            ChaCha8Rng::seed_from_u64(seed as u64),
+            true,
+        ))
+    }
+
+    // Add a ball to the starting arrangement and return its id. Unlike
+    // `add_ball` the ball is checked (finite position and velocity, positive
+    // radius) and a full world is an error rather than None.
+    pub fn seed_ball(
+        &mut self,
+        x: f32,
+        y: f32,
+        vx: f32,
+        vy: f32,
+        radius: f32,
+        color: u32,
+    ) -> Result<u32, WorldError> {
+        let finite = [x, y, vx, vy, radius].iter().all(|value| value.is_finite());
+        if !finite || radius <= 0.0 {
+            return Err(WorldError::InvalidBall);
+        }
+        if self.live_count() >= self.max_balls {
+            return Err(WorldError::WorldFull {
+                max_balls: self.max_balls,
+            });
+        }
+        Ok(self.add_edited_ball(Ball::new(x, y, vx, vy, radius, color & 0xFFFFFF)))
+    }
+
+    // One of the built-in scenes (see preset_names), sized to the canvas.
+    // With a seed the scene plays out the same every time.
+    pub fn from_preset(
+        name: &str,
+        width: f32,
+        height: f32,
+        seed: Option<u32>,
+    ) -> Result<World, WorldError> {
+        if !PRESETS.contains(&name) {
+            return Err(WorldError::UnknownPreset(name.to_string()));
+        }
+        let (max_balls, split_ratio) = match name {
+            "classic" => (10_000, 0.8),
+            "rain" => (4_000, 0.7),
+            "orbit" => (3_000, 0.75),
+            "billiards" => (64, 0.8),
+            "galton" => (2_000, 0.8),
+            _ => (6_000, 0.65),
+        };
+        let mut world = match seed {
+            Some(seed) => World::new_empty_seeded(width, height, max_balls, split_ratio, seed)?,
+            None => World::new_empty(width, height, max_balls, split_ratio)?,
+        };
+        match name {
+            "classic" => world.classic_scene(),
+            "rain" => world.rain_scene(),
+            "orbit" => world.orbit_scene(),
+            "billiards" => world.billiards_scene(),
+            "galton" => world.galton_scene(),
+            _ => world.fireworks_scene(),
+        }
+        Ok(world)
+    }
+}
+
+impl World {
+    fn classic_scene(&mut self) {
+        let (x, y) = (self.width / 2.0, self.height / 2.0);
+        self.insert_ball(Ball::new(x, y, 8.0, -6.0, 60.0, 0xFF4444));
+    }
+
+    // Drops falling from the top edge that shatter on the floor
+    fn rain_scene(&mut self) {
+        self.set_gravity(0.0, 0.2);
+        let mut drops = Emitter::new(self.width / 2.0, 6.0, 0.0, 1.0, 5.0, 0.4);
+        drops.width = self.width - 12.0;
+        drops.jitter = 0.5;
+        drops.random_color = false;
+        drops.color = 0x66AAFF;
+        self.add_emitter(&drops);
+    }
+
+    // A ring of balls in slightly too fast orbits around a central well, so
+    // they swing out to the walls and split
+    fn orbit_scene(&mut self) {
+        let (cx, cy) = (self.width / 2.0, self.height / 2.0);
+        let ring = self.width.min(self.height) * 0.3;
+        let strength = 16.0 * ring; // Circular speed of 4 px/frame at the ring
+        self.add_attractor(cx, cy, strength);
+        let speed = (strength / ring).sqrt() * 1.15;
+        for index in 0..8 {
+            let angle = index as f32 / 8.0 * TAU;
+            let (sin, cos) = angle.sin_cos();
+            let color = self.random_color();
+            self.insert_ball(Ball::new(
+                cx + cos * ring,
+                cy + sin * ring,
+                -sin * speed,
+                cos * speed,
+                10.0,
+                color,
+            ));
+        }
+    }
+
+    // A racked triangle of 15 balls and a cue ball; collisions on, no splitting
+    fn billiards_scene(&mut self) {
+        const COLORS: [u32; 8] = [
+            0xF2C200, 0x1F4FC4, 0xD1281F, 0x5B2A8C, 0xF07A1A, 0x1C7A3A, 0x7A1E1E, 0x111111,
+        ];
+        self.set_splitting(false);
+        self.set_collisions(true);
+        let radius = (self.width.min(self.height) / 40.0).max(2.0);
+        let (apex_x, cy) = (self.width * 0.65, self.height / 2.0);
+        let row_step = radius * 3f32.sqrt();
+        let mut index = 0;
+        for row in 0..5 {
+            for slot in 0..=row {
+                let x = apex_x + row as f32 * row_step;
+                let y = cy + (slot as f32 - row as f32 / 2.0) * radius * 2.0;
+                self.insert_ball(Ball::new(x, y, 0.0, 0.0, radius, COLORS[index % 8]));
+                index += 1;
+            }
+        }
+        self.insert_ball(Ball::new(
+            self.width * 0.25,
+            cy,
+            12.0,
+            0.3,
+            radius,
+            0xFFFFFF,
+        ));
+    }
+
+    // One burst to start with, then rockets launched from the bottom
+    fn fireworks_scene(&mut self) {
+        self.set_gravity(0.0, 0.12);
+        let (cx, cy) = (self.width / 2.0, self.height * 0.4);
+        for index in 0..36 {
+            let angle = index as f32 / 36.0 * TAU;
+            let (sin, cos) = angle.sin_cos();
+            let color = self.random_color();
+            self.insert_ball(Ball::new(cx, cy, cos * 6.0, sin * 6.0, 6.0, color));
+        }
+        let mut rockets = Emitter::new(self.width / 2.0, self.height - 8.0, 0.0, -9.0, 7.0, 0.05);
+        rockets.width = self.width / 2.0;
+        rockets.jitter = 1.5;
+        self.add_emitter(&rockets);
+    }
+
+    // A Galton board: a stream of balls poured through a funnel onto a
+    // board of pegs, draining out through the open floor (bin_counts() tallies
+    // where, for the bell curve)
+    fn galton_scene(&mut self) {
+        self.set_splitting(false);
+        self.set_gravity(0.0, 0.2);
+        self.set_open_walls(sim::WALL_BOTTOM);
+        // Dead bounces, so balls trickle from peg to peg instead of flying off
+        self.set_wall_restitution(0.3);
+        let radius = (self.width.min(self.height) / 120.0).max(2.0);
+        let cx = self.width / 2.0;
+        let mut pour = Emitter::new(cx, radius * 2.0, 0.0, 0.0, radius, 0.2);
+        pour.jitter = 0.3;
+        pour.random_color = false;
+        pour.color = 0xF2C200;
+        self.add_emitter(&pour);
+
+        let (top, depth) = (self.height * 0.06, self.height * 0.12);
+        let gap = radius * 3.0;
+        let width = (self.width * 0.3).max(gap * 2.0);
+        self.add_funnel(cx, top, width, gap, depth);
+
+        // Staggered rows of pegs across the board, the first peg right under
+        // the mouth. The gaps between pegs are a little wider than a ball.
+        let spacing = radius * 4.0;
+        let row_step = spacing * 3f32.sqrt() / 2.0;
+        let columns = ((cx - spacing) / spacing) as i32;
+        let mut y = top + depth + spacing;
+        for row in 0.. {
+            if y > self.height * 0.8 {
+                break;
+            }
+            // Odd rows sit half a gap to the right and have one peg less
+            let (offset, last) = if row % 2 == 0 {
+                (0.0, columns)
+            } else {
+                (spacing / 2.0, columns - 1)
+            };
+            for column in -columns..=last {
+                let x = cx + offset + column as f32 * spacing;
+                self.add_circle_obstacle(x, y, radius * 0.6);
+            }
+            y += row_step;
+        }
+    }
+}
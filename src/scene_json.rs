@@ -0,0 +1,504 @@
+// Declarative scenes (`scene` feature). A scene is a JSON object; only the
+// size is required, everything else has the same default as a fresh World:
+//
+//   {
+//     "width": 800, "height": 600, "max_balls": 5000, "seed": 7,
+//     "gravity": [0, 0.2], "integrator": "verlet", "substeps": 2,
+//     "collisions": false, "wall_friction": 0.3, "magnus": 0.01,
+//     "wall_restitution": 0.9, "mix_rule": "min",
+//     "split": { "enabled": true, "ratio": 0.8, "min_radius": 1, "max_generation": 6,
+//                "direction": "random_cone", "cone_angle": 1.2, "kinematics": "momentum",
+//                "impact_speed": 6, "grow_frames": 4, "child_color": "golden_angle" },
+//     "walls": { "bottom": false, "thickness": 12, "color": "#606060",
+//                "shape": "rounded", "corner_radius": 40 },
+//     "obstacles": [
+//       { "shape": "circle", "x": 400, "y": 300, "radius": 40 },
+//       { "shape": "rect", "x": 100, "y": 450, "width": 200, "height": 20 },
+//       { "shape": "polygon", "points": [[500, 500], [700, 420], [700, 500]] }
+//     ],
+//     "emitters": [{ "x": 400, "y": 10, "width": 600, "vy": 1, "radius": 4, "rate": 0.5 }],
+//     "attractors": [{ "x": 400, "y": 300, "strength": 2000 }],
+//     "water": { "level": 450, "density": 1.5 },
+//     "balls": [{ "x": 400, "y": 300, "vx": 8, "vy": -6, "radius": 60, "color": "#ff4444", "spin": 0.1,
+//                 "restitution": 0.6, "friction": 1 }]
+//   }
+//
+// Walls are solid unless set to false. Colors are 0xRRGGBB numbers or
+// "#rrggbb" strings; emitters without a color spawn random ones. Unknown keys
+// are rejected so typos don't go unnoticed.
+
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    sim, ArenaShape, ChildColor, Emitter, Integrator, MixRule, SplitConfig, SplitDirection, SplitKinematics,
+    World, WorldError,
+};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scene {
+    width: f32,
+    height: f32,
+    #[serde(default = "default_max_balls")]
+    max_balls: usize,
+    seed: Option<u32>,
+    #[serde(default)]
+    gravity: [f32; 2],
+    #[serde(default)]
+    integrator: SceneIntegrator,
+    #[serde(default = "default_substeps")]
+    substeps: u32,
+    #[serde(default)]
+    collisions: bool,
+    #[serde(default)]
+    wall_friction: f32,
+    #[serde(default)]
+    magnus: f32,
+    #[serde(default = "default_one")]
+    wall_restitution: f32,
+    #[serde(default)]
+    mix_rule: SceneMixRule,
+    #[serde(default)]
+    split: SceneSplit,
+    #[serde(default)]
+    walls: SceneWalls,
+    #[serde(default)]
+    obstacles: Vec<SceneObstacle>,
+    #[serde(default)]
+    emitters: Vec<SceneEmitter>,
+    #[serde(default)]
+    attractors: Vec<SceneAttractor>,
+    water: Option<SceneWater>,
+    #[serde(default)]
+    balls: Vec<SceneBall>,
+}
+
+fn default_max_balls() -> usize {
+    10_000
+}
+
+fn default_substeps() -> u32 {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SceneIntegrator {
+    #[default]
+    Euler,
+    SemiImplicitEuler,
+    Verlet,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneSplit {
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default = "SceneSplit::default_ratio")]
+    ratio: f32,
+    #[serde(default = "SceneSplit::default_min_radius")]
+    min_radius: f32,
+    max_generation: Option<u32>,
+    #[serde(default)]
+    direction: SceneSplitDirection,
+    cone_angle: Option<f32>,
+    #[serde(default)]
+    kinematics: SceneSplitKinematics,
+    #[serde(default)]
+    impact_speed: f32,
+    #[serde(default)]
+    grow_frames: u32,
+    #[serde(default)]
+    child_color: SceneChildColor,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SceneChildColor {
+    #[default]
+    Random,
+    GoldenAngle,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SceneSplitKinematics {
+    #[default]
+    Visual,
+    Momentum,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SceneSplitDirection {
+    #[default]
+    Parent,
+    Mirror,
+    Orthogonal,
+    Opposite,
+    RandomCone,
+}
+
+impl SceneSplit {
+    fn default_ratio() -> f32 {
+        0.8
+    }
+
+    fn default_min_radius() -> f32 {
+        1.0
+    }
+}
+
+impl Default for SceneSplit {
+    fn default() -> SceneSplit {
+        SceneSplit {
+            enabled: true,
+            ratio: SceneSplit::default_ratio(),
+            min_radius: SceneSplit::default_min_radius(),
+            max_generation: None,
+            direction: SceneSplitDirection::default(),
+            cone_angle: None,
+            kinematics: SceneSplitKinematics::default(),
+            impact_speed: 0.0,
+            grow_frames: 0,
+            child_color: SceneChildColor::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneWalls {
+    #[serde(default = "default_true")]
+    left: bool,
+    #[serde(default = "default_true")]
+    right: bool,
+    #[serde(default = "default_true")]
+    top: bool,
+    #[serde(default = "default_true")]
+    bottom: bool,
+    #[serde(default)]
+    thickness: f32,
+    color: Option<SceneColor>,
+    #[serde(default)]
+    shape: SceneArenaShape,
+    #[serde(default)]
+    corner_radius: f32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SceneArenaShape {
+    #[default]
+    Rectangle,
+    Rounded,
+    Capsule,
+}
+
+impl Default for SceneWalls {
+    fn default() -> SceneWalls {
+        SceneWalls {
+            left: true,
+            right: true,
+            top: true,
+            bottom: true,
+            thickness: 0.0,
+            color: None,
+            shape: SceneArenaShape::default(),
+            corner_radius: 0.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case", deny_unknown_fields)]
+enum SceneObstacle {
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Polygon {
+        points: Vec<[f32; 2]>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneEmitter {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    vx: f32,
+    #[serde(default)]
+    vy: f32,
+    #[serde(default)]
+    jitter: f32,
+    radius: f32,
+    rate: f32,
+    color: Option<SceneColor>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneAttractor {
+    x: f32,
+    y: f32,
+    strength: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneWater {
+    level: f32,
+    density: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneBall {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    vx: f32,
+    #[serde(default)]
+    vy: f32,
+    radius: f32,
+    color: SceneColor,
+    #[serde(default)]
+    spin: f32,
+    #[serde(default = "default_one")]
+    restitution: f32,
+    #[serde(default = "default_one")]
+    friction: f32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SceneMixRule {
+    #[default]
+    Product,
+    Min,
+    Average,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SceneColor {
+    Rgb(u32),
+    Hex(String),
+}
+
+impl SceneColor {
+    // The reason is the caller's to wrap: timelines share the format
+    pub(crate) fn rgb(&self) -> Result<u32, String> {
+        match self {
+            SceneColor::Rgb(rgb) => Ok(rgb & 0xFFFFFF),
+            SceneColor::Hex(hex) => hex
+                .strip_prefix('#')
+                .filter(|digits| digits.len() == 6)
+                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| format!("bad color {hex:?}")),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Build a world from a JSON scene (format at the top of scene_json.rs).
+    // Throws in JS if the JSON is malformed or describes an invalid world.
+    pub fn from_scene_json(json: &str) -> Result<World, WorldError> {
+        let scene: Scene = serde_json::from_str(json)
+            .map_err(|error| WorldError::InvalidScene(error.to_string()))?;
+
+        let (width, height, max_balls, ratio) = (
+            scene.width,
+            scene.height,
+            scene.max_balls,
+            scene.split.ratio,
+        );
+        let mut world = match scene.seed {
+            Some(seed) => World::new_empty_seeded(width, height, max_balls, ratio, seed)?,
+            None => World::new_empty(width, height, max_balls, ratio)?,
+        };
+
+        let [gx, gy] = scene.gravity;
+        if !(gx.is_finite() && gy.is_finite()) {
+            return Err(WorldError::InvalidScene(
+                "gravity must be finite".to_string(),
+            ));
+        }
+        world.set_gravity(gx, gy);
+        world.set_integrator(match scene.integrator {
+            SceneIntegrator::Euler => Integrator::Euler,
+            SceneIntegrator::SemiImplicitEuler => Integrator::SemiImplicitEuler,
+            SceneIntegrator::Verlet => Integrator::Verlet,
+        });
+        world.set_substeps(scene.substeps);
+        world.set_collisions(scene.collisions);
+        world.set_wall_friction(scene.wall_friction);
+        world.set_magnus(scene.magnus);
+        world.set_wall_restitution(scene.wall_restitution);
+        world.set_mix_rule(match scene.mix_rule {
+            SceneMixRule::Product => MixRule::Product,
+            SceneMixRule::Min => MixRule::Min,
+            SceneMixRule::Average => MixRule::Average,
+        });
+        world.set_splitting(scene.split.enabled);
+        world.set_min_radius(scene.split.min_radius)?;
+        world.set_max_generation(scene.split.max_generation.unwrap_or(u32::MAX));
+        let default_split = SplitConfig::default();
+        let cone_angle = scene.split.cone_angle.unwrap_or(default_split.cone_angle);
+        if !cone_angle.is_finite() {
+            return Err(WorldError::InvalidScene(
+                "split.cone_angle must be finite".to_string(),
+            ));
+        }
+        world.set_split_config(SplitConfig {
+            direction: match scene.split.direction {
+                SceneSplitDirection::Parent => SplitDirection::Parent,
+                SceneSplitDirection::Mirror => SplitDirection::Mirror,
+                SceneSplitDirection::Orthogonal => SplitDirection::Orthogonal,
+                SceneSplitDirection::Opposite => SplitDirection::Opposite,
+                SceneSplitDirection::RandomCone => SplitDirection::RandomCone,
+            },
+            cone_angle,
+            kinematics: match scene.split.kinematics {
+                SceneSplitKinematics::Visual => SplitKinematics::Visual,
+                SceneSplitKinematics::Momentum => SplitKinematics::Momentum,
+            },
+            grow_frames: scene.split.grow_frames,
+            child_color: match scene.split.child_color {
+                SceneChildColor::Random => ChildColor::Random,
+                SceneChildColor::GoldenAngle => ChildColor::GoldenAngle,
+            },
+        });
+
+        world.set_impact_splitting(scene.split.impact_speed);
+
+        let walls = &scene.walls;
+        let open = [
+            (walls.left, sim::WALL_LEFT),
+            (walls.right, sim::WALL_RIGHT),
+            (walls.top, sim::WALL_TOP),
+            (walls.bottom, sim::WALL_BOTTOM),
+        ];
+        world.set_open_walls(
+            open.iter()
+                .filter(|(solid, _)| !solid)
+                .fold(0, |mask, (_, wall)| mask | wall),
+        );
+        let wall_color = match &walls.color {
+            Some(color) => color.rgb().map_err(WorldError::InvalidScene)?,
+            None => world.wall_color(),
+        };
+        world.set_wall_thickness(walls.thickness, wall_color);
+        let shape = match walls.shape {
+            SceneArenaShape::Rectangle => ArenaShape::Rectangle,
+            SceneArenaShape::Rounded => ArenaShape::Rounded,
+            SceneArenaShape::Capsule => ArenaShape::Capsule,
+        };
+        world.set_arena_shape(shape, walls.corner_radius);
+
+        for (index, obstacle) in scene.obstacles.iter().enumerate() {
+            let sized = "finite coordinates and a positive size";
+            let (added, needs) = match obstacle {
+                &SceneObstacle::Circle { x, y, radius } => {
+                    (world.add_circle_obstacle(x, y, radius), sized)
+                }
+                &SceneObstacle::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => (world.add_rect_obstacle(x, y, width, height), sized),
+                SceneObstacle::Polygon { points } => (
+                    world.add_polygon_obstacle(points.concat()),
+                    "3 to 16 finite points making a convex polygon",
+                ),
+            };
+            if added.is_none() {
+                return Err(WorldError::InvalidScene(format!(
+                    "obstacle {index} needs {needs}"
+                )));
+            }
+        }
+
+        for emitter in &scene.emitters {
+            let mut added = Emitter::new(
+                emitter.x,
+                emitter.y,
+                emitter.vx,
+                emitter.vy,
+                emitter.radius,
+                emitter.rate,
+            );
+            added.width = emitter.width;
+            added.jitter = emitter.jitter;
+            if let Some(color) = &emitter.color {
+                added.color = color.rgb().map_err(WorldError::InvalidScene)?;
+                added.random_color = false;
+            }
+            world.add_emitter(&added);
+        }
+
+        for (index, attractor) in scene.attractors.iter().enumerate() {
+            if world
+                .add_attractor(attractor.x, attractor.y, attractor.strength)
+                .is_none()
+            {
+                return Err(WorldError::InvalidScene(format!(
+                    "attractor {index} needs finite values"
+                )));
+            }
+        }
+
+        if let Some(water) = &scene.water {
+            if !(water.level.is_finite() && water.density.is_finite()) {
+                return Err(WorldError::InvalidScene(
+                    "water needs finite values".to_string(),
+                ));
+            }
+            world.set_water_level(water.level, water.density);
+        }
+
+        for ball in &scene.balls {
+            if !ball.spin.is_finite() {
+                return Err(WorldError::InvalidScene("spin must be finite".to_string()));
+            }
+            let id = world.seed_ball(
+                ball.x,
+                ball.y,
+                ball.vx,
+                ball.vy,
+                ball.radius,
+                ball.color.rgb().map_err(WorldError::InvalidScene)?,
+            )?;
+            world.balls[id as usize].spin = ball.spin;
+            if !world.set_material(id, ball.restitution, ball.friction) {
+                return Err(WorldError::InvalidScene(
+                    "restitution and friction must be finite".to_string(),
+                ));
+            }
+        }
+        Ok(world)
+    }
+}
@@ -0,0 +1,305 @@
+// Scheduled actions, for choreographing a run ahead of time: each queued
+// action fires at the start of the update() that produces its frame, before
+// emitters and shockwaves, so a seeded world with the same queue replays the
+// same show every time. Actions queued for the same frame fire in the order
+// they were queued. Spawned balls are part of the run like an emitter's,
+// not edits that undo() takes back.
+//
+// A wind gust is an extra force applied like apply_global_force() on each of
+// its frames. Gusts are looked up from the queue each frame rather than
+// started once, and nothing is consumed when an action fires: rewinding to a
+// checkpoint replays whatever was queued for the frames after it.
+
+use wasm_bindgen::prelude::*;
+
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::{sim, Ball, World};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Gravity {
+        x: f32,
+        y: f32,
+    },
+    Wind {
+        fx: f32,
+        fy: f32,
+        frames: u32,
+    },
+    Shockwave {
+        x: f32,
+        y: f32,
+        radius: f32,
+        strength: f32,
+    },
+    Spawn {
+        x: f32,
+        y: f32,
+        vx: f32,
+        vy: f32,
+        radius: f32,
+        color: u32,
+    },
+    Camera {
+        x: f32,
+        y: f32,
+        zoom: f32,
+        frames: u32,
+    },
+    Splitting(bool),
+    Collisions(bool),
+}
+
+impl StateHash for Kind {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        match *self {
+            Kind::Gravity { x, y } => {
+                hash.write_u32(0);
+                hash.write(&(x, y));
+            }
+            Kind::Wind { fx, fy, frames } => {
+                hash.write_u32(1);
+                hash.write(&(fx, fy));
+                hash.write_u32(frames);
+            }
+            Kind::Shockwave {
+                x,
+                y,
+                radius,
+                strength,
+            } => {
+                hash.write_u32(2);
+                hash.write(&(x, y));
+                hash.write(&(radius, strength));
+            }
+            Kind::Spawn {
+                x,
+                y,
+                vx,
+                vy,
+                radius,
+                color,
+            } => {
+                hash.write_u32(3);
+                hash.write(&(x, y));
+                hash.write(&(vx, vy));
+                hash.write_f32(radius);
+                hash.write_u32(color);
+            }
+            Kind::Camera { x, y, zoom, frames } => {
+                hash.write_u32(4);
+                hash.write(&(x, y));
+                hash.write_f32(zoom);
+                hash.write_u32(frames);
+            }
+            Kind::Splitting(enabled) => {
+                hash.write_u32(5);
+                hash.write(&enabled);
+            }
+            Kind::Collisions(enabled) => {
+                hash.write_u32(6);
+                hash.write(&enabled);
+            }
+        }
+    }
+}
+
+// Something for the schedule to do, built with one of the constructors below
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Action {
+    kind: Kind,
+}
+
+#[wasm_bindgen]
+impl Action {
+    // set_gravity(x, y)
+    pub fn gravity(x: f32, y: f32) -> Action {
+        Action {
+            kind: Kind::Gravity { x, y },
+        }
+    }
+
+    // apply_global_force(fx, fy) on each of `frames` frames, starting with its own
+    pub fn wind(fx: f32, fy: f32, frames: u32) -> Action {
+        Action {
+            kind: Kind::Wind { fx, fy, frames },
+        }
+    }
+
+    // shockwave(x, y, radius, strength)
+    pub fn shockwave(x: f32, y: f32, radius: f32, strength: f32) -> Action {
+        Action {
+            kind: Kind::Shockwave {
+                x,
+                y,
+                radius,
+                strength,
+            },
+        }
+    }
+
+    // A new ball, like add_ball() would make it (skipped while the world is full)
+    pub fn spawn(x: f32, y: f32, vx: f32, vy: f32, radius: f32, color: u32) -> Action {
+        Action {
+            kind: Kind::Spawn {
+                x,
+                y,
+                vx,
+                vy,
+                radius,
+                color,
+            },
+        }
+    }
+
+    // move_camera(x, y, zoom, frames)
+    pub fn camera(x: f32, y: f32, zoom: f32, frames: u32) -> Action {
+        Action {
+            kind: Kind::Camera { x, y, zoom, frames },
+        }
+    }
+
+    // set_splitting(enabled)
+    pub fn splitting(enabled: bool) -> Action {
+        Action {
+            kind: Kind::Splitting(enabled),
+        }
+    }
+
+    // set_collisions(enabled)
+    pub fn collisions(enabled: bool) -> Action {
+        Action {
+            kind: Kind::Collisions(enabled),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Queued {
+    frame: u32,
+    action: Action,
+}
+
+impl StateHash for Queued {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write_u32(self.frame);
+        hash.write(&self.action.kind);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Schedule {
+    queued: Vec<Queued>, // Sorted by frame, then by when they were queued
+}
+
+impl StateHash for Schedule {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&self.queued);
+    }
+}
+
+impl Schedule {
+    fn push(&mut self, frame: u32, action: Action) {
+        let at = self.queued.partition_point(|queued| queued.frame <= frame);
+        self.queued.insert(at, Queued { frame, action });
+    }
+
+    // Force of the gusts blowing during `frame`
+    fn wind(&self, frame: u32) -> (f32, f32) {
+        let started = self.queued.partition_point(|queued| queued.frame <= frame);
+        let mut force = (0.0, 0.0);
+        for queued in &self.queued[..started] {
+            if let Kind::Wind { fx, fy, frames } = queued.action.kind {
+                if frame - queued.frame < frames {
+                    force.0 += fx;
+                    force.1 += fy;
+                }
+            }
+        }
+        force
+    }
+}
+
+impl World {
+    // Fire the actions for `stamp`, the frame this update() produces
+    pub(crate) fn run_schedule(&mut self, stamp: u32) {
+        if self.schedule.queued.is_empty() {
+            return;
+        }
+        let first = self
+            .schedule
+            .queued
+            .partition_point(|queued| queued.frame < stamp);
+        let last = self
+            .schedule
+            .queued
+            .partition_point(|queued| queued.frame <= stamp);
+        for index in first..last {
+            match self.schedule.queued[index].action.kind {
+                Kind::Gravity { x, y } => self.set_gravity(x, y),
+                // Picked up below with the gusts still blowing
+                Kind::Wind { .. } => {}
+                Kind::Shockwave {
+                    x,
+                    y,
+                    radius,
+                    strength,
+                } => {
+                    self.shockwave(x, y, radius, strength);
+                }
+                Kind::Spawn {
+                    x,
+                    y,
+                    vx,
+                    vy,
+                    radius,
+                    color,
+                } => {
+                    if self.live_count() < self.max_balls {
+                        let mut ball = Ball::new(x, y, vx, vy, radius, color & 0xFFFFFF);
+                        sim::sanitize_ball(&mut ball, self.width, self.height);
+                        self.energy.record_emitted(&ball);
+                        self.insert_child(ball, stamp);
+                    }
+                }
+                Kind::Camera { x, y, zoom, frames } => self.move_camera(x, y, zoom, frames),
+                Kind::Splitting(enabled) => self.set_splitting(enabled),
+                Kind::Collisions(enabled) => self.set_collisions(enabled),
+            }
+        }
+        let (fx, fy) = self.schedule.wind(stamp);
+        if fx != 0.0 || fy != 0.0 {
+            self.apply_global_force(fx, fy);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Fire `action` at the start of the update() that produces frame
+    // `at_frame` (the next one is frame() + 1). Ignored (false) for frames
+    // already simulated.
+    pub fn queue_action(&mut self, at_frame: u32, action: &Action) -> bool {
+        if at_frame <= self.frame {
+            return false;
+        }
+        self.schedule.push(at_frame, *action);
+        true
+    }
+
+    // Blow with an extra (fx, fy) pixels/frame^2 for `duration` frames from
+    // `at_frame` on; shorthand for queue_action(at_frame, Action.wind(...))
+    pub fn queue_wind_gust(&mut self, at_frame: u32, duration: u32, fx: f32, fy: f32) -> bool {
+        self.queue_action(at_frame, &Action::wind(fx, fy, duration))
+    }
+
+    // Actions queued, including those that already fired
+    pub fn scheduled_count(&self) -> usize {
+        self.schedule.queued.len()
+    }
+
+    pub fn clear_schedule(&mut self) {
+        self.schedule.queued.clear();
+    }
+}
@@ -0,0 +1,101 @@
+// Live retuning of the construction-time configuration, e.g. from sliders in
+// a host UI. Setters validate like `try_new` and leave the world unchanged on
+// error. Changes take effect on the next update().
+
+use wasm_bindgen::prelude::*;
+
+use crate::{validate_config, SplitConfig, World, WorldError};
+
+#[wasm_bindgen]
+impl World {
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    // Resize the arena. Balls left outside are pushed back in by the next update().
+    pub fn set_size(&mut self, width: f32, height: f32) -> Result<(), WorldError> {
+        validate_config(width, height, self.max_balls, self.split_ratio)?;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    pub fn max_balls(&self) -> usize {
+        self.max_balls
+    }
+
+    // Lowering the cap below live_count() removes nothing; splits and
+    // emitters just wait until there is room again.
+    pub fn set_max_balls(&mut self, max_balls: usize) -> Result<(), WorldError> {
+        validate_config(self.width, self.height, max_balls, self.split_ratio)?;
+        self.max_balls = max_balls;
+        Ok(())
+    }
+
+    pub fn split_ratio(&self) -> f32 {
+        self.split_ratio
+    }
+
+    pub fn set_split_ratio(&mut self, split_ratio: f32) -> Result<(), WorldError> {
+        validate_config(self.width, self.height, self.max_balls, split_ratio)?;
+        self.split_ratio = split_ratio;
+        Ok(())
+    }
+
+    pub fn max_generation(&self) -> u32 {
+        self.max_generation
+    }
+
+    // Balls that have been through this many splits stop splitting
+    // (u32::MAX, the default, means no limit)
+    pub fn set_max_generation(&mut self, max_generation: u32) {
+        self.max_generation = max_generation;
+    }
+
+    pub fn max_splits_per_frame(&self) -> Option<u32> {
+        self.max_splits_per_frame
+    }
+
+    // Limit how many balls may split in one update() (None = no limit), so a
+    // frame where many balls hit the walls at once doesn't double the
+    // population in one go. Past the limit a ball bounces without splitting,
+    // as on a full world, and splits on a later impact instead.
+    pub fn set_max_splits_per_frame(&mut self, max_splits: Option<u32>) {
+        self.max_splits_per_frame = max_splits;
+    }
+
+    pub fn min_radius(&self) -> f32 {
+        self.min_radius
+    }
+
+    pub fn split_config(&self) -> SplitConfig {
+        self.split
+    }
+
+    // How split children are launched (see SplitDirection). A non-finite
+    // cone angle keeps the current one; others are clamped to 0..=2 pi.
+    pub fn set_split_config(&mut self, config: SplitConfig) {
+        let cone_angle = if config.cone_angle.is_finite() {
+            config.cone_angle.clamp(0.0, std::f32::consts::TAU)
+        } else {
+            self.split.cone_angle
+        };
+        self.split = SplitConfig {
+            cone_angle,
+            ..config
+        };
+    }
+
+    // Balls stop splitting once a split would make them smaller than this (1 px by default)
+    pub fn set_min_radius(&mut self, min_radius: f32) -> Result<(), WorldError> {
+        if !(min_radius > 0.0 && min_radius.is_finite()) {
+            return Err(WorldError::InvalidMinRadius(min_radius));
+        }
+        self.min_radius = min_radius;
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+// Camera shake: a render-only jolt. camera_shake() moves the picture by up to
+// `intensity` pixels on each axis, a new random offset every frame, dying down
+// quadratically over `duration` frames. The offsets come from a small RNG
+// seeded with the frame number rather than the world's, so shaking never
+// changes how a run unfolds and every render of a frame shows the same offset.
+//
+// With an impact speed set, a ball bouncing off a wall, or two balls
+// colliding, at least that fast along the contact normal starts a shake by
+// itself. A weaker shake never cuts a stronger one short.
+
+use wasm_bindgen::prelude::*;
+
+use crate::sim::{self, SimRng, SmallRng};
+use crate::{Ball, World};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Shake {
+    intensity: f32,
+    start: u32, // Frame the shake started in
+    duration: u32,
+    impact_speed: f32, // 0 = impacts don't shake
+    impact_intensity: f32,
+    impact_duration: u32,
+}
+
+impl Shake {
+    fn amplitude(&self, frame: u32) -> f32 {
+        let elapsed = frame.wrapping_sub(self.start);
+        if elapsed >= self.duration {
+            return 0.0;
+        }
+        let left = 1.0 - elapsed as f32 / self.duration as f32;
+        self.intensity * left * left
+    }
+
+    // Screen offset of the picture at `frame`, in pixels
+    pub(crate) fn offset(&self, frame: u32) -> (f32, f32) {
+        let amplitude = self.amplitude(frame);
+        if amplitude <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let mut rng = SmallRng::new(frame as u64);
+        let dx = (rng.next_f32() * 2.0 - 1.0) * amplitude;
+        let dy = (rng.next_f32() * 2.0 - 1.0) * amplitude;
+        (dx, dy)
+    }
+
+    fn start(&mut self, frame: u32, intensity: f32, duration: u32) {
+        if intensity >= self.amplitude(frame) {
+            self.intensity = intensity;
+            self.start = frame;
+            self.duration = duration;
+        }
+    }
+
+    fn impact(&mut self, frame: u32, speed: f32) {
+        if self.impact_speed > 0.0 && speed >= self.impact_speed {
+            self.start(frame, self.impact_intensity, self.impact_duration);
+        }
+    }
+
+    // A wall bounce of `ball`, after its velocity was reflected
+    pub(crate) fn record_wall(&mut self, frame: u32, ball: &Ball, hits: sim::WallHits) {
+        let x = if hits.x { ball.vx.abs() } else { 0.0 };
+        let y = if hits.y { ball.vy.abs() } else { 0.0 };
+        self.impact(frame, x.max(y));
+    }
+
+    // A collision between two balls closing at `speed`
+    pub(crate) fn record_collision(&mut self, frame: u32, speed: f32) {
+        self.impact(frame, speed);
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Shake the picture by up to `intensity` pixels, fading out over
+    // `duration` frames. Physics is unaffected.
+    pub fn camera_shake(&mut self, intensity: f32, duration: u32) {
+        if intensity.is_finite() {
+            self.shake.start(self.frame, intensity.max(0.0), duration);
+        }
+    }
+
+    // Shake by `intensity` pixels for `duration` frames whenever a wall
+    // bounce or collision is at least `speed` pixels/frame fast (0, the
+    // default, turns it off)
+    pub fn set_impact_shake(&mut self, speed: f32, intensity: f32, duration: u32) {
+        if !(speed.is_finite() && intensity.is_finite()) {
+            return;
+        }
+        self.shake.impact_speed = speed.max(0.0);
+        self.shake.impact_intensity = intensity.max(0.0);
+        self.shake.impact_duration = duration;
+    }
+
+    pub fn impact_shake_speed(&self) -> f32 {
+        self.shake.impact_speed
+    }
+
+    // The current offset, for hosts drawing with their own renderer
+    pub fn shake_x(&self) -> f32 {
+        self.shake.offset(self.frame).0
+    }
+
+    pub fn shake_y(&self) -> f32 {
+        self.shake.offset(self.frame).1
+    }
+}
@@ -0,0 +1,198 @@
+// Expanding shockwaves for "click to detonate" demos. shockwave() starts a
+// ring at (x, y) that grows by SPEED pixels per frame up to its radius; each
+// ball the front passes is kicked outward by strength * (1 - d / radius)
+// pixels/frame. A ball right at the center has no outward direction and is
+// left alone.
+//
+// With a split energy set, a ball whose kick carries at least that much
+// kinetic energy (0.5 * r^2 * kick^2, so big balls go first) also splits,
+// pushing off along the wave like off a wall, with the usual split rules. Each
+// such split is reported as a Shockwave event. With a chain share, every split
+// also detonates a new wave at the ball, its radius and strength scaled by
+// the share, so one click ripples outward through a crowd and dies down.
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::{self, Event, EventKind};
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::World;
+
+// How fast the front expands, in pixels per frame
+const SPEED: f32 = 12.0;
+// Chained waves smaller than this are not started
+const MIN_RADIUS: f32 = 1.0;
+const MAX_WAVES: usize = 1024;
+// Upper bound on the chain share, so cascades always die out
+const MAX_CHAIN: f32 = 0.95;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Wave {
+    x: f32,
+    y: f32,
+    radius: f32,
+    strength: f32,
+    front: f32, // Distance already swept
+}
+
+impl StateHash for Wave {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        for value in [self.x, self.y, self.radius, self.strength, self.front] {
+            hash.write_f32(value);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Shockwaves {
+    waves: Vec<Wave>,
+    split_energy: f32, // 0 = shockwaves never split
+    chain: f32,
+}
+
+impl StateHash for Shockwaves {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&self.waves);
+        hash.write(&(self.split_energy, self.chain));
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Start a shockwave (see shockwave.rs). Ignored (false) unless every
+    // argument is finite and the radius positive; a negative strength pulls
+    // balls in instead.
+    pub fn shockwave(&mut self, x: f32, y: f32, radius: f32, strength: f32) -> bool {
+        if !(x.is_finite() && y.is_finite() && radius.is_finite() && strength.is_finite())
+            || radius <= 0.0
+        {
+            return false;
+        }
+        self.shockwaves.start(x, y, radius, strength)
+    }
+
+    // Kick energy at which a shockwave splits a ball (0, the default, = never)
+    pub fn set_shockwave_split_energy(&mut self, energy: f32) {
+        if energy.is_finite() {
+            self.shockwaves.split_energy = energy.max(0.0);
+        }
+    }
+
+    pub fn shockwave_split_energy(&self) -> f32 {
+        self.shockwaves.split_energy
+    }
+
+    // Share (0..=0.95, default 0) of radius and strength passed on to the
+    // wave a shockwave split detonates
+    pub fn set_shockwave_chain(&mut self, share: f32) {
+        if share.is_finite() {
+            self.shockwaves.chain = share.clamp(0.0, MAX_CHAIN);
+        }
+    }
+
+    pub fn shockwave_chain(&self) -> f32 {
+        self.shockwaves.chain
+    }
+
+    pub fn shockwave_count(&self) -> usize {
+        self.shockwaves.waves.len()
+    }
+
+    // Active waves as x, y, front, radius quadruples, for drawing the rings
+    pub fn shockwaves(&self) -> Vec<f32> {
+        self.shockwaves
+            .waves
+            .iter()
+            .flat_map(|wave| [wave.x, wave.y, wave.front, wave.radius])
+            .collect()
+    }
+
+    pub fn clear_shockwaves(&mut self) {
+        self.shockwaves.waves.clear();
+    }
+}
+
+impl Shockwaves {
+    fn start(&mut self, x: f32, y: f32, radius: f32, strength: f32) -> bool {
+        if self.waves.len() >= MAX_WAVES {
+            return false;
+        }
+        self.waves.push(Wave {
+            x,
+            y,
+            radius,
+            strength,
+            front: 0.0,
+        });
+        true
+    }
+}
+
+impl World {
+    // Called once per update(), before the balls move. Waves detonated by
+    // splits this frame start expanding on the next one.
+    pub(crate) fn propagate_shockwaves(&mut self, stamp: u32) {
+        if self.shockwaves.waves.is_empty() {
+            return;
+        }
+        let mut waves = std::mem::take(&mut self.shockwaves.waves);
+        let split_energy = self.shockwaves.split_energy;
+        // Balls to split as (id, outward normal), and per ball its kick
+        // energy and the radius and strength of the wave that kicked it
+        let mut impacts = Vec::new();
+        let mut sources = Vec::new();
+
+        for wave in &mut waves {
+            let inner = wave.front;
+            wave.front = (wave.front + SPEED).min(wave.radius);
+            let balls = self.balls.iter_mut().zip(self.modified.iter_mut());
+            for (id, (ball, modified)) in balls.enumerate() {
+                if ball.alive == 0 {
+                    continue;
+                }
+                let (dx, dy) = (ball.x - wave.x, ball.y - wave.y);
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance == 0.0 || distance < inner || distance >= wave.front {
+                    continue;
+                }
+                let (nx, ny) = (dx / distance, dy / distance);
+                let kick = wave.strength * (1.0 - distance / wave.radius);
+                ball.vx += kick * nx;
+                ball.vy += kick * ny;
+                *modified = stamp;
+
+                let energy = 0.5 * ball.radius * ball.radius * kick * kick;
+                if split_energy > 0.0 && energy >= split_energy {
+                    impacts.push((id, (nx, ny)));
+                    sources.push((energy, wave.radius, wave.strength));
+                }
+            }
+        }
+        waves.retain(|wave| wave.front < wave.radius);
+        self.shockwaves.waves = waves;
+        if impacts.is_empty() {
+            return;
+        }
+
+        let chain = self.shockwaves.chain;
+        for index in self.split_impacts(&impacts, stamp) {
+            let id = impacts[index].0;
+            let (energy, radius, strength) = sources[index];
+            let ball = self.balls[id];
+            events::push_event(
+                &mut self.events,
+                Event {
+                    kind: EventKind::Shockwave,
+                    id: id as u32,
+                    frame: stamp,
+                    x: ball.x,
+                    y: ball.y,
+                    value: energy,
+                },
+            );
+            if chain > 0.0 && radius * chain >= MIN_RADIUS {
+                self.shockwaves
+                    .start(ball.x, ball.y, radius * chain, strength * chain);
+            }
+        }
+    }
+}
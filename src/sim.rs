@@ -0,0 +1,939 @@
+// The simulation core: ball physics on plain data, using only `core` + `alloc`.
+//
+// Nothing here touches wasm_bindgen, the OS or std, so the same physics runs
+// on no_std targets, e.g. an RP2040 driving an LED matrix:
+// `cargo build --no-default-features --target thumbv6m-none-eabi`. Without a
+// no_std `--target` the build fails: the cdylib crate type needs a global
+// allocator and a panic handler, which only the final firmware provides
+// (bare-metal targets drop the cdylib). World::update runs the same steps as
+// `advance` (advance_motion, then split_ball) and adds slot reuse, change
+// stamps and events on top.
+
+use alloc::vec::Vec;
+
+use crate::Ball;
+
+pub const SANITIZED_POSITION: u32 = 1;
+pub const SANITIZED_VELOCITY: u32 = 2;
+pub const SANITIZED_RADIUS: u32 = 4;
+
+// Arena walls, for SimConfig::open_walls
+pub const WALL_LEFT: u32 = 1;
+pub const WALL_RIGHT: u32 = 2;
+pub const WALL_TOP: u32 = 4;
+pub const WALL_BOTTOM: u32 = 8;
+
+// The renderer's flat clear color (0xRRGGBB), and SimConfig's default background
+pub const CLEAR_COLOR: u32 = 0x1A1A1A;
+
+// The golden-angle palette (ChildColor::GoldenAngle and colors.rs):
+// consecutive hues 137.5 degrees apart, at one saturation and value
+pub(crate) const GOLDEN_ANGLE_DEGREES: f32 = 137.507_76;
+pub(crate) const PALETTE_SATURATION: f32 = 0.65;
+pub(crate) const PALETTE_VALUE: f32 = 0.95;
+
+// Source of randomness for splits. World uses its ChaCha8Rng; embedded hosts
+// can use SmallRng or wrap a hardware RNG.
+pub trait SimRng {
+    fn next_u32(&mut self) -> u32;
+
+    // Uniform in [0, 1), derived exactly like rand's `gen::<f32>()`
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+}
+
+// PCG32 (XSH RR): tiny, seedable and good enough for split jitter and colors
+#[derive(Clone, Debug)]
+pub struct SmallRng {
+    state: u64,
+    inc: u64,
+}
+
+impl SmallRng {
+    pub fn new(seed: u64) -> SmallRng {
+        let mut rng = SmallRng {
+            state: 0,
+            inc: (0xDA3E_39CB_94B9_5BDB << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+}
+
+impl SimRng for SmallRng {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+// How velocities and positions are advanced each frame
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Integrator {
+    // x += v, then v += a (the original step)
+    #[default]
+    Euler = 0,
+    // v += a, then x += v
+    SemiImplicitEuler = 1,
+    // x += (x - x_prev) + a, with x_prev = x - v. Afterwards the velocity is
+    // re-derived from the positions, so position corrections feed into it.
+    Verlet = 2,
+}
+
+// Which way a split child leaves, relative to its parent's velocity after the bounce
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitDirection {
+    // Roughly the parent's direction, with a little jitter along the wall (the original)
+    #[default]
+    Parent = 0,
+    // Reflected along the wall (or the contact tangent of a ball-ball
+    // impact): parent and child fan out in a V. Nothing to mirror in a
+    // corner, where the child follows the parent.
+    Mirror = 1,
+    // Perpendicular to the parent, turned away from the wall or the other ball
+    Orthogonal = 2,
+    // Straight back the other way
+    Opposite = 3,
+    // The parent's direction turned by a random angle within the cone
+    RandomCone = 4,
+}
+
+// What happens to the parent's momentum in a split
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitKinematics {
+    // The parent keeps its velocity and the child gets 0.8..1.2 of its speed
+    // (the original): every split adds a near-full-speed ball
+    #[default]
+    Visual = 0,
+    // Parent and child each take half the parent's mass and share its
+    // momentum: their center of mass keeps the parent's velocity, and they
+    // move off it by plus and minus half the kick the Visual child would get
+    Momentum = 1,
+}
+
+// What color a split child gets
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChildColor {
+    // Any 24-bit color (the original)
+    #[default]
+    Random = 0,
+    // The parent's hue turned by the golden angle once per generation the
+    // child is in, at the auto-color palette's saturation and value: no
+    // randomness, and consecutive generations stay far apart on the wheel
+    GoldenAngle = 1,
+}
+
+// How a ball's restitution or friction is combined with the wall's (or, for
+// restitution in a collision, the other ball's)
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MixRule {
+    // a * b: with the default 1 per ball, the wall's coefficient as is
+    #[default]
+    Product = 0,
+    // The lower one: the softer or slicker side wins
+    Min = 1,
+    // (a + b) / 2
+    Average = 2,
+}
+
+impl MixRule {
+    pub fn mix(self, a: f32, b: f32) -> f32 {
+        match self {
+            MixRule::Product => a * b,
+            MixRule::Min => a.min(b),
+            MixRule::Average => (a + b) * 0.5,
+        }
+    }
+}
+
+// How split children are launched
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SplitConfig {
+    pub direction: SplitDirection,
+    pub cone_angle: f32, // Full width of the RandomCone, in radians (0..=2 pi)
+    pub kinematics: SplitKinematics,
+    pub grow_frames: u32, // Children ease in to their radius on screen over this many frames (0 = at once)
+    pub child_color: ChildColor,
+}
+
+#[cfg_attr(feature = "std", wasm_bindgen::prelude::wasm_bindgen)]
+impl SplitConfig {
+    #[cfg_attr(feature = "std", wasm_bindgen(constructor))]
+    pub fn new(direction: SplitDirection, cone_angle: f32) -> SplitConfig {
+        SplitConfig {
+            direction,
+            cone_angle,
+            kinematics: SplitKinematics::Visual,
+            grow_frames: 0,
+            child_color: ChildColor::Random,
+        }
+    }
+}
+
+impl Default for SplitConfig {
+    fn default() -> SplitConfig {
+        SplitConfig::new(SplitDirection::Parent, core::f32::consts::FRAC_PI_2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SimConfig {
+    pub width: f32,
+    pub height: f32,
+    pub max_balls: usize,
+    pub split_ratio: f32,
+    pub split: SplitConfig,
+    pub integrator: Integrator,
+    pub gravity_x: f32, // Acceleration in pixels/frame^2
+    pub gravity_y: f32,
+    pub dt: f32,                // Fraction of a frame per step (1 / substeps)
+    pub splitting: bool,        // false: balls bounce off the walls without splitting
+    pub open_walls: u32,        // WALL_* mask of walls balls pass through instead of bouncing
+    pub min_radius: f32,        // Smallest radius a split may produce
+    pub max_generation: u32,    // Balls of this generation no longer split
+    pub wall_friction: f32,     // 0..=1: share of a contact's slip removed (see wall_friction)
+    pub magnus: f32,            // Magnus lift: acceleration = magnus * spin x velocity
+    pub heating: f32,           // Temperature gained per unit of speed into a wall (0 = off)
+    pub cooling: f32,           // Share of its temperature a ball loses per frame
+    pub split_temperature: f32, // Colder balls split with probability temperature / this (0 = always split)
+    pub wall_inset: f32,        // Closed walls stand this far inside the bounds (their visible thickness)
+    pub corner_radius: f32,     // Radius of the rounded corners between closed walls (0 = square)
+    pub wall_restitution: f32,  // 0..=1: share of its speed a ball keeps off a wall (1 = elastic)
+    pub mix_rule: MixRule,      // Combines the coefficients above with each ball's
+    pub background: u32,        // 0xRRGGBB random child colors must stand out from
+    pub min_contrast: f32,      // Contrast ratio they need against it (1 = any color, see random_color)
+}
+
+impl SimConfig {
+    // Euler integration without gravity, one step per frame, splitting on: like a fresh World
+    pub fn new(width: f32, height: f32, max_balls: usize, split_ratio: f32) -> SimConfig {
+        SimConfig {
+            width,
+            height,
+            max_balls,
+            split_ratio,
+            split: SplitConfig::default(),
+            integrator: Integrator::Euler,
+            gravity_x: 0.0,
+            gravity_y: 0.0,
+            dt: 1.0,
+            splitting: true,
+            open_walls: 0,
+            min_radius: 1.0,
+            max_generation: u32::MAX,
+            wall_friction: 0.0,
+            magnus: 0.0,
+            heating: 0.0,
+            cooling: 0.0,
+            split_temperature: 0.0,
+            wall_inset: 0.0,
+            corner_radius: 0.0,
+            wall_restitution: 1.0,
+            mix_rule: MixRule::Product,
+            background: CLEAR_COLOR,
+            min_contrast: 1.0,
+        }
+    }
+}
+
+// Outcome of a wall impact
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Split {
+    #[default]
+    None,
+    Child(Ball), // Ball split off at the wall, to be added by the caller
+    Denied,      // Would have split, but the world is full
+}
+
+impl Split {
+    pub fn child(self) -> Option<Ball> {
+        match self {
+            Split::Child(ball) => Some(ball),
+            _ => None,
+        }
+    }
+}
+
+// Result of advancing one ball by a frame
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BallStep {
+    pub sanitized: u32, // SANITIZED_* mask, 0 if the ball was healthy
+    pub split: Split,
+}
+
+// Walls touched by a ball during a frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WallHits {
+    pub x: bool,
+    pub y: bool,
+    // A rounded corner's unit normal (pointing into the arena) if one was
+    // hit; x and y are then both set
+    pub corner: Option<(f32, f32)>,
+    // Kinetic energy (0.5 r^2 v^2) the bounce's restitution took (0 when elastic)
+    pub lost: f32,
+}
+
+impl WallHits {
+    pub fn any(self) -> bool {
+        self.x || self.y
+    }
+}
+
+// Advance one live ball: repair non-finite state, then `advance_ball`
+pub fn step_ball(
+    ball: &mut Ball,
+    config: &SimConfig,
+    room: bool,
+    rng: &mut impl SimRng,
+) -> BallStep {
+    // A single NaN would otherwise propagate forever (and make the ball vanish)
+    let sanitized = sanitize_ball(ball, config.width, config.height);
+    let split = advance_ball(ball, config, room, rng);
+    BallStep { sanitized, split }
+}
+
+// Integrate, bounce off the walls and split on impact. `room` says whether the
+// world can take another ball; without it the ball still bounces but keeps its size.
+pub fn advance_ball(
+    ball: &mut Ball,
+    config: &SimConfig,
+    room: bool,
+    rng: &mut impl SimRng,
+) -> Split {
+    advance(ball, config, room, rng).split
+}
+
+// What advancing a ball did: the walls it touched and the split they caused
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Advance {
+    pub hits: WallHits,
+    pub split: Split,
+}
+
+// `advance_ball`, also reporting the wall hits
+pub fn advance(ball: &mut Ball, config: &SimConfig, room: bool, rng: &mut impl SimRng) -> Advance {
+    let (hits, was_just_split) = advance_motion(ball, config);
+    let split = split_ball(ball, config, hits, was_just_split, room, rng);
+    Advance { hits, split }
+}
+
+// `advance` up to the split: move the ball and bounce it off the walls.
+// Returns the hits and whether the ball had just split, for split_ball.
+pub fn advance_motion(ball: &mut Ball, config: &SimConfig) -> (WallHits, bool) {
+    // Reset the just_split flag at the start of each frame
+    let was_just_split = ball.just_split == 1;
+    ball.just_split = 0;
+
+    let start = (ball.x, ball.y);
+    integrate(ball, config);
+    let hits = bounce_walls(ball, config);
+    if config.integrator == Integrator::Verlet {
+        verlet_velocity(ball, start, hits, config.dt);
+    }
+    wall_friction(ball, config, hits);
+    heat(ball, config, hits);
+    (hits, was_just_split)
+}
+
+pub fn integrate(ball: &mut Ball, config: &SimConfig) {
+    let dt = config.dt;
+    match config.integrator {
+        Integrator::Euler => {
+            ball.x += ball.vx * dt;
+            ball.y += ball.vy * dt;
+            accelerate(ball, config);
+        }
+        Integrator::SemiImplicitEuler | Integrator::Verlet => {
+            accelerate(ball, config);
+            ball.x += ball.vx * dt;
+            ball.y += ball.vy * dt;
+        }
+    }
+}
+
+// Skipped for zero gravity so velocities stay bit-identical (-0.0 + 0.0 is +0.0)
+fn accelerate(ball: &mut Ball, config: &SimConfig) {
+    if config.gravity_x != 0.0 {
+        ball.vx += config.gravity_x * config.dt;
+    }
+    if config.gravity_y != 0.0 {
+        ball.vy += config.gravity_y * config.dt;
+    }
+    // Magnus effect: spinning balls curve sideways. The force is perpendicular
+    // to the velocity, so it turns it by about magnus * spin * dt radians; the
+    // Cayley form rotates without trig (no_std) and keeps the speed exact.
+    if config.magnus != 0.0 && ball.spin != 0.0 {
+        let half = 0.5 * config.magnus * ball.spin * config.dt;
+        let scale = 1.0 / (1.0 + half * half);
+        let (vx, vy) = (ball.vx, ball.vy);
+        ball.vx = ((1.0 - half * half) * vx - 2.0 * half * vy) * scale;
+        ball.vy = ((1.0 - half * half) * vy + 2.0 * half * vx) * scale;
+    }
+}
+
+// Surface friction at the walls hit this step. The ball is a solid disc
+// (moment of inertia m r^2 / 2), so removing a share `wall_friction` (mixed
+// with the ball's friction) of the contact point's slip u changes the tangential velocity by -wall_friction * u / 3
+// and the spin by 2 * wall_friction * u / (3 r): sliding balls start to roll,
+// spinning balls kick off sideways.
+pub fn wall_friction(ball: &mut Ball, config: &SimConfig, hits: WallHits) {
+    let share = config
+        .mix_rule
+        .mix(config.wall_friction, ball.friction)
+        .min(1.0);
+    if share <= 0.0 || !hits.any() {
+        return;
+    }
+    if let Some((nx, ny)) = hits.corner {
+        // Same as below, along the arc's tangent (-ny, nx)
+        let slip = -ball.vx * ny + ball.vy * nx - ball.spin * ball.radius;
+        ball.vx += share * slip / 3.0 * ny;
+        ball.vy -= share * slip / 3.0 * nx;
+        ball.spin += 2.0 * share * slip / (3.0 * ball.radius);
+        return;
+    }
+    if hits.x {
+        // Normal of the wall hit, pointing into the arena; the tangent is (-ny, nx)
+        let nx = (config.width * 0.5 - ball.x).signum();
+        let slip = ball.vy * nx - ball.spin * ball.radius;
+        ball.vy -= share * slip / 3.0 * nx;
+        ball.spin += 2.0 * share * slip / (3.0 * ball.radius);
+    }
+    if hits.y {
+        let ny = (config.height * 0.5 - ball.y).signum();
+        let slip = -ball.vx * ny - ball.spin * ball.radius;
+        ball.vx += share * slip / 3.0 * ny;
+        ball.spin += 2.0 * share * slip / (3.0 * ball.radius);
+    }
+}
+
+// Wall impacts heat a ball by `heating` per unit of speed into the wall (read
+// after the bounce); every step it cools by `cooling` of its temperature per
+// frame. Both are skipped when off so a cold ball stays bit-identical.
+pub fn heat(ball: &mut Ball, config: &SimConfig, hits: WallHits) {
+    if config.cooling > 0.0 && ball.temperature != 0.0 {
+        ball.temperature -= ball.temperature * (config.cooling * config.dt).min(1.0);
+    }
+    if config.heating > 0.0 {
+        if let Some((nx, ny)) = hits.corner {
+            ball.temperature += config.heating * (ball.vx * nx + ball.vy * ny).abs();
+            return;
+        }
+        if hits.x {
+            ball.temperature += config.heating * ball.vx.abs();
+        }
+        if hits.y {
+            ball.temperature += config.heating * ball.vy.abs();
+        }
+    }
+}
+
+// Verlet: the velocity is the distance travelled since `start`, except on axes
+// where a wall reflected the ball (there the reflected velocity is kept)
+pub fn verlet_velocity(ball: &mut Ball, start: (f32, f32), hits: WallHits, dt: f32) {
+    if !hits.x {
+        ball.vx = (ball.x - start.0) / dt;
+    }
+    if !hits.y {
+        ball.vy = (ball.y - start.1) / dt;
+    }
+}
+
+// Push the ball back inside the arena, reflecting its velocity away from any wall it crossed
+pub fn bounce_walls(ball: &mut Ball, config: &SimConfig) -> WallHits {
+    let mut hits = WallHits::default();
+    let closed = |wall: u32| config.open_walls & wall == 0;
+    let inset = config.wall_inset;
+    let restitution = wall_restitution(ball, config);
+    let radius = ball.radius;
+
+    // Bounce x
+    if closed(WALL_LEFT) && ball.x - ball.radius < inset {
+        ball.x = inset + ball.radius;
+        ball.vx = rebound(-ball.vx, restitution, radius, &mut hits); // Force positive (right)
+        hits.x = true;
+    } else if closed(WALL_RIGHT) && ball.x + ball.radius > config.width - inset {
+        ball.x = config.width - inset - ball.radius;
+        ball.vx = -rebound(ball.vx, restitution, radius, &mut hits); // Force negative (left)
+        hits.x = true;
+    }
+    // A ball wider than the arena can't clear both walls: center it, so it
+    // stays over the arena when a split shrinks it
+    if hits.x
+        && closed(WALL_LEFT | WALL_RIGHT)
+        && 2.0 * radius > config.width - 2.0 * inset
+    {
+        ball.x = 0.5 * config.width;
+    }
+
+    // Bounce y
+    if closed(WALL_TOP) && ball.y - ball.radius < inset {
+        ball.y = inset + ball.radius;
+        ball.vy = rebound(-ball.vy, restitution, radius, &mut hits); // Force positive (down)
+        hits.y = true;
+    } else if closed(WALL_BOTTOM) && ball.y + ball.radius > config.height - inset {
+        ball.y = config.height - inset - ball.radius;
+        ball.vy = -rebound(ball.vy, restitution, radius, &mut hits); // Force negative (up)
+        hits.y = true;
+    }
+    if hits.y
+        && closed(WALL_TOP | WALL_BOTTOM)
+        && 2.0 * radius > config.height - 2.0 * inset
+    {
+        ball.y = 0.5 * config.height;
+    }
+
+    round_corner(ball, config, restitution, &mut hits);
+    hits
+}
+
+// The walls' restitution mixed with the ball's, for wall and obstacle bounces
+pub fn wall_restitution(ball: &Ball, config: &SimConfig) -> f32 {
+    config
+        .mix_rule
+        .mix(config.wall_restitution, ball.restitution)
+        .clamp(0.0, 1.0)
+}
+
+// Kinetic energy a bounce with `restitution` takes from a ball of `radius`
+// hitting at `speed`
+pub fn restitution_loss(radius: f32, speed: f32, restitution: f32) -> f32 {
+    0.5 * radius * radius * speed * speed * (1.0 - restitution * restitution)
+}
+
+// Speed away from a wall for a velocity component of `into` towards it
+fn rebound(into: f32, restitution: f32, radius: f32, hits: &mut WallHits) -> f32 {
+    if into <= 0.0 || restitution == 1.0 {
+        return into.abs();
+    }
+    hits.lost += restitution_loss(radius, into, restitution);
+    into * restitution
+}
+
+// Center of the rounded corner whose quarter-square (x, y) is in, if that
+// corner is rounded and both its walls are closed
+pub fn corner_center(x: f32, y: f32, config: &SimConfig) -> Option<(f32, f32)> {
+    let radius = config.corner_radius;
+    if radius <= 0.0 {
+        return None;
+    }
+    let closed = |wall: u32| config.open_walls & wall == 0;
+    let inset = config.wall_inset;
+    let (left, right) = (inset + radius, config.width - inset - radius);
+    let (top, bottom) = (inset + radius, config.height - inset - radius);
+    let cx = if x < left && closed(WALL_LEFT) {
+        left
+    } else if x > right && closed(WALL_RIGHT) {
+        right
+    } else {
+        return None;
+    };
+    let cy = if y < top && closed(WALL_TOP) {
+        top
+    } else if y > bottom && closed(WALL_BOTTOM) {
+        bottom
+    } else {
+        return None;
+    };
+    Some((cx, cy))
+}
+
+// Keep a ball in a corner zone within the arc, reflecting its velocity off
+// the arc's normal. A ball wider than the arc is held by the straight walls.
+fn round_corner(ball: &mut Ball, config: &SimConfig, restitution: f32, hits: &mut WallHits) {
+    let Some((cx, cy)) = corner_center(ball.x, ball.y, config) else {
+        return;
+    };
+    let limit = config.corner_radius - ball.radius;
+    let (dx, dy) = (ball.x - cx, ball.y - cy);
+    let squared = dx * dx + dy * dy;
+    if limit <= 0.0 || squared <= limit * limit {
+        return;
+    }
+    // Outward unit normal
+    let distance = sqrt(squared);
+    let (ox, oy) = (dx / distance, dy / distance);
+    ball.x = cx + ox * limit;
+    ball.y = cy + oy * limit;
+    let out = ball.vx * ox + ball.vy * oy;
+    if out > 0.0 {
+        ball.vx -= (1.0 + restitution) * out * ox;
+        ball.vy -= (1.0 + restitution) * out * oy;
+        if restitution < 1.0 {
+            hits.lost += restitution_loss(ball.radius, out, restitution);
+        }
+    }
+    hits.x = true;
+    hits.y = true;
+    hits.corner = Some((-ox, -oy));
+}
+
+// Square root without libm: a bit-level first guess refined by Newton's method
+// (accurate to about 1 ulp for the positive, finite values used here)
+fn sqrt(value: f32) -> f32 {
+    let mut root = f32::from_bits((value.to_bits() >> 1) + 0x1FBD_1DF5);
+    for _ in 0..3 {
+        root = 0.5 * (root + value / root);
+    }
+    root
+}
+
+// The open wall a ball has completely passed through, or 0 while any of it is inside
+pub fn escaped(ball: &Ball, config: &SimConfig) -> u32 {
+    let open = |wall: u32| config.open_walls & wall != 0;
+    if open(WALL_LEFT) && ball.x + ball.radius < 0.0 {
+        WALL_LEFT
+    } else if open(WALL_RIGHT) && ball.x - ball.radius > config.width {
+        WALL_RIGHT
+    } else if open(WALL_TOP) && ball.y + ball.radius < 0.0 {
+        WALL_TOP
+    } else if open(WALL_BOTTOM) && ball.y - ball.radius > config.height {
+        WALL_BOTTOM
+    } else {
+        0
+    }
+}
+
+// Split a ball that hit a wall this frame
+pub fn split_ball(
+    ball: &mut Ball,
+    config: &SimConfig,
+    hits: WallHits,
+    was_just_split: bool,
+    room: bool,
+    rng: &mut impl SimRng,
+) -> Split {
+    // Split logic: only split if we hit a wall AND didn't just split in the previous frame
+    if !hits.any() || was_just_split {
+        return Split::None;
+    }
+    // A rounded corner pushes off along its normal like another ball would
+    let trigger = match hits.corner {
+        Some((nx, ny)) => Trigger::Impact(nx, ny),
+        None => Trigger::Wall(hits),
+    };
+    split_off(ball, config, trigger, room, rng)
+}
+
+// Split a ball that was hit hard by another one (see World::set_impact_splitting).
+// `normal` is the unit vector from the other ball towards this one; balls
+// that split this frame already (just_split) don't split again.
+pub fn split_on_impact(
+    ball: &mut Ball,
+    config: &SimConfig,
+    normal: (f32, f32),
+    room: bool,
+    rng: &mut impl SimRng,
+) -> Split {
+    if ball.just_split == 1 {
+        return Split::None;
+    }
+    split_off(ball, config, Trigger::Impact(normal.0, normal.1), room, rng)
+}
+
+// What a split pushes off from
+#[derive(Clone, Copy)]
+enum Trigger {
+    Wall(WallHits),
+    Impact(f32, f32), // Unit normal away from the other ball
+}
+
+fn split_off(
+    ball: &mut Ball,
+    config: &SimConfig,
+    trigger: Trigger,
+    room: bool,
+    rng: &mut impl SimRng,
+) -> Split {
+    if !config.splitting || ball.generation >= config.max_generation {
+        return Split::None;
+    }
+
+    // With a split temperature, cold balls only split sometimes
+    if config.split_temperature > 0.0
+        && ball.temperature < config.split_temperature
+        && rng.next_f32() * config.split_temperature >= ball.temperature
+    {
+        return Split::None;
+    }
+
+    // Calculate new radius
+    let new_radius = ball.radius * config.split_ratio;
+
+    if !room {
+        return if new_radius >= config.min_radius {
+            Split::Denied
+        } else {
+            Split::None
+        };
+    }
+
+    // Only split if new radius would be >= min_radius (1 pixel by default)
+    if new_radius < config.min_radius {
+        // Keep minimum radius of 1.0
+        ball.radius = ball.radius.max(1.0);
+        return Split::None;
+    }
+    ball.radius = new_radius;
+    ball.just_split = 1;
+    ball.generation = ball.generation.saturating_add(1);
+
+    // Create new ball
+    let mut new_ball = *ball;
+
+    // Randomize velocity slightly but keep direction away from wall
+    let speed_factor = 0.8 + rng.next_f32() * 0.4;
+
+    let (vx, vy) = split_direction(ball, config, trigger, rng);
+    new_ball.vx = vx * speed_factor;
+    new_ball.vy = vy * speed_factor;
+
+    // Add slight angle jitter to make the split more visible
+    if config.split.direction == SplitDirection::Parent {
+        match trigger {
+            Trigger::Wall(hits) => {
+                if hits.x {
+                    // Perturb VY freely, but keep VX sign
+                    new_ball.vy += (rng.next_f32() - 0.5) * 2.0;
+                }
+                if hits.y {
+                    // Perturb VX freely, but keep VY sign
+                    new_ball.vx += (rng.next_f32() - 0.5) * 2.0;
+                }
+            }
+            Trigger::Impact(nx, ny) => {
+                // Along the contact tangent
+                let jitter = (rng.next_f32() - 0.5) * 2.0;
+                new_ball.vx -= jitter * ny;
+                new_ball.vy += jitter * nx;
+            }
+        }
+    }
+
+    if config.split.kinematics == SplitKinematics::Momentum {
+        // Both fragments get half the parent's mass, whatever split_ratio
+        // makes of their radii
+        let kick = (new_ball.vx - ball.vx, new_ball.vy - ball.vy);
+        new_ball.vx = ball.vx + 0.5 * kick.0;
+        new_ball.vy = ball.vy + 0.5 * kick.1;
+        ball.vx -= 0.5 * kick.0;
+        ball.vy -= 0.5 * kick.1;
+    }
+
+    // born_frame is left as the parent's; World sets it when inserting the child
+    new_ball.color = match config.split.child_color {
+        ChildColor::Random => random_color(config.background, config.min_contrast, rng),
+        ChildColor::GoldenAngle => {
+            let turn = GOLDEN_ANGLE_DEGREES * new_ball.generation as f32;
+            let hue = (hue(ball.color) + turn) % 360.0;
+            hsv_to_rgb(hue, PALETTE_SATURATION, PALETTE_VALUE)
+        }
+    };
+    new_ball.just_split = 1;
+
+    Split::Child(new_ball)
+}
+
+// Relative luminance of 0xRRGGBB, 0..=1, with gamma 2 standing in for the sRGB curve
+fn luminance(color: u32) -> f32 {
+    let channel = |shift: u32| {
+        let c = ((color >> shift) & 0xFF) as f32 / 255.0;
+        c * c
+    };
+    0.2126 * channel(16) + 0.7152 * channel(8) + 0.0722 * channel(0)
+}
+
+// WCAG-style contrast ratio of two colors: 1 (the same) to 21 (black and white)
+pub fn contrast(a: u32, b: u32) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// A random 0xRRGGBB with a contrast ratio of at least `min_contrast` against
+// `background`. Colors too close to it are re-rolled a few times; if none
+// works, the last one is blended toward white or black (whichever stands out
+// more) until it does.
+pub fn random_color(background: u32, min_contrast: f32, rng: &mut impl SimRng) -> u32 {
+    const REROLLS: u32 = 8;
+    const BLEND_STEPS: u32 = 8;
+    let mut color = rng.next_u32() & 0xFFFFFF;
+    for _ in 0..REROLLS {
+        if contrast(color, background) >= min_contrast {
+            return color;
+        }
+        color = rng.next_u32() & 0xFFFFFF;
+    }
+    let target = if contrast(0xFFFFFF, background) >= contrast(0, background) {
+        0xFFFFFF
+    } else {
+        0
+    };
+    let blend = |step: u32| {
+        let mix = |shift: u32| {
+            let (from, to) = ((color >> shift) & 0xFF, (target >> shift) & 0xFF);
+            (from * (BLEND_STEPS - step) + to * step) / BLEND_STEPS
+        };
+        (mix(16) << 16) | (mix(8) << 8) | mix(0)
+    };
+    (0..BLEND_STEPS)
+        .map(blend)
+        .find(|&blended| contrast(blended, background) >= min_contrast)
+        .unwrap_or(target)
+}
+
+// Hue of 0xRRGGBB in degrees, 0..360 (0 for grays)
+fn hue(color: u32) -> f32 {
+    let channel = |shift: u32| ((color >> shift) & 0xFF) as f32 / 255.0;
+    let (r, g, b) = (channel(16), channel(8), channel(0));
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    if delta == 0.0 {
+        return 0.0;
+    }
+    let hue = if max == r {
+        (g - b) / delta
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    } * 60.0;
+    if hue < 0.0 {
+        hue + 360.0
+    } else {
+        hue
+    }
+}
+
+// `hue` in degrees, saturation and value in 0..=1, as 0xRRGGBB
+pub(crate) fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> u32 {
+    let chroma = value * saturation;
+    let mut sector = hue / 60.0 % 6.0;
+    if sector < 0.0 {
+        sector += 6.0;
+    }
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f32| (((c + m) * 255.0 + 0.5) as u32).min(255);
+    (channel(r) << 16) | (channel(g) << 8) | channel(b)
+}
+
+// Velocity of a split child before the speed factor, at the parent's speed
+fn split_direction(
+    ball: &Ball,
+    config: &SimConfig,
+    trigger: Trigger,
+    rng: &mut impl SimRng,
+) -> (f32, f32) {
+    let (vx, vy) = (ball.vx, ball.vy);
+    match config.split.direction {
+        SplitDirection::Parent => (vx, vy),
+        SplitDirection::Mirror => match trigger {
+            Trigger::Wall(WallHits { x: true, y: false, .. }) => (vx, -vy),
+            Trigger::Wall(WallHits { x: false, y: true, .. }) => (-vx, vy),
+            Trigger::Wall(_) => (vx, vy),
+            // Tangential component flipped
+            Trigger::Impact(nx, ny) => {
+                let along = 2.0 * (vx * nx + vy * ny);
+                (along * nx - vx, along * ny - vy)
+            }
+        },
+        SplitDirection::Orthogonal => {
+            // Of the two perpendiculars, the one heading further away from
+            // the wall or the other ball
+            let (nx, ny) = match trigger {
+                Trigger::Wall(hits) => (
+                    if hits.x {
+                        (config.width * 0.5 - ball.x).signum()
+                    } else {
+                        0.0
+                    },
+                    if hits.y {
+                        (config.height * 0.5 - ball.y).signum()
+                    } else {
+                        0.0
+                    },
+                ),
+                Trigger::Impact(nx, ny) => (nx, ny),
+            };
+            if -vy * nx + vx * ny >= 0.0 {
+                (-vy, vx)
+            } else {
+                (vy, -vx)
+            }
+        }
+        SplitDirection::Opposite => (-vx, -vy),
+        SplitDirection::RandomCone => {
+            let cone = config.split.cone_angle.clamp(0.0, core::f32::consts::TAU);
+            let (sin, cos) = sin_cos((rng.next_f32() - 0.5) * cone);
+            (vx * cos - vy * sin, vx * sin + vy * cos)
+        }
+    }
+}
+
+// sin and cos for |angle| <= pi without libm: Taylor series at half the
+// angle, then the double-angle formulas (error below 1e-4)
+fn sin_cos(angle: f32) -> (f32, f32) {
+    let x = angle * 0.5;
+    let x2 = x * x;
+    let sin = x * (1.0 - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0))));
+    let cos = 1.0 - x2 / 2.0 * (1.0 - x2 / 12.0 * (1.0 - x2 / 30.0 * (1.0 - x2 / 56.0)));
+    (2.0 * sin * cos, cos * cos - sin * sin)
+}
+
+// One frame for hosts that keep a plain Vec<Ball> (no slot reuse or events):
+// steps every live ball and appends the children. Returns how many were added.
+pub fn step(balls: &mut Vec<Ball>, config: &SimConfig, rng: &mut impl SimRng) -> usize {
+    let live = balls.iter().filter(|ball| ball.alive != 0).count();
+    let mut children = Vec::new();
+    for ball in balls.iter_mut().filter(|ball| ball.alive != 0) {
+        let room = live + children.len() < config.max_balls;
+        if let Some(child) = step_ball(ball, config, room, rng).split.child() {
+            children.push(child);
+        }
+    }
+    let added = children.len();
+    balls.extend(children);
+    added
+}
+
+// Repair a ball with NaN/Inf state: position goes back to the arena center,
+// velocity to zero and radius to 1px. Returns the SANITIZED_* mask (0 if healthy).
+pub fn sanitize_ball(ball: &mut Ball, width: f32, height: f32) -> u32 {
+    let mut mask = 0;
+    if !ball.radius.is_finite() || ball.radius <= 0.0 {
+        ball.radius = 1.0;
+        mask |= SANITIZED_RADIUS;
+    }
+    if !ball.x.is_finite() || !ball.y.is_finite() {
+        ball.x = width / 2.0;
+        ball.y = height / 2.0;
+        mask |= SANITIZED_POSITION;
+    }
+    if !ball.vx.is_finite() || !ball.vy.is_finite() {
+        ball.vx = 0.0;
+        ball.vy = 0.0;
+        mask |= SANITIZED_VELOCITY;
+    }
+    mask
+}
@@ -0,0 +1,216 @@
+// Compact binary snapshots for streaming a World to remote viewers.
+//
+// Layout (all little-endian):
+//   magic "BBS5", kind u8 (0 = full, 1 = delta), frame u32, base_frame u32,
+//   width f32, height f32, max_balls u64, split_ratio f32,
+//   ball_count u32, entry_count u32, then entry_count x (index u32, ball record).
+// A ball record is the 16 words of `Ball` in field order (layer widened to a word).
+// A full snapshot carries every slot; a delta only the slots changed after
+// `base_frame` (a removed ball is sent as a changed slot with alive == 0). `ball_count` is the sender's total, so balls beyond it on the
+// receiver are dropped.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Ball, World, WorldError};
+
+const MAGIC: &[u8; 4] = b"BBS5";
+const KIND_FULL: u8 = 0;
+const KIND_DELTA: u8 = 1;
+
+impl Ball {
+    pub(crate) const ENCODED_LEN: usize = 64;
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        for word in [
+            self.x.to_bits(),
+            self.y.to_bits(),
+            self.vx.to_bits(),
+            self.vy.to_bits(),
+            self.radius.to_bits(),
+            self.color,
+            self.just_split,
+            self.tag,
+            self.layer as u32,
+            self.alive,
+            self.born_frame,
+            self.generation,
+            self.spin.to_bits(),
+            self.temperature.to_bits(),
+            self.restitution.to_bits(),
+            self.friction.to_bits(),
+        ] {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Ball, WorldError> {
+        Ok(Ball {
+            x: reader.f32()?,
+            y: reader.f32()?,
+            vx: reader.f32()?,
+            vy: reader.f32()?,
+            radius: reader.f32()?,
+            color: reader.u32()?,
+            just_split: reader.u32()?,
+            tag: reader.u32()?,
+            layer: reader.u32()? as u8,
+            alive: reader.u32()?,
+            born_frame: reader.u32()?,
+            generation: reader.u32()?,
+            spin: reader.f32()?,
+            temperature: reader.f32()?,
+            restitution: reader.f32()?,
+            friction: reader.f32()?,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], WorldError> {
+        if self.bytes.len() < n {
+            return Err(WorldError::SnapshotTruncated);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, WorldError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, WorldError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, WorldError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn f32(&mut self) -> Result<f32, WorldError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Every ball plus the world config
+    pub fn snapshot_full(&self) -> Vec<u8> {
+        self.encode_snapshot(KIND_FULL, 0, |_| true)
+    }
+
+    // Only balls added or changed after `since_frame`
+    pub fn snapshot_delta(&self, since_frame: u32) -> Vec<u8> {
+        self.encode_snapshot(KIND_DELTA, since_frame, |stamp| stamp > since_frame)
+    }
+
+    // Apply a snapshot produced by another World. A delta must be based on a
+    // frame this world has already reached, otherwise changes would be missed.
+    pub fn apply_snapshot(&mut self, bytes: &[u8]) -> Result<(), WorldError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(WorldError::SnapshotBadMagic);
+        }
+        let kind = reader.u8()?;
+        if kind != KIND_FULL && kind != KIND_DELTA {
+            return Err(WorldError::SnapshotUnknownKind(kind));
+        }
+        let frame = reader.u32()?;
+        let base_frame = reader.u32()?;
+        if kind == KIND_DELTA && base_frame > self.frame {
+            return Err(WorldError::SnapshotBaseMismatch {
+                base_frame,
+                frame: self.frame,
+            });
+        }
+        let width = reader.f32()?;
+        let height = reader.f32()?;
+        // More than this build can address (a native sender's, on wasm32)
+        let max_balls = usize::try_from(reader.u64()?).map_err(|_| WorldError::InvalidMaxBalls)?;
+        let split_ratio = reader.f32()?;
+        crate::validate_config(width, height, max_balls, split_ratio)?;
+        let ball_count = reader.u32()?;
+        let entry_count = reader.u32()?;
+
+        // Decode everything before touching the world so a bad snapshot leaves it intact
+        let mut entries =
+            Vec::with_capacity((entry_count as usize).min(reader.bytes.len() / Ball::ENCODED_LEN));
+        for _ in 0..entry_count {
+            let index = reader.u32()?;
+            if index >= ball_count {
+                return Err(WorldError::SnapshotBadIndex(index));
+            }
+            entries.push((index as usize, Ball::decode(&mut reader)?));
+        }
+        if kind == KIND_FULL && entries.len() != ball_count as usize {
+            return Err(WorldError::SnapshotTruncated);
+        }
+
+        self.width = width;
+        self.height = height;
+        self.max_balls = max_balls;
+        self.split_ratio = split_ratio;
+        self.frame = frame;
+
+        // Balls missing from a delta keep their current state; new slots stay free unless an entry fills them
+        self.balls.resize(ball_count as usize, Ball::default());
+        self.modified.resize(ball_count as usize, frame);
+        for (index, ball) in entries {
+            self.balls[index] = ball;
+            self.modified[index] = frame;
+        }
+        self.rebuild_free_list();
+        Ok(())
+    }
+}
+
+impl World {
+    fn encode_snapshot(&self, kind: u8, base_frame: u32, include: impl Fn(u32) -> bool) -> Vec<u8> {
+        let entry_count = self
+            .modified
+            .iter()
+            .filter(|&&stamp| include(stamp))
+            .count();
+        let mut out = Vec::with_capacity(41 + entry_count * (4 + Ball::ENCODED_LEN));
+        out.extend_from_slice(MAGIC);
+        out.push(kind);
+        out.extend_from_slice(&self.frame.to_le_bytes());
+        out.extend_from_slice(&base_frame.to_le_bytes());
+        out.extend_from_slice(&self.width.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        out.extend_from_slice(&(self.max_balls as u64).to_le_bytes());
+        out.extend_from_slice(&self.split_ratio.to_le_bytes());
+        out.extend_from_slice(&(self.balls.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(entry_count as u32).to_le_bytes());
+        for (index, (ball, &stamp)) in self.balls.iter().zip(&self.modified).enumerate() {
+            if include(stamp) {
+                out.extend_from_slice(&(index as u32).to_le_bytes());
+                ball.encode(&mut out);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    // A capacity past u32::MAX survives the round trip
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn huge_max_balls_round_trips() {
+        let max_balls = u32::MAX as usize + 2;
+        let world = World::new_empty_seeded(200.0, 150.0, max_balls, 0.7, 1).unwrap();
+        let mut copy = World::new_empty_seeded(100.0, 100.0, 4, 0.5, 1).unwrap();
+        copy.apply_snapshot(&world.snapshot_full()).unwrap();
+        assert_eq!(copy.max_balls(), max_balls);
+    }
+}
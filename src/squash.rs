@@ -0,0 +1,117 @@
+// Squash-and-stretch, purely visual: after a wall impact a ball is drawn as
+// an ellipse flattened against the wall (and widened along it), easing back
+// to a circle over `duration` frames. The flattened side stays on the wall.
+// Physics, queries and snapshots keep seeing the round ball.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{sim, Ball, World};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Impact {
+    frame: u32,
+    // Unit normal of the wall that was hit, pointing into the arena (0, 0 = none)
+    nx: f32,
+    ny: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Squash {
+    amount: f32, // 0 = off
+    duration: u32,
+    impacts: Vec<Impact>, // Per slot, last wall impact
+}
+
+// Ellipse a ball is drawn as: center and axis-aligned semi-axes
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Shape {
+    pub cx: f32,
+    pub cy: f32,
+    pub rx: f32,
+    pub ry: f32,
+}
+
+impl Squash {
+    pub(crate) fn enabled(&self) -> bool {
+        self.amount > 0.0 && self.duration > 0
+    }
+
+    // How far past its radius a squashed ball may reach (for binning)
+    pub(crate) fn max_stretch(&self) -> f32 {
+        if self.enabled() {
+            1.0 + self.amount
+        } else {
+            1.0
+        }
+    }
+
+    // Remember a wall impact of ball `id`, after the bounce reflected its velocity
+    pub(crate) fn record(&mut self, id: usize, frame: u32, ball: &Ball, hits: sim::WallHits) {
+        if !self.enabled() || !hits.any() {
+            return;
+        }
+        // A corner hit squashes along the faster axis
+        let along_x = hits.x && (!hits.y || ball.vx.abs() >= ball.vy.abs());
+        let (nx, ny) = if along_x {
+            (ball.vx.signum(), 0.0)
+        } else {
+            (0.0, ball.vy.signum())
+        };
+        if id >= self.impacts.len() {
+            self.impacts.resize(id + 1, Impact::default());
+        }
+        self.impacts[id] = Impact { frame, nx, ny };
+    }
+
+    // The ellipse to draw ball `id` as, or None while it is round
+    pub(crate) fn shape(&self, id: usize, ball: &Ball, frame: u32) -> Option<Shape> {
+        // Slots are reused: an impact from before the ball was born belongs to a removed ball
+        let impact = self.impacts.get(id).filter(|impact| {
+            impact.frame >= ball.born_frame && (impact.nx, impact.ny) != (0.0, 0.0)
+        })?;
+        if !self.enabled() {
+            return None;
+        }
+        let elapsed = frame.wrapping_sub(impact.frame);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let squash = self.amount * (1.0 - elapsed as f32 / self.duration as f32);
+        let (flat, wide) = (ball.radius * (1.0 - squash), ball.radius * (1.0 + squash));
+        // Shift the center towards the wall by as much as the ball flattened
+        let shift = ball.radius - flat;
+        Some(Shape {
+            cx: ball.x - impact.nx * shift,
+            cy: ball.y - impact.ny * shift,
+            rx: if impact.nx != 0.0 { flat } else { wide },
+            ry: if impact.ny != 0.0 { flat } else { wide },
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // `amount` is the fraction of the radius a ball flattens by right after an
+    // impact (0 turns the effect off, at most 0.9); it recovers linearly over
+    // `duration_frames`.
+    pub fn set_squash(&mut self, amount: f32, duration_frames: u32) {
+        let amount = if amount.is_finite() {
+            amount.clamp(0.0, 0.9)
+        } else {
+            0.0
+        };
+        self.squash.amount = amount;
+        self.squash.duration = duration_frames;
+        if !self.squash.enabled() {
+            self.squash.impacts = Vec::new();
+        }
+    }
+
+    pub fn squash_amount(&self) -> f32 {
+        self.squash.amount
+    }
+
+    pub fn squash_duration(&self) -> u32 {
+        self.squash.duration
+    }
+}
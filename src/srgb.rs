@@ -0,0 +1,110 @@
+// sRGB-aware blending. The framebuffer holds sRGB values, so mixing a
+// channel part of the way to a color in those values (the default, and how
+// this renderer always blended) is off in light terms: a translucent overlap
+// or a faded edge comes out darker and muddier than it should. With linear
+// blending on, both sides are decoded to linear light through a 256-entry
+// table, mixed there and encoded back through a 4096-entry one.
+//
+// Every translucent draw goes through here: trails, plexus lines, despawn
+// fades, flashes, Voronoi shading, water and translucent background images.
+// Opaque ones (balls, walls, obstacles) don't blend and look the same either
+// way. Over transparent pixels the premultiplied values are mixed as if they
+// were colors, which is exact wherever the pixel underneath is opaque.
+
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::World;
+
+const ENCODE_STEPS: usize = 4096;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Blending {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+struct Tables {
+    decode: [f32; 256],         // sRGB byte -> linear 0..=1
+    encode: [u8; ENCODE_STEPS], // Linear, in ENCODE_STEPS - 1 steps -> sRGB byte
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Box<Tables>> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = Box::new(Tables {
+            decode: [0.0; 256],
+            encode: [0; ENCODE_STEPS],
+        });
+        for (value, linear) in tables.decode.iter_mut().enumerate() {
+            let c = value as f32 / 255.0;
+            *linear = if c <= 0.040_45 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+        for (step, value) in tables.encode.iter_mut().enumerate() {
+            let l = step as f32 / (ENCODE_STEPS - 1) as f32;
+            let c = if l <= 0.003_130_8 {
+                l * 12.92
+            } else {
+                1.055 * l.powf(1.0 / 2.4) - 0.055
+            };
+            *value = (c * 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+        tables
+    })
+}
+
+impl Blending {
+    // Draw opaque `rgb` with coverage `alpha` (0..=1) over a premultiplied
+    // RGBA pixel (see alpha.rs): its color moves that far towards `rgb`, and
+    // its alpha that far towards opaque
+    pub(crate) fn blend(self, pixel: &mut [u8], rgb: [u8; 3], alpha: f32) {
+        let coverage = pixel[3] as f32;
+        pixel[3] = (coverage + (255.0 - coverage) * alpha) as u8;
+        match self {
+            Blending::Srgb => {
+                for (channel, value) in pixel[..3].iter_mut().zip(rgb) {
+                    let (from, to) = (*channel as f32, value as f32);
+                    *channel = (from + (to - from) * alpha) as u8;
+                }
+            }
+            Blending::Linear => {
+                let tables = tables();
+                for (channel, value) in pixel[..3].iter_mut().zip(rgb) {
+                    let from = tables.decode[*channel as usize];
+                    let to = tables.decode[value as usize];
+                    let mixed = from + (to - from) * alpha;
+                    let step = (mixed * (ENCODE_STEPS - 1) as f32 + 0.5) as usize;
+                    *channel = tables.encode[step.min(ENCODE_STEPS - 1)];
+                }
+            }
+        }
+    }
+}
+
+// 0xRRGGBB as bytes
+pub(crate) fn rgb(color: u32) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+#[wasm_bindgen]
+impl World {
+    // Blend translucent drawing in linear light instead of straight on the
+    // sRGB values (see srgb.rs). Off by default.
+    pub fn set_linear_blending(&mut self, enabled: bool) {
+        self.render.blending = if enabled {
+            Blending::Linear
+        } else {
+            Blending::Srgb
+        };
+    }
+
+    pub fn linear_blending(&self) -> bool {
+        self.render.blending == Blending::Linear
+    }
+}
@@ -0,0 +1,270 @@
+// Slot storage for balls. `balls` is a slot array with a free list: removing
+// a ball only marks its slot free (alive = 0, radius = 0) and a later insert
+// reuses it, so adds and removals are O(1), ids stay put and the memory
+// behind get_balls_ptr never needs compacting.
+
+use wasm_bindgen::prelude::*;
+
+use crate::history::Edit;
+use crate::{sim, Ball, World};
+
+#[wasm_bindgen]
+impl World {
+    // Add a ball and return its id, or None if max_balls live balls already exist.
+    // A broken radius, position or velocity is fixed up like update() does.
+    pub fn add_ball(
+        &mut self,
+        x: f32,
+        y: f32,
+        vx: f32,
+        vy: f32,
+        radius: f32,
+        color: u32,
+    ) -> Option<u32> {
+        if self.live_count() >= self.max_balls {
+            return None;
+        }
+        let mut ball = Ball::new(x, y, vx, vy, radius, color & 0xFFFFFF);
+        sim::sanitize_ball(&mut ball, self.width, self.height);
+        Some(self.add_edited_ball(ball))
+    }
+
+    // Free a ball's slot. Returns false if the id is not a live ball.
+    pub fn remove_ball(&mut self, id: u32) -> bool {
+        match self.free_slot(id) {
+            Some(ball) => {
+                self.record_edit(Edit::RemoveBall { id, ball });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_alive(&self, id: u32) -> bool {
+        self.balls
+            .get(id as usize)
+            .is_some_and(|ball| ball.alive != 0)
+    }
+
+    // Balls currently in the simulation
+    pub fn live_count(&self) -> usize {
+        self.balls.len() - self.free.len()
+    }
+
+    // Slots currently used by the array, live or free (same as get_balls_len)
+    pub fn slot_count(&self) -> usize {
+        self.balls.len()
+    }
+
+    // Slots that fit in the current allocation before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.balls.capacity()
+    }
+
+    // Make room for `additional` more slots up front. Growing WASM memory detaches
+    // every JS view of it, so hosts can do this once instead of mid-simulation.
+    // Does nothing if that much memory can't be had.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.balls.try_reserve(additional).is_ok() {
+            let _ = self.modified.try_reserve(additional);
+        }
+    }
+
+    // Drop trailing free slots and release unused capacity
+    pub fn shrink_to_fit(&mut self) {
+        let live_end = self
+            .balls
+            .iter()
+            .rposition(|ball| ball.alive != 0)
+            .map_or(0, |id| id + 1);
+        self.balls.truncate(live_end);
+        self.modified.truncate(live_end);
+        self.free.retain(|&id| (id as usize) < live_end);
+        if let Some(precise) = &mut self.precise {
+            precise.truncate(live_end);
+            precise.shrink_to_fit();
+        }
+        #[cfg(feature = "fixed")]
+        if let Some(fixed) = &mut self.fixed {
+            fixed.truncate(live_end);
+            fixed.shrink_to_fit();
+        }
+        self.balls.shrink_to_fit();
+        self.modified.shrink_to_fit();
+        self.free.shrink_to_fit();
+        self.events.shrink_to_fit();
+    }
+
+    // Approximate heap footprint of this World in bytes
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.balls.capacity() * std::mem::size_of::<Ball>()
+            + self.modified.capacity() * std::mem::size_of::<u32>()
+            + self.free.capacity() * std::mem::size_of::<u32>()
+            + self.events.capacity() * std::mem::size_of::<crate::Event>()
+            + self.render.memory_usage_bytes()
+            + self.precise_memory_bytes()
+            + self.fixed_memory_bytes()
+            + self.trails.memory_usage_bytes()
+    }
+}
+
+impl World {
+    // insert_ball for the editing API: recorded for undo()
+    pub(crate) fn add_edited_ball(&mut self, ball: Ball) -> u32 {
+        let id = self.insert_ball(ball);
+        let ball = self.balls[id as usize];
+        self.record_edit(Edit::AddBall { id, ball });
+        id
+    }
+
+    // Remove a live ball without recording it for undo(), returning it
+    pub(crate) fn free_slot(&mut self, id: u32) -> Option<Ball> {
+        let ball = self
+            .balls
+            .get_mut(id as usize)
+            .filter(|ball| ball.alive != 0)?;
+        let removed = std::mem::take(ball);
+        // Frames don't advance in edit mode, so an animation there would never end
+        if !self.edit_mode() {
+            self.despawns.record(&removed, self.frame);
+        }
+        self.outlines.forget(id as usize);
+        self.free.push(id);
+        self.touch(id as usize);
+        Some(removed)
+    }
+
+    // Place a ball in a free slot (or a new one) and return its id. Ignores max_balls.
+    // Added from outside update(): born in the current frame
+    pub(crate) fn insert_ball(&mut self, ball: Ball) -> u32 {
+        let born_frame = self.frame;
+        self.insert_child(ball, born_frame)
+    }
+
+    // Added by update() while it builds frame `born_frame`
+    pub(crate) fn insert_child(&mut self, mut ball: Ball, born_frame: u32) -> u32 {
+        ball.born_frame = born_frame;
+        let stamp = self.frame.wrapping_add(1);
+        match self.free.pop() {
+            Some(id) => {
+                self.balls[id as usize] = ball;
+                self.modified[id as usize] = stamp;
+                id
+            }
+            None => {
+                self.balls.push(ball);
+                self.modified.push(stamp);
+                (self.balls.len() - 1) as u32
+            }
+        }
+    }
+
+    pub(crate) fn live_balls(&self) -> impl Iterator<Item = (usize, &Ball)> {
+        self.balls
+            .iter()
+            .enumerate()
+            .filter(|(_, ball)| ball.alive != 0)
+    }
+
+    // Lowest ids are reused first, which keeps the live set packed near the front
+    pub(crate) fn rebuild_free_list(&mut self) {
+        self.free.clear();
+        for (id, ball) in self.balls.iter().enumerate().rev() {
+            if ball.alive == 0 {
+                self.free.push(id as u32);
+            }
+        }
+    }
+}
+
+// Room for `slots` balls up front, or none if that much can't be allocated
+pub(crate) fn preallocated(slots: usize) -> Vec<Ball> {
+    let mut balls = Vec::new();
+    let _ = balls.try_reserve_exact(slots);
+    balls
+}
+
+// Builds without the `fixed` feature have no Q16.16 shadow
+#[cfg(not(feature = "fixed"))]
+impl World {
+    fn fixed_memory_bytes(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    // The Q16.16 shadow is counted and shrinks with the slots
+    #[cfg(feature = "fixed")]
+    #[test]
+    fn shrink_to_fit_frees_the_fixed_shadow() {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 64, 0.7, 1).unwrap();
+        let ids: Vec<u32> = (0..32)
+            .filter_map(|i| world.add_ball(10.0 + i as f32 * 5.0, 50.0, 0.0, 0.0, 2.0, 0))
+            .collect();
+        world.set_fixed_point(true);
+        let full = world.fixed_memory_bytes();
+        assert!(full >= 32 * std::mem::size_of::<crate::fixed::FixedState>());
+        for &id in &ids[1..] {
+            world.remove_ball(id);
+        }
+        world.shrink_to_fit();
+        assert_eq!(world.fixed.as_ref().unwrap().len(), 1);
+        assert!(world.fixed_memory_bytes() < full);
+    }
+
+    // Broken radii, positions and velocities are fixed up instead of poisoning the world
+    #[test]
+    fn add_ball_sanitizes_broken_input() {
+        let mut world = World::new(200.0, 150.0, 64, 0.7);
+        for radius in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -5.0, 0.0] {
+            let id = world
+                .add_ball(
+                    f32::NAN,
+                    f32::INFINITY,
+                    f32::NEG_INFINITY,
+                    f32::NAN,
+                    radius,
+                    0xFFFFFFFF,
+                )
+                .unwrap();
+            let ball = world.ball(id).unwrap();
+            assert_eq!(ball.radius(), 1.0);
+            assert_eq!((ball.x(), ball.y()), (100.0, 75.0));
+            assert_eq!((ball.vx(), ball.vy()), (0.0, 0.0));
+            assert_eq!(ball.color(), 0xFFFFFF);
+        }
+    }
+
+    // Huge but finite radii are kept, and updating and drawing them must not panic
+    #[test]
+    fn huge_radii_update_and_render() {
+        let mut world = World::new(200.0, 150.0, 64, 0.7);
+        world.set_collisions(true);
+        for radius in [1e30, f32::MAX] {
+            world.add_ball(50.0, 50.0, 1e38, -1e38, radius, 0).unwrap();
+        }
+        for _ in 0..20 {
+            world.update();
+        }
+        let mut buffer = vec![0; 200 * 150 * 4];
+        assert!(world.render_to_buffer(&mut buffer, 200, 150, None).is_ok());
+    }
+
+    #[test]
+    fn add_ball_fails_when_full() {
+        let mut world = World::new(200.0, 150.0, 1, 0.7);
+        while world.add_ball(10.0, 10.0, 0.0, 0.0, 5.0, 0).is_some() {}
+        assert_eq!(world.live_count(), 1);
+    }
+
+    // Reservations that can't be had are ignored
+    #[test]
+    fn reserve_survives_impossible_sizes() {
+        let mut world = World::new(200.0, 150.0, 64, 0.7);
+        world.reserve(usize::MAX);
+        assert!(world.add_ball(10.0, 10.0, 0.0, 0.0, 5.0, 0).is_some());
+    }
+}
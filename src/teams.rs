@@ -0,0 +1,106 @@
+// Team statistics. A ball's tag doubles as its team, and split children
+// inherit it, so a team grows as its balls split. team_count() and
+// team_area() add up a team's live balls. With an area threshold set, a team
+// whose balls together cover more than that many square pixels emits a
+// TeamThreshold event, once until it drops back under: the end of a color
+// war. Checked once per frame, after the (sub-)steps.
+
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::events::{self, Event, EventKind};
+use crate::{Ball, World};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Teams {
+    threshold: f32,  // Square pixels, 0 = no events
+    above: Vec<u32>, // Teams over the threshold at the last check
+}
+
+// Summed area and area-weighted center of each team
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    area: f32,
+    x: f32,
+    y: f32,
+}
+
+fn area(ball: &Ball) -> f32 {
+    std::f32::consts::PI * ball.radius * ball.radius
+}
+
+impl World {
+    pub(crate) fn check_teams(&mut self, stamp: u32) {
+        let threshold = self.teams.threshold;
+        if threshold <= 0.0 {
+            return;
+        }
+        let mut totals: BTreeMap<u32, Totals> = BTreeMap::new();
+        for (_, ball) in self.live_balls() {
+            let team = totals.entry(ball.tag).or_default();
+            let a = area(ball);
+            team.area += a;
+            team.x += ball.x * a;
+            team.y += ball.y * a;
+        }
+        let mut above = Vec::new();
+        for (&tag, team) in totals.iter().filter(|(_, team)| team.area > threshold) {
+            above.push(tag);
+            if self.teams.above.contains(&tag) {
+                continue;
+            }
+            events::push_event(
+                &mut self.events,
+                Event {
+                    kind: EventKind::TeamThreshold,
+                    id: tag,
+                    frame: stamp,
+                    x: team.x / team.area,
+                    y: team.y / team.area,
+                    value: team.area,
+                },
+            );
+        }
+        self.teams.above = above;
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // The tags (teams) live balls carry, ascending
+    pub fn teams(&self) -> Vec<u32> {
+        let mut tags: Vec<u32> = self.live_balls().map(|(_, ball)| ball.tag).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    // Live balls on a team
+    pub fn team_count(&self, tag: u32) -> u32 {
+        self.live_balls()
+            .filter(|(_, ball)| ball.tag == tag)
+            .count() as u32
+    }
+
+    // Total area of a team's live balls, in square pixels
+    pub fn team_area(&self, tag: u32) -> f32 {
+        self.live_balls()
+            .filter(|(_, ball)| ball.tag == tag)
+            .map(|(_, ball)| area(ball))
+            .sum()
+    }
+
+    // Emit a TeamThreshold event when a team's area grows past `area` square
+    // pixels (0, the default, turns it off)
+    pub fn set_team_area_threshold(&mut self, area: f32) {
+        if area.is_finite() {
+            self.teams.threshold = area.max(0.0);
+            self.teams.above.clear();
+        }
+    }
+
+    pub fn team_area_threshold(&self) -> f32 {
+        self.teams.threshold
+    }
+}
@@ -0,0 +1,143 @@
+// Per-frame telemetry for graphing a run (population growth, energy...).
+// With a capacity set, every update() appends one sample and the oldest ones
+// are dropped beyond the capacity. export_telemetry writes the samples as CSV
+// (header row, one line per frame) or as a JSON array of objects, both with
+// the columns: frame, balls, energy, mean_radius, splits.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::World;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    #[default]
+    Csv = 0,
+    Json = 1,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    frame: u32,
+    balls: usize,
+    energy: f64, // EnergyReport::total()
+    mean_radius: f32,
+    splits: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Telemetry {
+    capacity: usize, // 0 = off
+    samples: VecDeque<Sample>,
+    splits: u32, // Children added so far this frame
+}
+
+impl Telemetry {
+    pub(crate) fn record_splits(&mut self, count: usize) {
+        self.splits = self.splits.saturating_add(count as u32);
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Keep the last `capacity` frames of telemetry (0, the default, turns it
+    // off and drops what was recorded)
+    pub fn set_telemetry(&mut self, capacity: u32) {
+        let capacity = capacity as usize;
+        self.telemetry.capacity = capacity;
+        if capacity == 0 {
+            self.telemetry.samples = VecDeque::new();
+        } else {
+            let excess = self.telemetry.samples.len().saturating_sub(capacity);
+            self.telemetry.samples.drain(..excess);
+        }
+    }
+
+    pub fn telemetry_len(&self) -> usize {
+        self.telemetry.samples.len()
+    }
+
+    pub fn clear_telemetry(&mut self) {
+        self.telemetry.samples.clear();
+    }
+
+    // The last `frames` recorded frames (0 = all of them), oldest first
+    pub fn export_telemetry(&self, frames: u32, format: TelemetryFormat) -> String {
+        let samples = &self.telemetry.samples;
+        let skip = match frames {
+            0 => 0,
+            frames => samples.len().saturating_sub(frames as usize),
+        };
+        let mut out = String::new();
+        match format {
+            TelemetryFormat::Csv => {
+                out.push_str("frame,balls,energy,mean_radius,splits\n");
+                for sample in samples.iter().skip(skip) {
+                    let _ = writeln!(
+                        out,
+                        "{},{},{},{},{}",
+                        sample.frame,
+                        sample.balls,
+                        sample.energy,
+                        sample.mean_radius,
+                        sample.splits
+                    );
+                }
+            }
+            TelemetryFormat::Json => {
+                out.push('[');
+                for (index, sample) in samples.iter().skip(skip).enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    // Energy and radius are finite: balls are sanitized every step
+                    let _ = write!(
+                        out,
+                        "{{\"frame\":{},\"balls\":{},\"energy\":{},\"mean_radius\":{},\"splits\":{}}}",
+                        sample.frame,
+                        sample.balls,
+                        sample.energy,
+                        sample.mean_radius,
+                        sample.splits
+                    );
+                }
+                out.push(']');
+            }
+        }
+        out
+    }
+}
+
+impl World {
+    // Called at the end of update(), once the frame counter has advanced
+    pub(crate) fn record_telemetry(&mut self) {
+        let splits = std::mem::take(&mut self.telemetry.splits);
+        if self.telemetry.capacity == 0 {
+            return;
+        }
+        let (balls, radius_sum) = self
+            .live_balls()
+            .fold((0, 0.0f64), |(count, sum), (_, ball)| {
+                (count + 1, sum + ball.radius as f64)
+            });
+        let sample = Sample {
+            frame: self.frame,
+            balls,
+            energy: self.energy_report().total(),
+            mean_radius: if balls == 0 {
+                0.0
+            } else {
+                (radius_sum / balls as f64) as f32
+            },
+            splits,
+        };
+        let samples = &mut self.telemetry.samples;
+        if samples.len() >= self.telemetry.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+}
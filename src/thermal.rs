@@ -0,0 +1,79 @@
+// A simple temperature model. Every impact (walls always, other balls with
+// collisions on) heats a ball in proportion to its speed into the surface,
+// and balls cool exponentially towards 0 between impacts; see sim::heat.
+// Temperature has no physical unit: 1 is "red hot", and ColorMode::Temperature
+// draws balls blue at 0, red at 1 and white from 2 up. Optionally, balls
+// colder than a split temperature split on a wall hit only with probability
+// temperature / split_temperature, so hot regions grow faster.
+//
+// All of it is off by default: balls stay at temperature 0.
+
+use wasm_bindgen::prelude::*;
+
+use crate::World;
+
+const COLD: (f32, f32, f32) = (0.0, 64.0, 255.0);
+const HOT: (f32, f32, f32) = (255.0, 0.0, 0.0);
+const WHITE: (f32, f32, f32) = (255.0, 255.0, 255.0);
+
+// 0xRRGGBB for a temperature: blue -> red over 0..1, red -> white over 1..2
+pub(crate) fn heat_color(temperature: f32) -> u32 {
+    let t = if temperature.is_finite() {
+        temperature.clamp(0.0, 2.0)
+    } else {
+        2.0
+    };
+    let (from, to, share) = if t < 1.0 {
+        (COLD, HOT, t)
+    } else {
+        (HOT, WHITE, t - 1.0)
+    };
+    let mix = |a: f32, b: f32| (a + (b - a) * share).round() as u32;
+    (mix(from.0, to.0) << 16) | (mix(from.1, to.1) << 8) | mix(from.2, to.2)
+}
+
+#[wasm_bindgen]
+impl World {
+    // `heating`: temperature gained per pixel/frame of impact speed (0 = off).
+    // `cooling`: share of its temperature a ball loses per frame (0..=1).
+    pub fn set_temperature_model(&mut self, heating: f32, cooling: f32) {
+        if heating.is_finite() && cooling.is_finite() {
+            self.heating = heating.max(0.0);
+            self.cooling = cooling.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn heating(&self) -> f32 {
+        self.heating
+    }
+
+    pub fn cooling(&self) -> f32 {
+        self.cooling
+    }
+
+    // Balls below this temperature split less often (0, the default, turns
+    // the rule off: every wall hit splits)
+    pub fn set_split_temperature(&mut self, temperature: f32) {
+        if temperature.is_finite() {
+            self.split_temperature = temperature.max(0.0);
+        }
+    }
+
+    pub fn split_temperature(&self) -> f32 {
+        self.split_temperature
+    }
+
+    // Mean temperature of the live balls (0 for an empty world)
+    pub fn mean_temperature(&self) -> f32 {
+        let (sum, count) = self
+            .live_balls()
+            .fold((0.0f64, 0usize), |(sum, count), (_, ball)| {
+                (sum + ball.temperature as f64, count + 1)
+            });
+        if count == 0 {
+            0.0
+        } else {
+            (sum / count as f64) as f32
+        }
+    }
+}
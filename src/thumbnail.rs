@@ -0,0 +1,66 @@
+// Thumbnails. render_thumbnail() draws the whole arena at its own resolution,
+// whatever size the host's framebuffer has, and box-filters it down (or up)
+// to a small RGBA image for previews, minimaps or save-slot pictures.
+//
+// The arena is rendered BAND_ROWS rows at a time into one reused band, so a
+// thumbnail of a large world never holds a full-size framebuffer. The image
+// is stretched to exactly w x h; pass the arena's aspect ratio to keep balls
+// round.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{alpha, World};
+
+// Largest thumbnail side
+const MAX_THUMBNAIL: u32 = 4096;
+// Largest arena side rendered; bigger arenas are clipped to their top-left
+const MAX_SOURCE: usize = 16384;
+
+#[wasm_bindgen]
+impl World {
+    // A w x h RGBA snapshot of the whole arena (sides clamped to 4096; empty
+    // if either is 0)
+    pub fn render_thumbnail(&self, w: u32, h: u32) -> Vec<u8> {
+        if w == 0 || h == 0 {
+            return Vec::new();
+        }
+        let (tw, th) = (w.min(MAX_THUMBNAIL) as usize, h.min(MAX_THUMBNAIL) as usize);
+        let source = |side: f32| (side.ceil() as usize).clamp(1, MAX_SOURCE);
+        let (sw, sh) = (source(self.width), source(self.height));
+
+        // Thumbnail columns/rows each source column/row overlaps
+        let span =
+            |i: usize, to: usize, from: usize| (i * to / from, ((i + 1) * to).div_ceil(from));
+        let columns: Vec<(usize, usize)> = (0..sw).map(|x| span(x, tw, sw)).collect();
+
+        let mut sums = vec![[0u64; 4]; tw * th];
+        let mut counts = vec![0u64; tw * th];
+        self.paint_bands(sw, sh, |y0, band| {
+            for (row, pixels) in band.chunks_exact(sw * 4).enumerate() {
+                let (ty0, ty1) = span(y0 + row, th, sh);
+                for (pixel, &(tx0, tx1)) in pixels.chunks_exact(4).zip(&columns) {
+                    for ty in ty0..ty1 {
+                        for tx in tx0..tx1 {
+                            let cell = ty * tw + tx;
+                            for (sum, &value) in sums[cell].iter_mut().zip(pixel) {
+                                *sum += value as u64;
+                            }
+                            counts[cell] += 1;
+                        }
+                    }
+                }
+            }
+        });
+
+        // Averaged premultiplied, so transparent pixels don't darken their neighbors
+        let mut pixels: Vec<u8> = sums
+            .iter()
+            .zip(&counts)
+            .flat_map(|(sum, &count)| sum.map(|sum| (sum / count.max(1)) as u8))
+            .collect();
+        if self.straight_output() {
+            alpha::unpremultiply(&mut pixels);
+        }
+        pixels
+    }
+}
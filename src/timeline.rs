@@ -0,0 +1,182 @@
+// Timelines (`scene` feature): a whole schedule (see schedule.rs) written as
+// a JSON array and queued in one go, for scripted show-reels:
+//
+//   [
+//     { "frame": 1, "action": "camera", "params": { "x": 400, "y": 300, "zoom": 2 } },
+//     { "frame": 30, "action": "spawn",
+//       "params": { "x": 400, "y": 100, "vx": 4, "radius": 30, "color": "#ff4444" } },
+//     { "frame": 90, "action": "explosion",
+//       "params": { "x": 400, "y": 500, "radius": 300, "strength": 12 } },
+//     { "frame": 120, "action": "gravity", "params": { "y": 0.3 } },
+//     { "frame": 150, "action": "wind", "params": { "fx": 0.2, "frames": 60 } },
+//     { "frame": 200, "action": "camera", "params": { "x": 400, "y": 300, "frames": 90 } },
+//     { "frame": 300, "action": "splitting", "params": { "enabled": false } },
+//     { "frame": 300, "action": "collisions", "params": { "enabled": true } }
+//   ]
+//
+// An explosion is a shockwave(). Omitted velocities, gravity and wind
+// components are 0, a camera's zoom is 1 and its frames 0 (a jump). Colors
+// are written like in scenes, and unknown keys are rejected the same way.
+
+use serde::Deserialize;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+use crate::scene_json::SceneColor;
+use crate::{Action, World, WorldError};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    frame: u32,
+    action: EntryKind,
+    #[serde(default = "no_params")]
+    params: Value,
+}
+
+fn no_params() -> Value {
+    Value::Object(Default::default())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EntryKind {
+    Spawn,
+    Explosion,
+    Gravity,
+    Wind,
+    Camera,
+    Splitting,
+    Collisions,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpawnParams {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    vx: f32,
+    #[serde(default)]
+    vy: f32,
+    radius: f32,
+    color: SceneColor,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExplosionParams {
+    x: f32,
+    y: f32,
+    radius: f32,
+    strength: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GravityParams {
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindParams {
+    #[serde(default)]
+    fx: f32,
+    #[serde(default)]
+    fy: f32,
+    frames: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraParams {
+    x: f32,
+    y: f32,
+    #[serde(default = "default_zoom")]
+    zoom: f32,
+    #[serde(default)]
+    frames: u32,
+}
+
+fn default_zoom() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SwitchParams {
+    enabled: bool,
+}
+
+impl Entry {
+    fn action(self) -> Result<Action, String> {
+        fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, String> {
+            serde_json::from_value(params).map_err(|error| error.to_string())
+        }
+        let action = match self.action {
+            EntryKind::Spawn => {
+                let p: SpawnParams = params(self.params)?;
+                Action::spawn(p.x, p.y, p.vx, p.vy, p.radius, p.color.rgb()?)
+            }
+            EntryKind::Explosion => {
+                let p: ExplosionParams = params(self.params)?;
+                Action::shockwave(p.x, p.y, p.radius, p.strength)
+            }
+            EntryKind::Gravity => {
+                let p: GravityParams = params(self.params)?;
+                Action::gravity(p.x, p.y)
+            }
+            EntryKind::Wind => {
+                let p: WindParams = params(self.params)?;
+                Action::wind(p.fx, p.fy, p.frames)
+            }
+            EntryKind::Camera => {
+                let p: CameraParams = params(self.params)?;
+                Action::camera(p.x, p.y, p.zoom, p.frames)
+            }
+            EntryKind::Splitting => {
+                let p: SwitchParams = params(self.params)?;
+                Action::splitting(p.enabled)
+            }
+            EntryKind::Collisions => {
+                let p: SwitchParams = params(self.params)?;
+                Action::collisions(p.enabled)
+            }
+        };
+        Ok(action)
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Queue every action of a JSON timeline (format at the top of
+    // timeline.rs) and return how many there were. Throws in JS if the JSON
+    // is malformed or an entry is for a frame already simulated; nothing is
+    // queued then.
+    pub fn load_timeline(&mut self, json: &str) -> Result<usize, WorldError> {
+        let entries: Vec<Entry> = serde_json::from_str(json)
+            .map_err(|error| WorldError::InvalidTimeline(error.to_string()))?;
+        let mut actions = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.into_iter().enumerate() {
+            let frame = entry.frame;
+            if frame <= self.frame {
+                return Err(WorldError::InvalidTimeline(format!(
+                    "entry {index} is for frame {frame}, but frame {} is already simulated",
+                    self.frame
+                )));
+            }
+            let action = entry.action().map_err(|reason| {
+                WorldError::InvalidTimeline(format!("entry {index}: {reason}"))
+            })?;
+            actions.push((frame, action));
+        }
+        for (frame, action) in &actions {
+            self.queue_action(*frame, action);
+        }
+        Ok(actions.len())
+    }
+}
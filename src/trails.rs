@@ -0,0 +1,177 @@
+// Trajectory history. With a trail length of N, every update() appends each
+// live ball's position to a ring of its last N positions. trail_points(id)
+// returns them oldest first, and with draw_trails on the renderer paints them
+// as a polyline in the ball's color that fades out towards the oldest point.
+//
+// A slot's trail is dropped when its ball is removed, and restarted when the
+// slot is reused (detected by the ball's born_frame).
+
+use wasm_bindgen::prelude::*;
+
+use crate::render::Clip;
+use crate::srgb::{self, Blending};
+use crate::{Ball, World};
+
+// Opacity of the newest segment; older ones fade linearly to 0
+const TRAIL_ALPHA: f32 = 0.6;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Trails {
+    length: usize, // 0 = off
+    draw: bool,
+    slots: Vec<Trail>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Trail {
+    born_frame: u32,
+    head: usize, // Where the next point goes once the ring is full
+    points: Vec<(f32, f32)>,
+}
+
+impl Trail {
+    fn oldest_first(&self) -> impl Iterator<Item = &(f32, f32)> {
+        let (newer, older) = self.points.split_at(self.head);
+        older.iter().chain(newer)
+    }
+}
+
+impl Trails {
+    pub(crate) fn drawn(&self) -> Option<&Trails> {
+        (self.length > 0 && self.draw).then_some(self)
+    }
+
+    pub(crate) fn memory_usage_bytes(&self) -> usize {
+        self.slots.capacity() * std::mem::size_of::<Trail>()
+            + self
+                .slots
+                .iter()
+                .map(|trail| trail.points.capacity() * std::mem::size_of::<(f32, f32)>())
+                .sum::<usize>()
+    }
+
+    pub(crate) fn get(&self, id: usize) -> Option<&Trail> {
+        self.slots.get(id).filter(|trail| !trail.points.is_empty())
+    }
+
+    // Append the current position of every live ball
+    pub(crate) fn record(&mut self, balls: &[Ball]) {
+        if self.length == 0 {
+            return;
+        }
+        self.slots.resize_with(balls.len(), Trail::default);
+        for (trail, ball) in self.slots.iter_mut().zip(balls) {
+            if ball.alive == 0 {
+                trail.points = Vec::new();
+                continue;
+            }
+            if trail.born_frame != ball.born_frame {
+                trail.points.clear();
+                trail.born_frame = ball.born_frame;
+            }
+            if trail.points.len() < self.length {
+                trail.points.push((ball.x, ball.y));
+                trail.head = 0;
+            } else {
+                trail.points[trail.head] = (ball.x, ball.y);
+                trail.head = (trail.head + 1) % self.length;
+            }
+        }
+    }
+}
+
+// Blend a trail's segments into the pixels inside `clip`. `buffer` starts at row `clip.y0`.
+pub(crate) fn fill_trail(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    trail: &Trail,
+    color: u32,
+    blending: Blending,
+) {
+    let rgb = srgb::rgb(color);
+    let segments = trail.points.len().saturating_sub(1);
+    let mut points = trail.oldest_first();
+    let Some(&(mut x0, mut y0)) = points.next() else {
+        return;
+    };
+    for (index, &(x1, y1)) in points.enumerate() {
+        let alpha = TRAIL_ALPHA * (index + 1) as f32 / segments as f32;
+        let segment = ((x0, y0), (x1, y1));
+        blend_line(buffer, stride, clip, segment, rgb, alpha, blending);
+        (x0, y0) = (x1, y1);
+    }
+}
+
+// Blend a one pixel line from (x0, y0) to (x1, y1) into the pixels inside
+// `clip`. `buffer` starts at row `clip.y0`.
+pub(crate) fn blend_line(
+    buffer: &mut [u8],
+    stride: usize,
+    clip: Clip,
+    ((x0, y0), (x1, y1)): ((f32, f32), (f32, f32)),
+    rgb: [u8; 3],
+    alpha: f32,
+    blending: Blending,
+) {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let steps = dx.abs().max(dy.abs()).ceil();
+    // Skip non-finite and absurdly long segments (a ball that wrapped or was moved)
+    if !(steps.is_finite() && steps < 4096.0) {
+        return;
+    }
+    let steps = steps.max(1.0) as usize;
+    for step in 0..steps {
+        let share = step as f32 / steps as f32;
+        let (px, py) = (x0 + dx * share, y0 + dy * share);
+        if px < clip.x0 as f32 || py < clip.y0 as f32 {
+            continue;
+        }
+        let (px, py) = (px as usize, py as usize);
+        if px >= clip.x1 || py >= clip.y1 {
+            continue;
+        }
+        let idx = (py - clip.y0) * stride + px * 4;
+        blending.blend(&mut buffer[idx..idx + 4], rgb, alpha);
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Keep the last `length` positions of every ball (0, the default, turns
+    // trails off and frees them). Changing the length restarts all trails.
+    pub fn set_trail_length(&mut self, length: u32) {
+        let length = length as usize;
+        if length != self.trails.length {
+            self.trails.length = length;
+            self.trails.slots = Vec::new();
+        }
+    }
+
+    pub fn trail_length(&self) -> u32 {
+        self.trails.length as u32
+    }
+
+    // Forget the recorded positions, e.g. after balls were moved by hand
+    pub fn clear_trails(&mut self) {
+        self.trails.slots = Vec::new();
+    }
+
+    // Recorded positions of ball `id` as x, y pairs, oldest first (empty for
+    // a free slot or with trails off)
+    pub fn trail_points(&self, id: u32) -> Vec<f32> {
+        match self.trails.get(id as usize) {
+            Some(trail) => trail.oldest_first().flat_map(|&(x, y)| [x, y]).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Paint the trails behind the balls (needs a trail length)
+    pub fn set_draw_trails(&mut self, enabled: bool) {
+        self.trails.draw = enabled;
+    }
+
+    pub fn draw_trails(&self) -> bool {
+        self.trails.draw
+    }
+}
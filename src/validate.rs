@@ -0,0 +1,212 @@
+// World health checks. validate() walks every slot and reports the first
+// broken invariant:
+//
+//   - no NaN or infinite position, velocity, radius, spin, temperature,
+//     restitution or friction
+//   - every live ball at least touches the arena (its center is within one
+//     radius of the bounds); sides with an open wall aren't checked
+//   - radii are positive
+//   - ids are unique: no slot is on the free list twice or while it's live
+//
+// Limits that only hold for new balls aren't checked: lowering max_balls
+// below live_count() or raising min_radius above existing children is
+// allowed. With the `debug` feature, update() asserts all of this after
+// every (sub-)step.
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{sim, World};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    NotFinite { id: u32 },
+    OutOfBounds { id: u32, x: f32, y: f32 },
+    InvalidRadius { id: u32, radius: f32 },
+    DuplicateId { id: u32 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NotFinite { id } => {
+                write!(f, "ball {id} has a NaN or infinite field")
+            }
+            ValidationError::OutOfBounds { id, x, y } => {
+                write!(f, "ball {id} at ({x}, {y}) is outside the arena")
+            }
+            ValidationError::InvalidRadius { id, radius } => {
+                write!(f, "ball {id} has a non-positive radius {radius}")
+            }
+            ValidationError::DuplicateId { id } => {
+                write!(
+                    f,
+                    "slot {id} is on the free list twice, while live or out of range"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for JsValue {
+    fn from(error: ValidationError) -> JsValue {
+        JsError::new(&error.to_string()).into()
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Check the invariants listed in validate.rs (throws the first broken
+    // one in JS)
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let closed = |wall: u32| self.open_walls & wall == 0;
+        for (id, ball) in self.live_balls() {
+            let id = id as u32;
+            let fields = [
+                ball.x,
+                ball.y,
+                ball.vx,
+                ball.vy,
+                ball.radius,
+                ball.spin,
+                ball.temperature,
+                ball.restitution,
+                ball.friction,
+            ];
+            if !fields.iter().all(|field| field.is_finite()) {
+                return Err(ValidationError::NotFinite { id });
+            }
+            if ball.radius <= 0.0 {
+                return Err(ValidationError::InvalidRadius {
+                    id,
+                    radius: ball.radius,
+                });
+            }
+            let outside = (closed(sim::WALL_LEFT) && ball.x < -ball.radius)
+                || (closed(sim::WALL_RIGHT) && ball.x > self.width + ball.radius)
+                || (closed(sim::WALL_TOP) && ball.y < -ball.radius)
+                || (closed(sim::WALL_BOTTOM) && ball.y > self.height + ball.radius);
+            if outside {
+                return Err(ValidationError::OutOfBounds {
+                    id,
+                    x: ball.x,
+                    y: ball.y,
+                });
+            }
+        }
+        let mut listed = vec![false; self.balls.len()];
+        for &id in &self.free {
+            let slot = id as usize;
+            let reused = match self.balls.get(slot) {
+                Some(ball) => ball.alive != 0 || listed[slot],
+                None => true,
+            };
+            if reused {
+                return Err(ValidationError::DuplicateId { id });
+            }
+            listed[slot] = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "debug")]
+impl World {
+    // Called after every (sub-)step
+    pub(crate) fn assert_valid(&self) {
+        if let Err(error) = self.validate() {
+            panic!(
+                "invariant broken in frame {}: {error}",
+                self.frame.wrapping_add(1)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationError;
+    use crate::{sim, Emitter, World};
+
+    fn world() -> World {
+        let mut world = World::new_empty_seeded(200.0, 150.0, 4, 0.7, 1).unwrap();
+        world.add_ball(50.0, 50.0, 2.0, 1.0, 10.0, 0xFF0000);
+        world
+    }
+
+    // Settings changes and undo that leave the world past its limits are valid use
+    #[test]
+    fn limits_for_new_balls_are_not_invariants() {
+        let mut world = world();
+        world.set_undo_limit(8);
+        while world.add_ball(100.0, 75.0, 0.0, 0.0, 4.0, 0).is_some() {}
+        world.set_max_balls(1).unwrap();
+        world.set_min_radius(50.0).unwrap();
+        assert_eq!(world.validate(), Ok(()));
+
+        // Another ball takes the removed one's place before the undo
+        world.set_max_balls(4).unwrap();
+        let (id, _) = world.live_balls().next().unwrap();
+        world.remove_ball(id as u32);
+        world.add_emitter(&Emitter::new(100.0, 75.0, 0.0, 0.0, 4.0, 1.0));
+        world.update();
+        world.clear_emitters();
+        world.undo();
+        assert_eq!(world.validate(), Ok(()));
+        for _ in 0..50 {
+            world.update();
+            assert_eq!(world.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn broken_balls_are_reported() {
+        let mut world = world();
+        world.balls[0].vx = f32::NAN;
+        assert_eq!(world.validate(), Err(ValidationError::NotFinite { id: 0 }));
+
+        let mut world = self::world();
+        world.balls[0].radius = -1.0;
+        assert_eq!(
+            world.validate(),
+            Err(ValidationError::InvalidRadius {
+                id: 0,
+                radius: -1.0
+            })
+        );
+
+        let mut world = self::world();
+        world.balls[0].x = -30.0;
+        assert_eq!(
+            world.validate(),
+            Err(ValidationError::OutOfBounds {
+                id: 0,
+                x: -30.0,
+                y: 50.0
+            })
+        );
+        // Through an open wall is fine
+        world.set_open_walls(sim::WALL_LEFT);
+        assert_eq!(world.validate(), Ok(()));
+    }
+
+    #[test]
+    fn reused_ids_are_reported() {
+        let mut world = world();
+        world.free.push(0);
+        assert_eq!(
+            world.validate(),
+            Err(ValidationError::DuplicateId { id: 0 })
+        );
+
+        let mut world = self::world();
+        world.free.push(7);
+        assert_eq!(
+            world.validate(),
+            Err(ValidationError::DuplicateId { id: 7 })
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// Typed-array access to the ball slots without hand-built views over
+// WebAssembly.memory.
+//
+// A view aliases WASM memory and is detached as soon as that memory grows
+// (e.g. when a split or add_ball reallocates the slot array), so never keep a
+// view across update() or any call that adds balls: call these methods again
+// instead, every call builds a fresh view of the current allocation. Pass
+// `copy = true` to get an independent JS-owned copy that stays valid forever
+// (and can be transferred to a worker).
+//
+// Each slot is ball_stride_words() 32-bit words laid out like `Ball`:
+// x, y, vx, vy, radius, color, just_split, tag, layer (low byte), alive,
+// born_frame, generation, spin, temperature, restitution, friction.
+//
+// For the occasional single ball, `ball(id)` returns a BallView instead: a
+// copy of the ball taken at call time, read through plain getters.
+// `copy_balls_into` is the explicit-copy alternative to the views: it fills a
+// caller-owned Float32Array and never aliases WASM memory.
+
+use js_sys::{Float32Array, Uint32Array};
+use wasm_bindgen::prelude::*;
+
+use crate::{Ball, World};
+
+const _: () = assert!(std::mem::size_of::<Ball>().is_multiple_of(4));
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct BallView {
+    id: u32,
+    ball: Ball,
+    frame: u32, // World frame when the view was taken
+}
+
+#[wasm_bindgen]
+impl BallView {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn x(&self) -> f32 {
+        self.ball.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.ball.y
+    }
+
+    pub fn vx(&self) -> f32 {
+        self.ball.vx
+    }
+
+    pub fn vy(&self) -> f32 {
+        self.ball.vy
+    }
+
+    pub fn speed(&self) -> f32 {
+        (self.ball.vx * self.ball.vx + self.ball.vy * self.ball.vy).sqrt()
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.ball.radius
+    }
+
+    // 0xRRGGBB
+    pub fn color(&self) -> u32 {
+        self.ball.color
+    }
+
+    // CSS color string, e.g. "#ff4444"
+    pub fn color_hex(&self) -> String {
+        format!("#{:06x}", self.ball.color & 0xFFFFFF)
+    }
+
+    pub fn tag(&self) -> u32 {
+        self.ball.tag
+    }
+
+    pub fn layer(&self) -> u8 {
+        self.ball.layer
+    }
+
+    pub fn just_split(&self) -> bool {
+        self.ball.just_split != 0
+    }
+
+    // Frames since the ball appeared (a split child starts at 0)
+    pub fn age_frames(&self) -> u32 {
+        self.frame.wrapping_sub(self.ball.born_frame)
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.ball.generation
+    }
+
+    // Radians per frame, positive = clockwise
+    pub fn spin(&self) -> f32 {
+        self.ball.spin
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.ball.temperature
+    }
+
+    pub fn restitution(&self) -> f32 {
+        self.ball.restitution
+    }
+
+    pub fn friction(&self) -> f32 {
+        self.ball.friction
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Copy of a live ball, or None (undefined in JS) for a free or unknown id
+    pub fn ball(&self, id: u32) -> Option<BallView> {
+        self.balls
+            .get(id as usize)
+            .filter(|ball| ball.alive != 0)
+            .map(|&ball| BallView {
+                id,
+                ball,
+                frame: self.frame,
+            })
+    }
+
+    // Write x, y, radius and the color's bits (reinterpreted as f32, read them
+    // back through a Uint32Array over the same buffer) for each live ball in id
+    // order. Stops at the last ball that fits whole; returns how many were written.
+    pub fn copy_balls_into(&self, dest: &mut [f32]) -> usize {
+        let mut written = 0;
+        for ((_, ball), out) in self.live_balls().zip(dest.chunks_exact_mut(4)) {
+            out.copy_from_slice(&[ball.x, ball.y, ball.radius, f32::from_bits(ball.color)]);
+            written += 1;
+        }
+        written
+    }
+
+    pub fn ball_stride_words(&self) -> usize {
+        std::mem::size_of::<Ball>() / 4
+    }
+
+    // All slots as f32 words (float fields read directly; use balls_u32 for the integer ones)
+    pub fn balls_f32(&mut self, copy: bool) -> Float32Array {
+        let len = self.balls.len() * self.ball_stride_words();
+        // Raw view: padding bytes are never read on the Rust side
+        let view = unsafe { Float32Array::view_mut_raw(self.balls.as_mut_ptr() as *mut f32, len) };
+        if copy {
+            view.slice(0, len as u32)
+        } else {
+            view
+        }
+    }
+
+    // All slots as u32 words
+    pub fn balls_u32(&mut self, copy: bool) -> Uint32Array {
+        let len = self.balls.len() * self.ball_stride_words();
+        let view = unsafe { Uint32Array::view_mut_raw(self.balls.as_mut_ptr() as *mut u32, len) };
+        if copy {
+            view.slice(0, len as u32)
+        } else {
+            view
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Voronoi shading: with an opacity set, every background pixel is tinted
+// with the color of the ball whose center is nearest, which tiles the arena
+// into the balls' regions; the walls, obstacles and balls are drawn on top
+// as usual. Nearest balls come from the collision broadphase grid, with
+// cells sized to hold about one ball each, searched ring by ring outwards.
+
+use wasm_bindgen::prelude::*;
+
+use crate::collision::Grid;
+use crate::render::{Clip, ColorMode};
+use crate::srgb::{self, Blending};
+use crate::{Ball, World};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Voronoi {
+    opacity: f32, // 0 = off
+}
+
+impl Voronoi {
+    // The grid for this frame's regions, or None when off or there are no balls
+    pub(crate) fn grid(&self, balls: &[Ball], width: f32, height: f32) -> Option<Grid> {
+        if self.opacity <= 0.0 {
+            return None;
+        }
+        let live = balls.iter().filter(|ball| ball.alive != 0).count();
+        if live == 0 {
+            return None;
+        }
+        let cell = (width * height / live as f32).sqrt();
+        Some(Grid::new(balls, cell))
+    }
+
+    // Tint the band with the regions `grid` (from grid()) finds among
+    // `balls`. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(
+        &self,
+        buffer: &mut [u8],
+        stride: usize,
+        clip: Clip,
+        (grid, balls): (&Grid, &[Ball]),
+        color_mode: ColorMode,
+        blending: Blending,
+    ) {
+        for py in clip.y0..clip.y1 {
+            let row = (py - clip.y0) * stride;
+            for px in clip.x0..clip.x1 {
+                let Some(id) = grid.nearest(balls, px as f32, py as f32) else {
+                    continue;
+                };
+                let rgb = srgb::rgb(color_mode.fill(&balls[id]));
+                let idx = row + px * 4;
+                blending.blend(&mut buffer[idx..idx + 4], rgb, self.opacity);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Tint the background with each ball's region, `opacity` (0..=1) of the
+    // way to its color; 0, the default, turns it off
+    pub fn set_voronoi_shading(&mut self, opacity: f32) {
+        if opacity.is_finite() {
+            self.voronoi.opacity = opacity.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn voronoi_shading(&self) -> f32 {
+        self.voronoi.opacity
+    }
+}
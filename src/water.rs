@@ -0,0 +1,143 @@
+// Water. With a density set, everything below the water line (y = level,
+// y growing downwards) is a second, denser medium. A ball there is lifted by
+// buoyancy and slowed by drag, both in proportion to the share of its area
+// under the surface:
+//
+//   - buoyancy pushes up with density times the strength of y gravity (or
+//     DEFAULT_TILT_GRAVITY while gravity is off or points up), so at density
+//     1 a fully submerged ball just floats and at 2 it bobs up half out
+//   - drag removes WATER_DRAG * density of the velocity per frame
+//
+// A ball whose center crosses the line emits a Splash event. The energy
+// drag takes is reported as drag_removed; buoyancy's work isn't itemized.
+// Like attractors, this kicks velocities once per (sub-)step, before the
+// balls move. Below the line the arena is drawn tinted over the balls.
+
+use wasm_bindgen::prelude::*;
+
+use crate::dynamics::DEFAULT_TILT_GRAVITY;
+use crate::events::{self, Event, EventKind};
+use crate::lockstep::{Fnv1a, StateHash};
+use crate::render::Clip;
+use crate::srgb::Blending;
+use crate::{energy, Ball, World};
+
+// Share of the velocity drag removes per frame at density 1, fully submerged
+const WATER_DRAG: f32 = 0.05;
+const WATER_COLOR: [u8; 3] = [40, 110, 200];
+const WATER_ALPHA: f32 = 80.0 / 255.0;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Water {
+    level: f32,
+    density: f32, // 0 = no water
+}
+
+impl StateHash for Water {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&(self.level, self.density));
+    }
+}
+
+impl Water {
+    // Tint the band below the line. `buffer` starts at row `clip.y0`.
+    pub(crate) fn fill(&self, buffer: &mut [u8], stride: usize, clip: Clip, blending: Blending) {
+        if self.density <= 0.0 {
+            return;
+        }
+        // Rows whose pixel centers are under the surface
+        let first = (self.level - 0.5).ceil().max(clip.y0 as f32) as usize;
+        for py in first..clip.y1 {
+            let row = (py - clip.y0) * stride;
+            for pixel in buffer[row + clip.x0 * 4..row + clip.x1 * 4].chunks_exact_mut(4) {
+                blending.blend(pixel, WATER_COLOR, WATER_ALPHA);
+            }
+        }
+    }
+}
+
+// Share of the ball's area below the surface: a circular segment
+fn submerged(ball: &Ball, level: f32) -> f32 {
+    let r = ball.radius;
+    let depth = ball.y + r - level;
+    if depth <= 0.0 {
+        return 0.0;
+    }
+    if depth >= 2.0 * r {
+        return 1.0;
+    }
+    // Distance from the center up to the surface (negative: center under water)
+    let d = r - depth;
+    let segment = r * r * (d / r).acos() - d * (r * r - d * d).sqrt();
+    segment / (std::f32::consts::PI * r * r)
+}
+
+impl World {
+    // Buoyancy, drag and splashes for one (sub-)step
+    pub(crate) fn immerse(&mut self, stamp: u32) {
+        let Water { level, density } = self.water;
+        if density <= 0.0 {
+            return;
+        }
+        let dt = 1.0 / self.substeps as f32;
+        let gravity = if self.gravity.1 > 0.0 {
+            self.gravity.1
+        } else {
+            DEFAULT_TILT_GRAVITY
+        };
+        let balls = self.balls.iter_mut().zip(self.modified.iter_mut());
+        for (id, (ball, modified)) in balls.enumerate() {
+            if ball.alive == 0 {
+                continue;
+            }
+            // The center went through the surface during the last step
+            let before = ball.y - ball.vy * dt;
+            if (before > level) != (ball.y > level) {
+                events::push_event(
+                    &mut self.events,
+                    Event {
+                        kind: EventKind::Splash,
+                        id: id as u32,
+                        frame: stamp,
+                        x: ball.x,
+                        y: level,
+                        value: ball.vy,
+                    },
+                );
+            }
+            let share = submerged(ball, level);
+            if share <= 0.0 {
+                continue;
+            }
+            let kinetic = energy::kinetic(ball);
+            let keep = 1.0 - (WATER_DRAG * density * share * dt).min(1.0);
+            ball.vx *= keep;
+            ball.vy *= keep;
+            self.energy.record_drag(kinetic - energy::kinetic(ball));
+            ball.vy -= density * share * gravity * dt;
+            *modified = stamp;
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl World {
+    // Fill the arena below `y` with water `density` times as dense as the
+    // balls (0, the default, drains it)
+    pub fn set_water_level(&mut self, y: f32, density: f32) {
+        if y.is_finite() && density.is_finite() {
+            self.water = Water {
+                level: y,
+                density: density.max(0.0),
+            };
+        }
+    }
+
+    pub fn water_level(&self) -> f32 {
+        self.water.level
+    }
+
+    pub fn water_density(&self) -> f32 {
+        self.water.density
+    }
+}
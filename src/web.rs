@@ -0,0 +1,207 @@
+// Direct canvas output (`web` feature). attach_canvas() takes an
+// OffscreenCanvas (e.g. one transferred to a worker, or `new OffscreenCanvas`
+// on the main thread) and present() renders the current frame into it, so a
+// host needs no ImageData or putImageData code of its own:
+//
+//   world.attach_canvas(canvas.transferControlToOffscreen());
+//   function tick() { world.update(); world.present(); requestAnimationFrame(tick); }
+//
+// Or, on the main thread, let the crate own the loop entirely:
+//
+//   const running = world.run("canvas-id");   // takes the world
+//   const world = running.stop();             // ... and hands it back
+//
+// The frame is drawn at the canvas's size at present() time; resizing the
+// canvas just reallocates the pixel buffer. A cloned World presents to the
+// same canvas.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{
+    console, CanvasRenderingContext2d, HtmlCanvasElement, ImageData, OffscreenCanvas,
+    OffscreenCanvasRenderingContext2d,
+};
+
+use crate::World;
+
+#[derive(Clone, Debug)]
+enum Target {
+    Offscreen(OffscreenCanvas, OffscreenCanvasRenderingContext2d),
+    Element(HtmlCanvasElement, CanvasRenderingContext2d),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Canvas {
+    target: Target,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn size(&self) -> (u32, u32) {
+        match &self.target {
+            Target::Offscreen(canvas, _) => (canvas.width(), canvas.height()),
+            Target::Element(canvas, _) => (canvas.width(), canvas.height()),
+        }
+    }
+
+    fn put(&self, image: &ImageData) -> Result<(), JsValue> {
+        match &self.target {
+            Target::Offscreen(_, context) => context.put_image_data(image, 0.0, 0.0),
+            Target::Element(_, context) => context.put_image_data(image, 0.0, 0.0),
+        }
+    }
+}
+
+fn context_2d(context: Option<js_sys::Object>) -> Result<js_sys::Object, JsValue> {
+    context.ok_or_else(|| JsError::new("canvas has no 2d context").into())
+}
+
+#[wasm_bindgen]
+impl World {
+    // Throws if the canvas can't give a 2d context (e.g. it already has a webgl one)
+    pub fn attach_canvas(&mut self, canvas: OffscreenCanvas) -> Result<(), JsValue> {
+        let context = context_2d(canvas.get_context("2d")?)?.dyn_into()?;
+        self.canvas = Some(Canvas {
+            target: Target::Offscreen(canvas, context),
+            pixels: Vec::new(),
+        });
+        Ok(())
+    }
+
+    pub fn detach_canvas(&mut self) {
+        self.canvas = None;
+    }
+
+    pub fn has_canvas(&self) -> bool {
+        self.canvas.is_some()
+    }
+
+    // Render the current frame into the attached canvas. Throws without one.
+    pub fn present(&mut self) -> Result<(), JsValue> {
+        let Some(mut target) = self.canvas.take() else {
+            return Err(JsError::new("no canvas attached").into());
+        };
+        let result = self.present_into(&mut target);
+        self.canvas = Some(target);
+        result
+    }
+
+    // Take over the <canvas id=canvas_id> element and call update() and
+    // present() from requestAnimationFrame until the returned loop is
+    // stopped. Throws (and drops the world) if there is no such canvas.
+    pub fn run(mut self, canvas_id: &str) -> Result<RunLoop, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsError::new("run() needs a window"))?;
+        let canvas = window
+            .document()
+            .and_then(|document| document.get_element_by_id(canvas_id))
+            .ok_or_else(|| JsError::new(&format!("no element with id {canvas_id:?}")))?
+            .dyn_into::<HtmlCanvasElement>()
+            .map_err(|_| JsError::new(&format!("#{canvas_id} is not a canvas")))?;
+        let context = context_2d(canvas.get_context("2d")?)?.dyn_into()?;
+        self.canvas = Some(Canvas {
+            target: Target::Element(canvas, context),
+            pixels: Vec::new(),
+        });
+
+        let inner = Rc::new(RefCell::new(Running {
+            world: self,
+            request: None,
+            tick: None,
+        }));
+        let weak = Rc::downgrade(&inner);
+        inner.borrow_mut().tick = Some(Closure::new(move || tick(&weak)));
+        request_frame(&inner)?;
+        Ok(RunLoop { inner })
+    }
+}
+
+impl World {
+    fn present_into(&self, target: &mut Canvas) -> Result<(), JsValue> {
+        let (width, height) = target.size();
+        target
+            .pixels
+            .resize(width as usize * height as usize * 4, 0);
+        self.render_to_buffer(&mut target.pixels, width as usize, height as usize, None)?;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let image =
+            ImageData::new_with_u8_clamped_array_and_sh(Clamped(&target.pixels), width, height)?;
+        target.put(&image)
+    }
+}
+
+struct Running {
+    world: World,
+    request: Option<i32>, // Pending requestAnimationFrame id
+    tick: Option<Closure<dyn FnMut()>>,
+}
+
+// A World driven by World::run
+#[wasm_bindgen]
+pub struct RunLoop {
+    inner: Rc<RefCell<Running>>,
+}
+
+#[wasm_bindgen]
+impl RunLoop {
+    pub fn is_running(&self) -> bool {
+        self.inner.borrow().request.is_some()
+    }
+
+    // Frames simulated so far
+    pub fn frame(&self) -> u32 {
+        self.inner.borrow().world.frame
+    }
+
+    // Cancel the loop and hand the world back (its canvas stays attached)
+    pub fn stop(self) -> World {
+        cancel(&self.inner);
+        let running = self.inner.borrow();
+        running.world.clone()
+    }
+}
+
+impl Drop for RunLoop {
+    fn drop(&mut self) {
+        cancel(&self.inner);
+    }
+}
+
+fn request_frame(inner: &Rc<RefCell<Running>>) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsError::new("run() needs a window"))?;
+    let mut running = inner.borrow_mut();
+    let Some(tick) = &running.tick else {
+        return Ok(());
+    };
+    running.request = Some(window.request_animation_frame(tick.as_ref().unchecked_ref())?);
+    Ok(())
+}
+
+fn tick(weak: &Weak<RefCell<Running>>) {
+    let Some(inner) = weak.upgrade() else {
+        return;
+    };
+    let result = {
+        let mut running = inner.borrow_mut();
+        running.request = None;
+        running.world.update();
+        running.world.present()
+    };
+    // On failure the loop just isn't rescheduled: dropping the closure here
+    // would free it while it runs
+    if let Err(error) = result.and_then(|()| request_frame(&inner)) {
+        console::error_1(&error);
+    }
+}
+
+fn cancel(inner: &Rc<RefCell<Running>>) {
+    let mut running = inner.borrow_mut();
+    if let (Some(request), Some(window)) = (running.request.take(), web_sys::window()) {
+        let _ = window.cancel_animation_frame(request);
+    }
+    running.tick = None;
+}
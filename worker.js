@@ -0,0 +1,28 @@
+// Dedicated worker bootstrap for the `worker` feature (WorldDriver).
+// Build with: wasm-pack build --target web -- --features worker
+//
+// Main thread usage:
+//   const worker = new Worker('./worker.js', { type: 'module' });
+//   worker.postMessage({ type: 'init', width: canvas.width, height: canvas.height,
+//                        maxBalls: 1000000, splitRatio: 0.8, fps: 60 });
+//   worker.onmessage = ({ data }) => {
+//       if (data.type === 'frame') {
+//           ctx.putImageData(new ImageData(data.pixels, data.width, data.height), 0, 0);
+//       }
+//   };
+//   worker.postMessage({ type: 'stop' });
+import init, { World, WorldDriver } from './pkg/bouncing_balls.js';
+
+let driver = null;
+
+self.onmessage = async ({ data }) => {
+    if (data.type !== 'init' || driver) {
+        return;
+    }
+    await init();
+    const world = World.new(data.width, data.height, data.maxBalls, data.splitRatio);
+    driver = new WorldDriver(world, data.width, data.height);
+    // From now on the driver handles start/stop/resize/set messages itself
+    driver.listen();
+    driver.start(data.fps ?? 60);
+};